@@ -0,0 +1,116 @@
+-- 001_initial.sql
+--
+-- Roll calls, their totals, documents and amendments, members and ballots.
+
+-- One row per roll call, both chambers.
+-- Senate-only and House-only columns are left NULL for the other chamber.
+CREATE TABLE IF NOT EXISTS votes (
+    vote_hash            TEXT PRIMARY KEY NOT NULL,
+    chamber              TEXT NOT NULL CHECK (chamber IN ('house', 'senate')),
+    congress             INTEGER NOT NULL,
+    session              INTEGER NOT NULL,
+    rollcall             INTEGER NOT NULL,
+    year                 INTEGER NOT NULL,
+    vote_date            TEXT,
+    modify_date          TEXT,
+    question             TEXT,
+    question_text        TEXT,
+    document_text        TEXT,
+    result               TEXT,
+    title                TEXT,
+    majority_requirement TEXT,
+    majority             TEXT,
+    committee            TEXT,
+    legis_num            TEXT,
+    vote_type            TEXT,
+    action_time          TEXT,
+    action_time_etz      TEXT,
+    vote_desc            TEXT,
+    UNIQUE (chamber, congress, session, rollcall)
+);
+
+CREATE INDEX IF NOT EXISTS idx_votes_congress ON votes (chamber, congress, session);
+CREATE INDEX IF NOT EXISTS idx_votes_year ON votes (year);
+
+-- Chamber-wide totals. Senate "absent" is stored as `not_voting`.
+CREATE TABLE IF NOT EXISTS vote_counts (
+    vote_hash          TEXT PRIMARY KEY NOT NULL REFERENCES votes (vote_hash) ON DELETE CASCADE,
+    yea                INTEGER NOT NULL DEFAULT 0,
+    nay                INTEGER NOT NULL DEFAULT 0,
+    present            INTEGER NOT NULL DEFAULT 0,
+    not_voting         INTEGER NOT NULL DEFAULT 0,
+    tie_breaker_by     TEXT,
+    tie_breaker_vote   TEXT
+);
+
+-- Per-party totals (House `totals-by-party`).
+CREATE TABLE IF NOT EXISTS party_totals (
+    vote_hash  TEXT NOT NULL REFERENCES votes (vote_hash) ON DELETE CASCADE,
+    party      TEXT NOT NULL,
+    yea        INTEGER NOT NULL DEFAULT 0,
+    nay        INTEGER NOT NULL DEFAULT 0,
+    present    INTEGER NOT NULL DEFAULT 0,
+    not_voting INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (vote_hash, party)
+);
+
+-- Documents a Senate vote was taken on, in file order.
+CREATE TABLE IF NOT EXISTS documents (
+    vote_hash            TEXT NOT NULL REFERENCES votes (vote_hash) ON DELETE CASCADE,
+    position             INTEGER NOT NULL,
+    document_congress    TEXT,
+    document_type        TEXT,
+    document_number      TEXT,
+    document_name        TEXT,
+    document_title       TEXT,
+    document_short_title TEXT,
+    PRIMARY KEY (vote_hash, position)
+);
+
+CREATE INDEX IF NOT EXISTS idx_documents_name ON documents (document_name);
+
+-- Amendments a Senate vote was taken on, in file order.
+CREATE TABLE IF NOT EXISTS amendments (
+    vote_hash                                   TEXT NOT NULL REFERENCES votes (vote_hash) ON DELETE CASCADE,
+    position                                    INTEGER NOT NULL,
+    amendment_number                            TEXT,
+    amendment_to_amendment_number               TEXT,
+    amendment_to_amendment_to_amendment_number  TEXT,
+    amendment_to_document_number                TEXT,
+    amendment_to_document_short_title           TEXT,
+    amendment_purpose                           TEXT,
+    PRIMARY KEY (vote_hash, position)
+);
+
+CREATE INDEX IF NOT EXISTS idx_amendments_number ON amendments (amendment_number);
+
+-- Legislators seen on any ballot. `member_id` is the House bioguide
+-- `name-id` or the generated id of a Senate member.
+CREATE TABLE IF NOT EXISTS members (
+    member_id     TEXT PRIMARY KEY NOT NULL,
+    chamber       TEXT NOT NULL CHECK (chamber IN ('house', 'senate')),
+    bioguide_id   TEXT,
+    lis_member_id TEXT,
+    full_name     TEXT,
+    first_name    TEXT,
+    last_name     TEXT,
+    party         TEXT,
+    state         TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_members_bioguide ON members (bioguide_id);
+CREATE INDEX IF NOT EXISTS idx_members_lis ON members (lis_member_id);
+
+-- One ballot per member per roll call. Party and state are recorded as
+-- they were on the day of the vote.
+CREATE TABLE IF NOT EXISTS member_votes (
+    vote_hash   TEXT NOT NULL REFERENCES votes (vote_hash) ON DELETE CASCADE,
+    member_id   TEXT NOT NULL REFERENCES members (member_id),
+    vote_cast   TEXT,
+    paired_with TEXT,
+    party       TEXT,
+    state       TEXT,
+    PRIMARY KEY (vote_hash, member_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_member_votes_member ON member_votes (member_id);
@@ -0,0 +1,11 @@
+-- 002_candidate_totals.sql
+--
+-- Per-candidate totals of House Speaker elections.
+
+-- Votes per candidate (House Speaker elections, `totals-by-candidate`).
+CREATE TABLE IF NOT EXISTS candidate_totals (
+    vote_hash TEXT NOT NULL REFERENCES votes (vote_hash) ON DELETE CASCADE,
+    candidate TEXT NOT NULL,
+    total     INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (vote_hash, candidate)
+);
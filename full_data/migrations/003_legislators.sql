@@ -0,0 +1,64 @@
+-- 003_legislators.sql
+--
+-- The legislator registry, and Senate members keyed on their LIS id instead
+-- of a hash of last name, party and state. Existing rows and ballots move to
+-- the new id; senators whose hashes collided need their roll calls
+-- re-ingested to be split.
+
+-- Legislator registry, imported from a congress-legislators crosswalk.
+-- One row per person; `bioguide_id` matches House members and `lis_id`
+-- matches Senate members, so both careers link to the same row.
+CREATE TABLE IF NOT EXISTS legislators (
+    bioguide_id   TEXT PRIMARY KEY NOT NULL,
+    lis_id        TEXT UNIQUE,
+    thomas_id     TEXT,
+    govtrack_id   INTEGER,
+    icpsr_id      INTEGER,
+    first_name    TEXT,
+    last_name     TEXT,
+    official_full TEXT,
+    birthday      TEXT,
+    gender        TEXT
+);
+
+-- Terms in office, in crosswalk order.
+CREATE TABLE IF NOT EXISTS legislator_terms (
+    bioguide_id TEXT NOT NULL REFERENCES legislators (bioguide_id) ON DELETE CASCADE,
+    position    INTEGER NOT NULL,
+    chamber     TEXT NOT NULL CHECK (chamber IN ('house', 'senate')),
+    start_date  TEXT NOT NULL,
+    end_date    TEXT NOT NULL,
+    state       TEXT NOT NULL,
+    district    INTEGER,
+    party       TEXT,
+    PRIMARY KEY (bioguide_id, position)
+);
+
+-- The registry entry of each member, by bioguide id in the House and LIS id in the Senate.
+CREATE VIEW IF NOT EXISTS member_legislators AS
+SELECT m.member_id, m.chamber, l.bioguide_id
+FROM members m
+JOIN legislators l
+  ON (m.chamber = 'house' AND l.bioguide_id = m.bioguide_id)
+  OR (m.chamber = 'senate' AND l.lis_id = m.lis_member_id);
+
+-- Every ballot of a legislator, across both chambers.
+CREATE VIEW IF NOT EXISTS legislator_votes AS
+SELECT ml.bioguide_id, v.chamber, v.congress, v.session, v.rollcall, v.vote_date,
+       mv.vote_hash, mv.member_id, mv.vote_cast, mv.party, mv.state
+FROM member_votes mv
+JOIN member_legislators ml ON ml.member_id = mv.member_id
+JOIN votes v ON v.vote_hash = mv.vote_hash;
+
+-- Senate members keyed on a hash move to their LIS id.
+CREATE TEMP TABLE senate_ids AS
+    SELECT member_id AS old_id, lis_member_id AS new_id FROM members
+    WHERE chamber = 'senate' AND lis_member_id IS NOT NULL AND member_id <> lis_member_id;
+INSERT OR IGNORE INTO members (member_id, chamber, bioguide_id, lis_member_id, full_name, first_name, last_name, party, state)
+    SELECT s.new_id, m.chamber, m.bioguide_id, m.lis_member_id, m.full_name, m.first_name, m.last_name, m.party, m.state
+    FROM members m JOIN senate_ids s ON s.old_id = m.member_id;
+UPDATE OR REPLACE member_votes
+    SET member_id = (SELECT new_id FROM senate_ids WHERE old_id = member_votes.member_id)
+    WHERE member_id IN (SELECT old_id FROM senate_ids);
+DELETE FROM members WHERE member_id IN (SELECT old_id FROM senate_ids);
+DROP TABLE senate_ids;
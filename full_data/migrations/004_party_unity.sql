@@ -0,0 +1,49 @@
+-- 004_party_unity.sql
+--
+-- Party-unity votes, scores and defections written by `analyze party_unity`.
+
+-- Party-unity votes: roll calls on which a majority of voting Democrats
+-- opposed a majority of voting Republicans. Positions are +1 yea, -1 nay.
+-- The analytics tables are rebuilt per chamber and congress by `analyze`.
+CREATE TABLE IF NOT EXISTS party_unity_votes (
+    chamber             TEXT NOT NULL CHECK (chamber IN ('house', 'senate')),
+    congress            INTEGER NOT NULL,
+    session             INTEGER NOT NULL,
+    rollcall            INTEGER NOT NULL,
+    democrat_yea        INTEGER NOT NULL,
+    democrat_nay        INTEGER NOT NULL,
+    republican_yea      INTEGER NOT NULL,
+    republican_nay      INTEGER NOT NULL,
+    democrat_position   INTEGER NOT NULL,
+    republican_position INTEGER NOT NULL,
+    PRIMARY KEY (chamber, congress, session, rollcall)
+);
+
+-- Party-unity scores per member and session; session 0 is the whole congress.
+CREATE TABLE IF NOT EXISTS party_unity_scores (
+    chamber       TEXT NOT NULL CHECK (chamber IN ('house', 'senate')),
+    congress      INTEGER NOT NULL,
+    session       INTEGER NOT NULL,
+    member_id     TEXT NOT NULL,
+    party         TEXT NOT NULL,
+    name          TEXT,
+    with_party    INTEGER NOT NULL,
+    against_party INTEGER NOT NULL,
+    score         REAL NOT NULL,
+    PRIMARY KEY (chamber, congress, session, member_id, party)
+);
+
+-- Ballots cast against the member's party majority on a party-unity vote.
+CREATE TABLE IF NOT EXISTS party_defections (
+    chamber        TEXT NOT NULL CHECK (chamber IN ('house', 'senate')),
+    congress       INTEGER NOT NULL,
+    session        INTEGER NOT NULL,
+    rollcall       INTEGER NOT NULL,
+    member_id      TEXT NOT NULL,
+    party          TEXT NOT NULL,
+    vote_cast      TEXT NOT NULL,
+    party_position INTEGER NOT NULL,
+    PRIMARY KEY (chamber, congress, session, rollcall, member_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_party_defections_member ON party_defections (member_id);
@@ -0,0 +1,22 @@
+-- 005_ideal_points.sql
+--
+-- Member ideal points written by `ideal_points`.
+
+-- Ideal points per member and congress, in one or two dimensions. coord2 and se2
+-- are NULL for one-dimensional fits; classified is the share of votes predicted correctly.
+CREATE TABLE IF NOT EXISTS member_ideal_points (
+    chamber    TEXT NOT NULL CHECK (chamber IN ('house', 'senate')),
+    congress   INTEGER NOT NULL,
+    member_id  TEXT NOT NULL,
+    dimensions INTEGER NOT NULL CHECK (dimensions IN (1, 2)),
+    name       TEXT,
+    party      TEXT,
+    state      TEXT,
+    votes      INTEGER NOT NULL,
+    coord1     REAL NOT NULL,
+    coord2     REAL,
+    se1        REAL NOT NULL,
+    se2        REAL,
+    classified REAL NOT NULL,
+    PRIMARY KEY (chamber, congress, member_id, dimensions)
+);
@@ -0,0 +1,6 @@
+-- 006_vote_times.sql
+--
+-- Parsed vote days and Eastern-time timestamps. The columns are added before
+-- this file runs, if missing, and filled in from the raw dates after it.
+
+CREATE INDEX IF NOT EXISTS idx_votes_day ON votes (vote_day, vote_epoch);
@@ -0,0 +1,25 @@
+-- 007_search.sql
+--
+-- Full-text search over roll call text. Votes already in the database are
+-- indexed after this file runs.
+
+-- Full-text index of each roll call's text, one row per vote. Rows are written
+-- with the vote (`search::index_vote`) and removed with it.
+--   title:      House vote_desc, Senate vote title
+--   question:   question and Senate question_text
+--   bill:       House legis_num, Senate document names
+--   documents:  Senate document_text and document titles
+--   amendments: Senate amendment purposes
+CREATE VIRTUAL TABLE IF NOT EXISTS vote_search USING fts5(
+    vote_hash UNINDEXED,
+    title,
+    question,
+    bill,
+    documents,
+    amendments,
+    tokenize = 'porter unicode61'
+);
+
+CREATE TRIGGER IF NOT EXISTS votes_search_delete AFTER DELETE ON votes BEGIN
+    DELETE FROM vote_search WHERE vote_hash = old.vote_hash;
+END;
@@ -0,0 +1,458 @@
+// analytics.rs
+//
+// Party unity. A party-unity vote is a roll call on which a majority of voting
+// Democrats opposed a majority of voting Republicans. A member's party-unity
+// score is the share of those votes on which they sided with their own party's
+// majority; every vote against it is a defection. Only yea/nay positions count,
+// and only Democrats and Republicans are scored. Roll calls are read from the
+// SQLite store, like attendance and the vote matrix.
+
+use std::cell::OnceCell;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use rusqlite::{params, Connection};
+use serde::Serialize;
+
+use crate::endpoints::CongressChamber;
+use crate::model::{Ballot, Member, Party, RollCall, VoteCast};
+use crate::{Error, Result};
+
+/// Yea and nay counts of one party on one roll call.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PartyTally {
+    pub yea: u32,
+    pub nay: u32,
+}
+
+impl PartyTally {
+    /// +1 if most of the party voted yea, -1 if most voted nay, `None` on a tie or without votes.
+    pub fn position(&self) -> Option<i8> {
+        match self.yea.cmp(&self.nay) {
+            std::cmp::Ordering::Greater => Some(1),
+            std::cmp::Ordering::Less => Some(-1),
+            std::cmp::Ordering::Equal => None,
+        }
+    }
+}
+
+/// A roll call on which the party majorities opposed each other.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UnityVote {
+    pub chamber: CongressChamber,
+    pub congress: u16,
+    pub session: u8,
+    pub rollcall: u32,
+    pub democrat_yea: u32,
+    pub democrat_nay: u32,
+    pub republican_yea: u32,
+    pub republican_nay: u32,
+    /// +1 for yea, -1 for nay.
+    pub democrat_position: i8,
+    pub republican_position: i8,
+}
+
+/// A member voting against their party's majority on a party-unity vote.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Defection {
+    pub chamber: CongressChamber,
+    pub congress: u16,
+    pub session: u8,
+    pub rollcall: u32,
+    pub member_id: String,
+    pub name: Option<String>,
+    pub party: String,
+    pub state: Option<String>,
+    pub vote_cast: String,
+    pub party_position: i8,
+}
+
+/// A member's record on the party-unity votes of a session, or of a whole congress.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct UnityScore {
+    pub chamber: CongressChamber,
+    pub congress: u16,
+    /// `None` for the whole congress.
+    pub session: Option<u8>,
+    pub member_id: String,
+    pub name: Option<String>,
+    /// Members who switched parties have a score per party.
+    pub party: String,
+    pub with_party: u32,
+    pub against_party: u32,
+    /// `with_party / (with_party + against_party)`.
+    pub score: f64,
+}
+
+#[derive(Debug, Clone, Default)]
+struct ScoreTally {
+    name: Option<String>,
+    with_party: u32,
+    against_party: u32,
+}
+
+/// (chamber, congress, session, member id, party). Session 0 holds the congress totals.
+type ScoreKey = (CongressChamber, u16, u8, String, String);
+
+/// Party-unity votes, defections and scores, built up one roll call at a time.
+#[derive(Debug, Clone, Default)]
+pub struct PartyUnity {
+    pub votes: Vec<UnityVote>,
+    pub defections: Vec<Defection>,
+    /// Roll calls seen per chamber and congress, unity vote or not.
+    pub rollcalls: BTreeMap<(CongressChamber, u16), u32>,
+    tallies: BTreeMap<ScoreKey, ScoreTally>,
+    /// Built from `tallies` on first use, cleared by `add`.
+    scores: OnceCell<Vec<UnityScore>>,
+}
+
+/// Party of a ballot, if it is one that is scored.
+fn scored_party(ballot: &Ballot) -> Option<&Party> {
+    ballot.member.party.as_ref().filter(|p| matches!(p, Party::Democrat | Party::Republican))
+}
+
+/// Id a ballot is counted under: bioguide or LIS id, else the printed name.
+fn member_key(ballot: &Ballot) -> Option<String> {
+    let member = &ballot.member;
+    member
+        .id()
+        .or(member.full_name.as_deref())
+        .or(member.last_name.as_deref())
+        .map(str::to_string)
+}
+
+fn member_name(ballot: &Ballot) -> Option<String> {
+    ballot.member.full_name.clone().or_else(|| ballot.member.last_name.clone())
+}
+
+/// Tallies yeas and nays of Democrats and Republicans.
+pub fn party_tallies(rollcall: &RollCall) -> (PartyTally, PartyTally) {
+    let mut democrats = PartyTally::default();
+    let mut republicans = PartyTally::default();
+
+    for ballot in &rollcall.ballots {
+        let tally = match scored_party(ballot) {
+            Some(Party::Democrat) => &mut democrats,
+            Some(Party::Republican) => &mut republicans,
+            _ => continue,
+        };
+
+        match ballot.cast.as_ref().and_then(VoteCast::position) {
+            Some(1) => tally.yea += 1,
+            Some(-1) => tally.nay += 1,
+            _ => {}
+        }
+    }
+
+    (democrats, republicans)
+}
+
+/// The roll call as a party-unity vote, or `None` if the party majorities agreed
+/// (or either party was split evenly or didn't vote).
+pub fn unity_vote(rollcall: &RollCall) -> Option<UnityVote> {
+    let (democrats, republicans) = party_tallies(rollcall);
+    let democrat_position = democrats.position()?;
+    let republican_position = republicans.position()?;
+
+    if democrat_position == republican_position {
+        return None;
+    }
+
+    Some(UnityVote {
+        chamber: rollcall.chamber,
+        congress: rollcall.congress,
+        session: rollcall.session,
+        rollcall: rollcall.number,
+        democrat_yea: democrats.yea,
+        democrat_nay: democrats.nay,
+        republican_yea: republicans.yea,
+        republican_nay: republicans.nay,
+        democrat_position,
+        republican_position,
+    })
+}
+
+impl PartyUnity {
+    /// Adds one roll call. Roll calls can arrive in any order.
+    pub fn add(&mut self, rollcall: &RollCall) {
+        *self.rollcalls.entry((rollcall.chamber, rollcall.congress)).or_default() += 1;
+
+        let Some(vote) = unity_vote(rollcall) else { return };
+
+        for ballot in &rollcall.ballots {
+            let (Some(party), Some(cast), Some(member_id)) = (scored_party(ballot), &ballot.cast, member_key(ballot)) else {
+                continue;
+            };
+            let Some(position) = cast.position() else {
+                continue;
+            };
+
+            let party_position = match party {
+                Party::Democrat => vote.democrat_position,
+                _ => vote.republican_position,
+            };
+            let with_party = position == party_position;
+
+            for session in [rollcall.session, 0] {
+                let key = (rollcall.chamber, rollcall.congress, session, member_id.clone(), party.code().to_string());
+                let tally = self.tallies.entry(key).or_default();
+                tally.name = member_name(ballot);
+                match with_party {
+                    true => tally.with_party += 1,
+                    false => tally.against_party += 1,
+                }
+            }
+
+            if !with_party {
+                self.defections.push(Defection {
+                    chamber: rollcall.chamber,
+                    congress: rollcall.congress,
+                    session: rollcall.session,
+                    rollcall: rollcall.number,
+                    member_id,
+                    name: member_name(ballot),
+                    party: party.code().to_string(),
+                    state: ballot.member.state.map(|s| s.code().to_string()),
+                    vote_cast: cast.to_string(),
+                    party_position,
+                });
+            }
+        }
+
+        self.votes.push(vote);
+        self.scores.take();
+    }
+
+    /// Scores per member and session, followed by each congress as a whole
+    /// (`session: None`), ordered by chamber, congress, session and member.
+    pub fn scores(&self) -> &[UnityScore] {
+        self.scores.get_or_init(|| self.compute_scores())
+    }
+
+    fn compute_scores(&self) -> Vec<UnityScore> {
+        let mut scores: Vec<UnityScore> = self
+            .tallies
+            .iter()
+            .map(|((chamber, congress, session, member_id, party), tally)| UnityScore {
+                chamber: *chamber,
+                congress: *congress,
+                session: (*session != 0).then_some(*session),
+                member_id: member_id.clone(),
+                name: tally.name.clone(),
+                party: party.clone(),
+                with_party: tally.with_party,
+                against_party: tally.against_party,
+                score: tally.with_party as f64 / (tally.with_party + tally.against_party) as f64,
+            })
+            .collect();
+
+        // Congress totals (session 0 in the key) after the sessions
+        scores.sort_by(|a, b| {
+            (a.chamber, a.congress, a.session.is_none(), a.session, &a.member_id, &a.party)
+                .cmp(&(b.chamber, b.congress, b.session.is_none(), b.session, &b.member_id, &b.party))
+        });
+        scores
+    }
+
+    /// Score of one member for a session, or for the congress with `session: None`.
+    pub fn score(&self, chamber: CongressChamber, congress: u16, session: Option<u8>, member_id: &str) -> Option<&UnityScore> {
+        self.scores().iter().find(|s| {
+            s.chamber == chamber && s.congress == congress && s.session == session && s.member_id == member_id
+        })
+    }
+
+    /// Share of roll calls that were party-unity votes, per chamber and congress.
+    pub fn unity_share(&self) -> BTreeMap<(CongressChamber, u16), f64> {
+        let mut unity: BTreeMap<(CongressChamber, u16), u32> = BTreeMap::new();
+        for vote in &self.votes {
+            *unity.entry((vote.chamber, vote.congress)).or_default() += 1;
+        }
+
+        self.rollcalls
+            .iter()
+            .map(|(key, &total)| (*key, unity.get(key).copied().unwrap_or(0) as f64 / total as f64))
+            .collect()
+    }
+}
+
+/// Ballot of a `member_votes` row. The member id goes where the chamber issues it.
+fn stored_ballot(chamber: CongressChamber, row: &rusqlite::Row<'_>) -> Result<Option<Ballot>> {
+    let Some(member_id) = row.get::<_, Option<String>>(5)? else { return Ok(None) };
+
+    let (bioguide_id, lis_member_id) = match chamber {
+        CongressChamber::House => (Some(member_id), None),
+        CongressChamber::Senate => (None, Some(member_id)),
+    };
+    let member = Member {
+        bioguide_id,
+        lis_member_id,
+        full_name: row.get(6)?,
+        last_name: row.get(7)?,
+        party: row.get::<_, Option<String>>(8)?.map(Party::from),
+        state: row.get::<_, Option<String>>(9)?.and_then(|s| s.parse().ok()),
+        ..Default::default()
+    };
+
+    let cast = row.get::<_, Option<String>>(10)?.and_then(|c| c.parse().ok());
+
+    Ok(Some(Ballot { member, cast, paired_with: None }))
+}
+
+/// Computes party unity over every roll call in the database.
+pub fn party_unity(conn: &Connection) -> Result<PartyUnity> {
+    // Roll calls without ballots still count towards the share of unity votes
+    let mut stmt = conn.prepare(
+        "SELECT v.vote_hash, v.chamber, v.congress, v.session, v.rollcall,
+                mv.member_id, m.full_name, m.last_name, mv.party, mv.state, mv.vote_cast
+         FROM votes v
+         LEFT JOIN member_votes mv ON mv.vote_hash = v.vote_hash
+         LEFT JOIN members m ON m.member_id = mv.member_id
+         ORDER BY v.chamber, v.congress, v.session, v.rollcall, mv.member_id",
+    )?;
+    let mut rows = stmt.query([])?;
+
+    let mut unity = PartyUnity::default();
+    let mut current: Option<(String, RollCall)> = None;
+
+    while let Some(row) = rows.next()? {
+        let vote_hash: String = row.get(0)?;
+        if current.as_ref().is_none_or(|(hash, _)| *hash != vote_hash) {
+            if let Some((_, rollcall)) = current.take() {
+                unity.add(&rollcall);
+            }
+            let rollcall = RollCall {
+                chamber: CongressChamber::from(row.get::<_, String>(1)?.as_str()),
+                congress: row.get(2)?,
+                session: row.get(3)?,
+                number: row.get(4)?,
+                date: None,
+                time: None,
+                modified: None,
+                question: None,
+                description: None,
+                result: None,
+                vote_type: None,
+                majority_requirement: None,
+                bill: None,
+                amendment: None,
+                ballots: vec![],
+            };
+            current = Some((vote_hash, rollcall));
+        }
+
+        let (_, rollcall) = current.as_mut().unwrap();
+        if let Some(ballot) = stored_ballot(rollcall.chamber, row)? {
+            rollcall.ballots.push(ballot);
+        }
+    }
+
+    if let Some((_, rollcall)) = current.take() {
+        unity.add(&rollcall);
+    }
+
+    Ok(unity)
+}
+
+/// Replaces the party-unity rows of every chamber and congress `unity` covers.
+pub fn insert_party_unity(tx: &rusqlite::Transaction<'_>, unity: &PartyUnity) -> Result<()> {
+    let covered: BTreeSet<(String, u16)> = unity.rollcalls.keys().map(|(c, n)| (c.to_string(), *n)).collect();
+
+    for (chamber, congress) in &covered {
+        for table in ["party_unity_votes", "party_unity_scores", "party_defections"] {
+            tx.execute(
+                &format!("DELETE FROM {} WHERE chamber = ?1 AND congress = ?2", table),
+                params![chamber, congress],
+            )?;
+        }
+    }
+
+    for vote in &unity.votes {
+        tx.execute(
+            "INSERT INTO party_unity_votes (
+                chamber, congress, session, rollcall,
+                democrat_yea, democrat_nay, republican_yea, republican_nay,
+                democrat_position, republican_position
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                vote.chamber.to_string(),
+                vote.congress,
+                vote.session,
+                vote.rollcall,
+                vote.democrat_yea,
+                vote.democrat_nay,
+                vote.republican_yea,
+                vote.republican_nay,
+                vote.democrat_position,
+                vote.republican_position,
+            ],
+        )?;
+    }
+
+    for score in unity.scores() {
+        tx.execute(
+            "INSERT INTO party_unity_scores (
+                chamber, congress, session, member_id, party, name, with_party, against_party, score
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                score.chamber.to_string(),
+                score.congress,
+                score.session.unwrap_or(0),
+                score.member_id,
+                score.party,
+                score.name,
+                score.with_party,
+                score.against_party,
+                score.score,
+            ],
+        )?;
+    }
+
+    for defection in &unity.defections {
+        tx.execute(
+            "INSERT INTO party_defections (
+                chamber, congress, session, rollcall, member_id, party, vote_cast, party_position
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+            ON CONFLICT (chamber, congress, session, rollcall, member_id) DO UPDATE SET
+                party = excluded.party,
+                vote_cast = excluded.vote_cast,
+                party_position = excluded.party_position",
+            params![
+                defection.chamber.to_string(),
+                defection.congress,
+                defection.session,
+                defection.rollcall,
+                defection.member_id,
+                defection.party,
+                defection.vote_cast,
+                defection.party_position,
+            ],
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Maps a CSV writer error on `path` to a crate error.
+pub(crate) fn csv_error(path: &Path) -> impl Fn(csv::Error) -> Error + Copy + '_ {
+    move |e| Error::schema(format!("Failed to write CSV: {}", e)).with_path(path)
+}
+
+/// Writes `rows` as CSV with a header row.
+pub(crate) fn write_csv<T: Serialize>(path: &Path, rows: impl IntoIterator<Item = T>) -> Result<()> {
+    let csv_err = csv_error(path);
+
+    let mut writer = csv::Writer::from_path(path).map_err(csv_err)?;
+    for row in rows {
+        writer.serialize(row).map_err(csv_err)?;
+    }
+    writer.flush().map_err(|e| Error::io(path, e))
+}
+
+/// Writes `party_unity_votes.csv`, `party_unity_scores.csv` and `party_defections.csv` to `dir`.
+pub fn write_party_unity_csv(dir: &Path, unity: &PartyUnity) -> Result<()> {
+    std::fs::create_dir_all(dir).map_err(|e| Error::io(dir, e))?;
+
+    write_csv(&dir.join("party_unity_votes.csv"), &unity.votes)?;
+    write_csv(&dir.join("party_unity_scores.csv"), unity.scores())?;
+    write_csv(&dir.join("party_defections.csv"), &unity.defections)
+}
@@ -0,0 +1,290 @@
+// attendance.rs
+//
+// Attendance, read from the SQLite store. A ballot is a missed vote when the
+// member is recorded as "Not Voting" (House) or absent (Senate), or without a
+// vote at all; "Present" counts as attending. Streaks run over the roll calls a
+// member was on the rolls for, in congress, session and roll call order.
+
+use std::collections::BTreeMap;
+
+use rusqlite::{params, Connection};
+use serde::Serialize;
+
+use crate::endpoints::CongressChamber;
+use crate::Result;
+
+/// Which roll calls to report on. `None` means all of them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AttendanceFilter {
+    pub chamber: Option<CongressChamber>,
+    pub congress: Option<u16>,
+}
+
+/// A member's attendance over a session, or a whole congress.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MemberAttendance {
+    pub chamber: CongressChamber,
+    pub congress: u16,
+    /// `None` for the whole congress.
+    pub session: Option<u8>,
+    pub member_id: String,
+    pub name: Option<String>,
+    pub party: Option<String>,
+    pub state: Option<String>,
+    pub rollcalls: u32,
+    pub missed: u32,
+    /// Share of roll calls voted on, `1 - missed / rollcalls`.
+    pub rate: f64,
+}
+
+/// Position of a roll call within a chamber.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct RollCallPosition {
+    pub congress: u16,
+    pub session: u8,
+    pub rollcall: u32,
+}
+
+/// A run of consecutive missed votes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MissedStreak {
+    pub chamber: CongressChamber,
+    pub member_id: String,
+    pub name: Option<String>,
+    pub length: u32,
+    pub first: RollCallPosition,
+    pub last: RollCallPosition,
+    /// "2024-01-09", or the date as published if it could not be parsed.
+    pub first_date: Option<String>,
+    pub last_date: Option<String>,
+}
+
+/// Attendance of a whole chamber over a session, or a whole congress.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChamberAttendance {
+    pub chamber: CongressChamber,
+    pub congress: u16,
+    /// `None` for the whole congress.
+    pub session: Option<u8>,
+    pub rollcalls: u32,
+    pub members: u32,
+    pub ballots: u32,
+    pub missed: u32,
+    /// Share of all ballots that were votes.
+    pub rate: f64,
+    /// Attendance rate of the median member.
+    pub median_member_rate: f64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AttendanceReport {
+    /// Ordered by chamber, congress, session (sessions before the congress) and member.
+    pub members: Vec<MemberAttendance>,
+    /// Each member's longest streak, longest first.
+    pub streaks: Vec<MissedStreak>,
+    /// Ordered by chamber, congress and session (sessions before the congress).
+    pub chambers: Vec<ChamberAttendance>,
+}
+
+/// Whether a recorded vote is a missed vote.
+pub fn is_missed(vote_cast: Option<&str>) -> bool {
+    match vote_cast.map(|v| v.trim().to_lowercase()) {
+        None => true,
+        Some(v) => v.is_empty() || v == "not voting" || v == "absent",
+    }
+}
+
+#[derive(Debug, Default)]
+struct Tally {
+    name: Option<String>,
+    party: Option<String>,
+    state: Option<String>,
+    rollcalls: u32,
+    missed: u32,
+}
+
+/// Streak being followed for the current member.
+struct OpenStreak {
+    length: u32,
+    first: RollCallPosition,
+    first_date: Option<String>,
+    last: RollCallPosition,
+    last_date: Option<String>,
+}
+
+/// (chamber, congress, session, member id). Session 0 holds the congress totals.
+type TallyKey = (CongressChamber, u16, u8, String);
+
+/// Computes attendance, streaks and chamber comparisons from the ballots in the database.
+pub fn attendance(conn: &Connection, filter: &AttendanceFilter) -> Result<AttendanceReport> {
+    let mut stmt = conn.prepare(
+        "SELECT v.chamber, v.congress, v.session, v.rollcall, COALESCE(v.vote_day, v.vote_date),
+                mv.member_id, m.full_name, mv.party, mv.state, mv.vote_cast
+         FROM member_votes mv
+         JOIN votes v ON v.vote_hash = mv.vote_hash
+         JOIN members m ON m.member_id = mv.member_id
+         WHERE (?1 IS NULL OR v.chamber = ?1) AND (?2 IS NULL OR v.congress = ?2)
+         ORDER BY v.chamber, mv.member_id, v.congress, v.session, v.rollcall",
+    )?;
+
+    let chamber = filter.chamber.map(|c| c.to_string());
+    let mut rows = stmt.query(params![chamber, filter.congress])?;
+
+    let mut tallies: BTreeMap<TallyKey, Tally> = BTreeMap::new();
+    let mut longest: BTreeMap<(CongressChamber, String), MissedStreak> = BTreeMap::new();
+    let mut current: Option<((CongressChamber, String), OpenStreak)> = None;
+
+    while let Some(row) = rows.next()? {
+        let chamber = CongressChamber::from(row.get::<_, String>(0)?.as_str());
+        let position = RollCallPosition { congress: row.get(1)?, session: row.get(2)?, rollcall: row.get(3)? };
+        let vote_date: Option<String> = row.get(4)?;
+        let member_id: String = row.get(5)?;
+        let name: Option<String> = row.get(6)?;
+        let vote_cast: Option<String> = row.get(9)?;
+        let missed = is_missed(vote_cast.as_deref());
+
+        for session in [position.session, 0] {
+            let tally = tallies.entry((chamber, position.congress, session, member_id.clone())).or_default();
+            tally.name = name.clone();
+            tally.party = row.get(7)?;
+            tally.state = row.get(8)?;
+            tally.rollcalls += 1;
+            tally.missed += missed as u32;
+        }
+
+        // A new member, or a vote cast, ends the open streak
+        let member = (chamber, member_id);
+        if current.as_ref().is_some_and(|(m, _)| *m != member || !missed) {
+            let (m, streak) = current.take().unwrap();
+            close_streak(&mut longest, m, streak);
+        }
+
+        if missed {
+            match &mut current {
+                Some((_, streak)) => {
+                    streak.length += 1;
+                    streak.last = position;
+                    streak.last_date = vote_date;
+                }
+                None => {
+                    current = Some((
+                        member,
+                        OpenStreak { length: 1, first: position, first_date: vote_date.clone(), last: position, last_date: vote_date },
+                    ))
+                }
+            }
+        }
+    }
+
+    if let Some((member, streak)) = current.take() {
+        close_streak(&mut longest, member, streak);
+    }
+
+    let mut streaks: Vec<MissedStreak> = longest.into_values().collect();
+    for streak in &mut streaks {
+        let key = (streak.chamber, streak.last.congress, 0, streak.member_id.clone());
+        streak.name = tallies.get(&key).and_then(|t| t.name.clone());
+    }
+    streaks.sort_by(|a, b| b.length.cmp(&a.length).then_with(|| (a.chamber, &a.member_id).cmp(&(b.chamber, &b.member_id))));
+
+    let mut members: Vec<MemberAttendance> = tallies
+        .into_iter()
+        .map(|((chamber, congress, session, member_id), t)| MemberAttendance {
+            chamber,
+            congress,
+            session: (session != 0).then_some(session),
+            member_id,
+            name: t.name,
+            party: t.party,
+            state: t.state,
+            rollcalls: t.rollcalls,
+            missed: t.missed,
+            rate: 1.0 - t.missed as f64 / t.rollcalls as f64,
+        })
+        .collect();
+    members.sort_by(|a, b| {
+        (a.chamber, a.congress, a.session.is_none(), a.session, &a.member_id)
+            .cmp(&(b.chamber, b.congress, b.session.is_none(), b.session, &b.member_id))
+    });
+
+    let chambers = chamber_attendance(conn, filter, &members)?;
+
+    Ok(AttendanceReport { members, streaks, chambers })
+}
+
+fn close_streak(
+    longest: &mut BTreeMap<(CongressChamber, String), MissedStreak>,
+    member: (CongressChamber, String),
+    streak: OpenStreak,
+) {
+    if longest.get(&member).is_some_and(|s| s.length >= streak.length) {
+        return;
+    }
+
+    let (chamber, member_id) = member.clone();
+    longest.insert(
+        member,
+        MissedStreak {
+            chamber,
+            member_id,
+            name: None,
+            length: streak.length,
+            first: streak.first,
+            last: streak.last,
+            first_date: streak.first_date,
+            last_date: streak.last_date,
+        },
+    );
+}
+
+/// Sums the member rows of each chamber, congress and session.
+fn chamber_attendance(conn: &Connection, filter: &AttendanceFilter, members: &[MemberAttendance]) -> Result<Vec<ChamberAttendance>> {
+    let mut groups: BTreeMap<(CongressChamber, u16, bool, Option<u8>), Vec<&MemberAttendance>> = BTreeMap::new();
+    for member in members {
+        groups.entry((member.chamber, member.congress, member.session.is_none(), member.session)).or_default().push(member);
+    }
+
+    // Roll calls per session, counted from `votes` so empty roll calls are included
+    let mut stmt = conn.prepare(
+        "SELECT chamber, congress, session, COUNT(*) FROM votes
+         WHERE (?1 IS NULL OR chamber = ?1) AND (?2 IS NULL OR congress = ?2)
+         GROUP BY chamber, congress, session",
+    )?;
+    let mut rollcalls: BTreeMap<(CongressChamber, u16, Option<u8>), u32> = BTreeMap::new();
+    let chamber = filter.chamber.map(|c| c.to_string());
+    let mut rows = stmt.query(params![chamber, filter.congress])?;
+    while let Some(row) = rows.next()? {
+        let chamber = CongressChamber::from(row.get::<_, String>(0)?.as_str());
+        let congress: u16 = row.get(1)?;
+        let count: u32 = row.get(3)?;
+        *rollcalls.entry((chamber, congress, Some(row.get(2)?))).or_default() += count;
+        *rollcalls.entry((chamber, congress, None)).or_default() += count;
+    }
+
+    Ok(groups
+        .into_iter()
+        .map(|((chamber, congress, _, session), group)| {
+            let ballots: u32 = group.iter().map(|m| m.rollcalls).sum();
+            let missed: u32 = group.iter().map(|m| m.missed).sum();
+
+            let mut rates: Vec<f64> = group.iter().map(|m| m.rate).collect();
+            rates.sort_by(|a, b| a.total_cmp(b));
+            let median_member_rate = match rates.len() % 2 {
+                1 => rates[rates.len() / 2],
+                _ => (rates[rates.len() / 2 - 1] + rates[rates.len() / 2]) / 2.0,
+            };
+
+            ChamberAttendance {
+                chamber,
+                congress,
+                session,
+                rollcalls: rollcalls.get(&(chamber, congress, session)).copied().unwrap_or(0),
+                members: group.len() as u32,
+                ballots,
+                missed,
+                rate: 1.0 - missed as f64 / ballots as f64,
+                median_member_rate,
+            }
+        })
+        .collect())
+}
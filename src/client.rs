@@ -0,0 +1,580 @@
+// client.rs
+//
+// HTTP access to clerk.house.gov and senate.gov, plus discovery of the latest
+// roll call from the House EVS index pages and the Senate vote menus.
+
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use indicatif::{ProgressBar, ProgressStyle};
+use reqwest::header::{HeaderValue, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::{Client, StatusCode};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{Mutex, Semaphore};
+use tokio::time::Instant;
+
+use crate::endpoints::{congress_for_date, CongressChamber, CongressEndpoint};
+use crate::sqllite::generate_hash;
+use crate::xml_reader::xml_to_value;
+use crate::{Error, Result};
+
+#[derive(Clone)]
+pub struct RollCallClient {
+    client: Client,
+}
+
+impl RollCallClient {
+    /// Creates a client, routing every request through `proxy` when one is given.
+    pub fn new(proxy: Option<&str>) -> Result<Self> {
+
+        let client = match proxy {
+            Some(url) => {
+                let proxy = reqwest::Proxy::all(url).map_err(|e| Error::Config {
+                    message: format!("Invalid proxy {}: {}", url, e),
+                })?;
+
+                Client::builder()
+                    .proxy(proxy)
+                    .build()
+                    .map_err(|e| Error::Config {
+                        message: format!("Error creating client: {}", e),
+                    })?
+            }
+            None => Client::new(),
+        };
+
+        Ok(RollCallClient {
+            client,
+        })
+    }
+
+    /// GETs `url`, failing with `Error::Network` on anything but a 2xx status.
+    pub async fn fetch_data(&self, url: &str) -> Result<String> {
+        match self.fetch_conditional(url, &Validators::default()).await? {
+            Some((body, _)) => Ok(body),
+            None => Err(Error::Network {
+                url: url.to_string(),
+                status: Some(304),
+                source: None,
+            }),
+        }
+    }
+
+    /// GETs `url` with `If-None-Match`/`If-Modified-Since` from `validators`. Returns
+    /// `None` when the server answers 304 Not Modified, otherwise the body and the
+    /// validators to send next time.
+    pub async fn fetch_conditional(&self, url: &str, validators: &Validators) -> Result<Option<(String, Validators)>> {
+        let mut request = self.client.get(url);
+        if let Some(etag) = &validators.etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &validators.last_modified {
+            request = request.header(IF_MODIFIED_SINCE, last_modified);
+        }
+
+        let response = request.send().await.map_err(|e| Error::network(url, e))?;
+
+        let status = response.status();
+        if status == StatusCode::NOT_MODIFIED {
+            return Ok(None);
+        }
+        if !status.is_success() {
+            return Err(Error::Network {
+                url: url.to_string(),
+                status: Some(status.as_u16()),
+                source: None,
+            });
+        }
+
+        let header = |name| response.headers().get(name).and_then(|v: &HeaderValue| v.to_str().ok()).map(|v| v.to_string());
+        let validators = Validators {
+            etag: header(ETAG),
+            last_modified: header(LAST_MODIFIED),
+        };
+
+        let body = response.text().await.map_err(|e| Error::network(url, e))?;
+        Ok(Some((body, validators)))
+    }
+
+    /// Highest roll call number published for the endpoint's chamber, congress and session.
+    pub async fn latest_roll(&self, endpoint: &CongressEndpoint) -> Result<u32> {
+        let index_url = endpoint.index_url()?;
+        let index = self.fetch_data(&index_url).await?;
+
+        let latest = match endpoint.chamber {
+            CongressChamber::House => {
+                // The index only links to pages of 100 votes; the newest page lists the latest roll call
+                match parse_house_index(&index) {
+                    Some(page) => {
+                        let page_url = format!("{}{}", &index_url[..index_url.rfind('/').unwrap_or(0) + 1], page);
+                        parse_house_roll_page(&self.fetch_data(&page_url).await?)
+                    }
+                    None => None,
+                }
+            }
+            CongressChamber::Senate => parse_senate_menu(&index)?,
+        };
+
+        latest.ok_or_else(|| Error::schema(format!("No roll calls listed at {}", index_url)).with_roll_call(*endpoint))
+    }
+
+    /// Endpoint of the latest roll call in the congress and session sitting today.
+    pub async fn discover(&self, chamber: &str) -> Result<CongressEndpoint> {
+        let (number, session) = congress_for_date(chrono::Utc::now().date_naive());
+        let mut endpoint = CongressEndpoint::new(chamber, number, session, 0);
+
+        let roll_call = self.latest_roll(&endpoint).await?;
+        Ok(endpoint.roll_call(roll_call).build())
+    }
+}
+
+/// Numbers that directly follow each occurrence of `marker` in `text`.
+fn numbers_after<'a>(text: &'a str, marker: &'a str) -> impl Iterator<Item = u32> + 'a {
+    text.match_indices(marker).filter_map(move |(i, _)| {
+        let rest = &text[i + marker.len()..];
+        let end = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+        rest[..end].parse().ok()
+    })
+}
+
+/// Newest `ROLL_xxx.asp` page linked from a House EVS `index.asp`.
+pub fn parse_house_index(html: &str) -> Option<String> {
+    numbers_after(html, "ROLL_")
+        .max()
+        .map(|page| format!("ROLL_{:0>3}.asp", page))
+}
+
+/// Highest `rollnumber=` linked from a House EVS `ROLL_xxx.asp` page.
+pub fn parse_house_roll_page(html: &str) -> Option<u32> {
+    numbers_after(html, "rollnumber=").max()
+}
+
+/// Highest `vote_number` in a Senate `vote_menu_{congress}_{session}.xml`.
+pub fn parse_senate_menu(xml: &str) -> Result<Option<u32>> {
+    let value = xml_to_value(xml)?;
+
+    // `vote` is a list when the session has more than one vote and a single object otherwise
+    let votes = match &value["vote_summary"]["votes"]["vote"] {
+        serde_json::Value::Array(list) => list.iter().collect(),
+        serde_json::Value::Null => vec![],
+        single => vec![single],
+    };
+
+    Ok(votes
+        .into_iter()
+        .filter_map(|vote| vote["vote_number"].as_str())
+        .filter_map(|number| number.trim().parse::<u32>().ok())
+        .max())
+}
+
+/// `ETag` and `Last-Modified` of a downloaded file, sent back on the next refresh.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Validators {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+/// What is known about a downloaded file: where it came from, its validators
+/// and the `generate_hash` of its content.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub url: String,
+    #[serde(flatten)]
+    pub validators: Validators,
+    pub hash: String,
+}
+
+/// Completed downloads between two saves of the manifest during a run.
+const MANIFEST_SAVE_INTERVAL: usize = 50;
+
+/// Manifest of downloaded files, keyed by path, kept as JSON next to the data (see `Workspace::manifest_path`).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub files: BTreeMap<String, ManifestEntry>,
+}
+
+impl Manifest {
+    /// Loads the manifest at `path`, or an empty one if there is none yet.
+    pub fn load(path: &Path) -> Result<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(data) => serde_json::from_str(&data)
+                .map_err(|e| Error::schema(format!("Invalid download manifest: {}", e)).with_path(path)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Manifest::default()),
+            Err(e) => Err(Error::io(path, e)),
+        }
+    }
+
+    /// Writes the manifest to a temporary file next to `path` and renames it into
+    /// place, so an interrupted save leaves the previous manifest intact.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let data = serde_json::to_string_pretty(self)
+            .map_err(|e| Error::schema(format!("Failed to serialize download manifest: {}", e)))?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| Error::io(parent, e))?;
+        }
+
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        let tmp = PathBuf::from(tmp);
+        std::fs::write(&tmp, data).map_err(|e| Error::io(&tmp, e))?;
+        std::fs::rename(&tmp, path).map_err(|e| Error::io(path, e))
+    }
+}
+
+/// One file to fetch: the roll call XML at `url`, saved to `path`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DownloadJob {
+    pub url: String,
+    pub path: PathBuf,
+}
+
+/// A file whose content changed on refresh.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change {
+    pub path: PathBuf,
+    pub url: String,
+    pub old_hash: String,
+    pub new_hash: String,
+}
+
+/// What a `Downloader::run` did. Failed jobs keep the error of their last attempt.
+#[derive(Debug, Default)]
+pub struct DownloadReport {
+    pub downloaded: usize,
+    pub skipped: usize,
+    pub unchanged: usize,
+    pub changed: Vec<Change>,
+    pub failed: Vec<(DownloadJob, Error)>,
+}
+
+enum Outcome {
+    Downloaded,
+    Unchanged,
+    Changed(Change),
+}
+
+/// Fetches many roll calls at once, with a cap on concurrent requests, a minimum
+/// delay between requests to the same host, and retries with exponential backoff.
+///
+/// Files are written to `{path}.part` and renamed when complete, and existing
+/// files are skipped, so an interrupted run can simply be started again. In
+/// refresh mode existing files are requested again, conditionally when the
+/// manifest has validators for them, and only replaced if their hash changed.
+pub struct Downloader {
+    client: RollCallClient,
+    concurrency: usize,
+    host_interval: Duration,
+    retries: u32,
+    backoff: Duration,
+    progress: bool,
+    refresh: bool,
+    manifest: Option<PathBuf>,
+    changelog: Option<PathBuf>,
+}
+
+impl Downloader {
+    pub fn new(client: RollCallClient) -> Self {
+        Downloader {
+            client,
+            concurrency: 4,
+            host_interval: Duration::from_millis(250),
+            retries: 3,
+            backoff: Duration::from_millis(500),
+            progress: true,
+            refresh: false,
+            manifest: None,
+            changelog: None,
+        }
+    }
+
+    /// Number of requests in flight at once.
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Minimum time between two requests to the same host.
+    pub fn rate_limit(mut self, host_interval: Duration) -> Self {
+        self.host_interval = host_interval;
+        self
+    }
+
+    /// Retries after the first attempt; the delay doubles from `backoff` on each one.
+    pub fn retries(mut self, retries: u32, backoff: Duration) -> Self {
+        self.retries = retries;
+        self.backoff = backoff;
+        self
+    }
+
+    /// Shows an `indicatif` progress bar while running.
+    pub fn progress(mut self, progress: bool) -> Self {
+        self.progress = progress;
+        self
+    }
+
+    /// Requests files that already exist again and replaces the ones that changed.
+    pub fn refresh(mut self, refresh: bool) -> Self {
+        self.refresh = refresh;
+        self
+    }
+
+    /// Records validators and hashes in the manifest at `path`, and uses them on refresh.
+    pub fn manifest(mut self, path: impl Into<PathBuf>) -> Self {
+        self.manifest = Some(path.into());
+        self
+    }
+
+    /// Appends a line per changed file to the log at `path`.
+    pub fn changelog(mut self, path: impl Into<PathBuf>) -> Self {
+        self.changelog = Some(path.into());
+        self
+    }
+
+    pub async fn run(&self, jobs: Vec<DownloadJob>) -> Result<DownloadReport> {
+        let mut report = DownloadReport::default();
+
+        let manifest = match &self.manifest {
+            Some(path) => Manifest::load(path)?,
+            None => Manifest::default(),
+        };
+
+        let pb = if self.progress {
+            ProgressBar::new(jobs.len() as u64)
+        } else {
+            ProgressBar::hidden()
+        };
+        pb.set_style(
+            ProgressStyle::default_bar()
+                .template("[{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} ({percent}%) {msg}")?
+                .progress_chars("=> "),
+        );
+        pb.set_message(if self.refresh { "🔄 Refreshing" } else { "📥 Downloading" });
+
+        // Resume: files from an earlier run are already complete
+        let (done, pending): (Vec<_>, Vec<_>) = jobs.into_iter().partition(|job| !self.refresh && job.path.exists());
+        report.skipped = done.len();
+        pb.inc(done.len() as u64);
+
+        let semaphore = Arc::new(Semaphore::new(self.concurrency));
+        let hosts: Arc<Mutex<HashMap<String, Instant>>> = Arc::new(Mutex::new(HashMap::new()));
+        let manifest = Arc::new(Mutex::new(manifest));
+
+        let mut handles = Vec::with_capacity(pending.len());
+        for job in pending {
+            let semaphore = semaphore.clone();
+            let hosts = hosts.clone();
+            let manifest = manifest.clone();
+            let client = self.client.clone();
+            let (host_interval, retries, backoff) = (self.host_interval, self.retries, self.backoff);
+            let pb = pb.clone();
+
+            handles.push(tokio::spawn(async move {
+                let _permit = semaphore.acquire_owned().await.expect("semaphore is never closed");
+                let result = download_one(&client, &job, &hosts, &manifest, host_interval, retries, backoff).await;
+                pb.inc(1);
+                (job, result)
+            }));
+        }
+
+        for (completed, handle) in handles.into_iter().enumerate() {
+            let (job, result) = handle.await.map_err(|e| Error::task("Download", e))?;
+
+            // Keep what is known so far if the run is interrupted
+            if let Some(path) = &self.manifest {
+                if (completed + 1) % MANIFEST_SAVE_INTERVAL == 0 {
+                    manifest.lock().await.save(path)?;
+                }
+            }
+
+            match result {
+                Ok(Outcome::Downloaded) => report.downloaded += 1,
+                Ok(Outcome::Unchanged) => report.unchanged += 1,
+                Ok(Outcome::Changed(change)) => {
+                    pb.println(format!("🔁 {} changed", change.path.display()));
+                    report.changed.push(change);
+                }
+                Err(e) => {
+                    pb.println(format!("❌ {}", e));
+                    report.failed.push((job, e));
+                }
+            }
+        }
+
+        if let Some(path) = &self.manifest {
+            manifest.lock().await.save(path)?;
+        }
+
+        if let Some(path) = &self.changelog {
+            write_changelog(path, &report.changed)?;
+        }
+
+        pb.finish_with_message(format!(
+            "✅ {} downloaded, {} changed, {} unchanged, {} skipped, {} failed",
+            report.downloaded,
+            report.changed.len(),
+            report.unchanged,
+            report.skipped,
+            report.failed.len()
+        ));
+
+        Ok(report)
+    }
+}
+
+/// Appends `{time}\t{path}\t{url}\t{old hash} -> {new hash}` for each change.
+fn write_changelog(path: &Path, changes: &[Change]) -> Result<()> {
+    if changes.is_empty() {
+        return Ok(());
+    }
+
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| Error::io(path, e))?;
+
+    let now = chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ");
+    for change in changes {
+        writeln!(
+            file,
+            "{}\t{}\t{}\t{} -> {}",
+            now,
+            change.path.display(),
+            change.url,
+            change.old_hash,
+            change.new_hash
+        )
+        .map_err(|e| Error::io(path, e))?;
+    }
+
+    Ok(())
+}
+
+/// Waits until `url`'s host may be contacted again and reserves the next slot.
+async fn wait_for_host(hosts: &Mutex<HashMap<String, Instant>>, url: &str, interval: Duration) {
+    let host = reqwest::Url::parse(url)
+        .ok()
+        .and_then(|u| u.host_str().map(|h| h.to_string()))
+        .unwrap_or_default();
+
+    let slot = {
+        let mut hosts = hosts.lock().await;
+        let now = Instant::now();
+        let slot = hosts.get(&host).copied().filter(|next| *next > now).unwrap_or(now);
+        hosts.insert(host, slot + interval);
+        slot
+    };
+
+    tokio::time::sleep_until(slot).await;
+}
+
+/// Timeouts, dropped connections, throttling and server errors are worth another try.
+fn is_retryable(e: &Error) -> bool {
+    match e {
+        Error::Network { status: Some(status), .. } => *status == 429 || *status >= 500,
+        Error::Network { .. } => true,
+        _ => false,
+    }
+}
+
+async fn download_one(
+    client: &RollCallClient,
+    job: &DownloadJob,
+    hosts: &Mutex<HashMap<String, Instant>>,
+    manifest: &Mutex<Manifest>,
+    host_interval: Duration,
+    retries: u32,
+    backoff: Duration,
+) -> Result<Outcome> {
+    let key = job.path.display().to_string();
+    let known = manifest.lock().await.files.get(&key).cloned();
+
+    // Validators only apply to the file they were recorded for
+    let exists = job.path.exists();
+    let validators = match &known {
+        Some(entry) if exists && entry.url == job.url => entry.validators.clone(),
+        _ => Validators::default(),
+    };
+
+    let mut attempt = 0;
+    let fetched = loop {
+        wait_for_host(hosts, &job.url, host_interval).await;
+
+        match client.fetch_conditional(&job.url, &validators).await {
+            Ok(fetched) => break fetched,
+            Err(e) if attempt < retries && is_retryable(&e) => {
+                tokio::time::sleep(backoff * 2u32.pow(attempt)).await;
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    };
+
+    let (body, validators) = match fetched {
+        Some(fetched) => fetched,
+        None => return Ok(Outcome::Unchanged),
+    };
+
+    // senate.gov answers blocked or moved requests with a 200 HTML page instead of the XML
+    if !body.contains("<rollcall-vote") && !body.contains("<roll_call_vote") {
+        return Err(Error::schema(format!("Response from {} is not a roll call", job.url)).with_path(&job.path));
+    }
+
+    let new_hash = generate_hash(&body);
+    let old_hash = if exists {
+        match &known {
+            Some(entry) if !entry.hash.is_empty() => Some(entry.hash.clone()),
+            _ => {
+                let data = std::fs::read(&job.path).map_err(|e| Error::io(&job.path, e))?;
+                Some(generate_hash(&String::from_utf8_lossy(&data)))
+            }
+        }
+    } else {
+        None
+    };
+
+    let outcome = match old_hash {
+        Some(old_hash) if old_hash == new_hash => Outcome::Unchanged,
+        Some(old_hash) => {
+            write_atomic(&job.path, &body)?;
+            Outcome::Changed(Change {
+                path: job.path.clone(),
+                url: job.url.clone(),
+                old_hash,
+                new_hash: new_hash.clone(),
+            })
+        }
+        None => {
+            write_atomic(&job.path, &body)?;
+            Outcome::Downloaded
+        }
+    };
+
+    manifest.lock().await.files.insert(
+        key,
+        ManifestEntry {
+            url: job.url.clone(),
+            validators,
+            hash: new_hash,
+        },
+    );
+
+    Ok(outcome)
+}
+
+/// Writes `{path}.part` and renames it over `path`, so readers never see half a file.
+fn write_atomic(path: &Path, body: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| Error::io(parent, e))?;
+    }
+
+    let mut part = path.to_path_buf().into_os_string();
+    part.push(".part");
+    let part = PathBuf::from(part);
+
+    std::fs::write(&part, body).map_err(|e| Error::io(&part, e))?;
+    std::fs::rename(&part, path).map_err(|e| Error::io(path, e))
+}
@@ -0,0 +1,178 @@
+// dates.rs
+//
+// Dates and times as the chambers publish them, turned into Eastern-time
+// timestamps. The House gives `action-date` ("24-Jul-2008") with the time in a
+// separate `action-time` element (`time-etz="14:36"`, content "2:36 PM"); the
+// Senate gives free text ("January 9, 2024, 05:31 PM") in `vote_date` and
+// `modify_date`, and only the day ("09-Jan") in its vote menus. The formats
+// have drifted since 1989 (case, spacing, weekday prefixes, "p.m.", two-digit
+// years), so parsing normalizes the text and tries the known shapes in turn.
+//
+// Both chambers record local time in Washington, so timestamps are in
+// America/New_York: a time repeated when clocks go back is the earlier one, and
+// a time skipped when they go forward is read an hour later.
+
+use chrono::{DateTime, Datelike, FixedOffset, LocalResult, NaiveDate, NaiveDateTime, NaiveTime, TimeZone};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
+/// Time zone of both chambers' timestamps.
+pub const EASTERN: Tz = chrono_tz::America::New_York;
+
+const DATE_FORMATS: &[&str] = &[
+    "%d-%b-%Y",
+    "%d-%B-%Y",
+    "%Y-%m-%d",
+    "%B %d, %Y",
+    "%B %d %Y",
+    "%d %B %Y",
+    "%m/%d/%Y",
+    "%d-%b-%y",
+    "%m/%d/%y",
+];
+
+/// Formats without a year, completed with the year the caller expects ("09-Jan" in a Senate vote menu).
+const YEARLESS_DATE_FORMATS: &[&str] = &["%d-%b", "%B %d", "%b %d"];
+
+const TIME_FORMATS: &[&str] = &["%I:%M %p", "%I:%M:%S %p", "%I:%M%p", "%H:%M", "%H:%M:%S"];
+
+const WEEKDAYS: &[&str] = &["monday", "tuesday", "wednesday", "thursday", "friday", "saturday", "sunday"];
+
+/// Collapses whitespace, drops a leading weekday and trailing time zone names,
+/// and spells "a.m."/"p.m." as "AM"/"PM".
+fn normalize(value: &str) -> String {
+    let mut words: Vec<String> = value
+        .split_whitespace()
+        .map(|w| match w.to_lowercase().trim_end_matches(',') {
+            "a.m." | "am" => "AM".to_string(),
+            "p.m." | "pm" => "PM".to_string(),
+            _ => w.to_string(),
+        })
+        .collect();
+
+    if words.first().is_some_and(|w| WEEKDAYS.contains(&w.to_lowercase().trim_end_matches(','))) {
+        words.remove(0);
+    }
+    while words
+        .last()
+        .is_some_and(|w| matches!(w.to_uppercase().trim_matches(|c| c == '(' || c == ')'), "ET" | "EST" | "EDT" | "ETZ"))
+    {
+        words.pop();
+    }
+
+    words.join(" ").trim_matches(|c: char| c == ',' || c.is_whitespace()).to_string()
+}
+
+/// Parses a date in any of the published formats. `year` completes dates published without one.
+pub fn parse_date(value: &str, year: Option<i32>) -> Option<NaiveDate> {
+    // "Jan." and "Sept." as well as "Jan" and "Sep"
+    let value = normalize(value).replace('.', "").replace("Sept ", "Sep ");
+
+    // `%Y` takes "08" as the year 8, which leaves two-digit years to `%y`
+    DATE_FORMATS
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(&value, format).ok().filter(|d| d.year() >= 1789))
+        .or_else(|| {
+            let year = year?;
+            YEARLESS_DATE_FORMATS
+                .iter()
+                .find_map(|format| NaiveDate::parse_from_str(&format!("{} {}", value, year), &format!("{} %Y", format)).ok())
+        })
+}
+
+/// Parses a time of day, 24-hour ("14:36") or 12-hour ("2:36 PM", "02:36 p.m.").
+pub fn parse_time(value: &str) -> Option<NaiveTime> {
+    let value = normalize(value).to_uppercase();
+    TIME_FORMATS.iter().find_map(|format| NaiveTime::parse_from_str(&value, format).ok())
+}
+
+/// Splits free text such as "January 9, 2024, 05:31 PM" into its date and,
+/// when there is one, its time. The time starts at the first word with a colon.
+pub fn parse_datetime(value: &str, year: Option<i32>) -> Option<(NaiveDate, Option<NaiveTime>)> {
+    let value = normalize(value);
+
+    // ISO 8601, as written by other tools
+    for format in ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"] {
+        if let Ok(datetime) = NaiveDateTime::parse_from_str(&value, format) {
+            return Some((datetime.date(), Some(datetime.time())));
+        }
+    }
+
+    let words: Vec<&str> = value.split(' ').collect();
+    match words.iter().position(|w| w.contains(':')) {
+        Some(split) => {
+            let date = words[..split].join(" ");
+            let date = date.trim_end_matches(',').trim_end_matches(" at");
+            Some((parse_date(date, year)?, parse_time(&words[split..].join(" "))))
+        }
+        None => Some((parse_date(&value, year)?, None)),
+    }
+}
+
+/// The Eastern-time instant of a local date and time in Washington.
+pub fn to_eastern(date: NaiveDate, time: NaiveTime) -> DateTime<Tz> {
+    let local = date.and_time(time);
+    match EASTERN.from_local_datetime(&local) {
+        LocalResult::Single(t) => t,
+        LocalResult::Ambiguous(earlier, _) => earlier,
+        LocalResult::None => EASTERN
+            .from_local_datetime(&(local + chrono::Duration::hours(1)))
+            .earliest()
+            .unwrap_or_else(|| EASTERN.from_utc_datetime(&local)),
+    }
+}
+
+/// When a roll call was held, and for the Senate, when its record was last changed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VoteTimes {
+    /// Day of the vote in Washington.
+    pub day: Option<NaiveDate>,
+    /// Time of the vote with its Eastern offset, when the chamber published one.
+    pub at: Option<DateTime<FixedOffset>>,
+    /// Senate `modify_date`.
+    pub modified: Option<DateTime<FixedOffset>>,
+}
+
+impl VoteTimes {
+    /// From a House `action-date` and the `time-etz` attribute (or, failing that, the content) of `action-time`.
+    /// `year` is the session's year, for dates published without one.
+    pub fn house(action_date: Option<&str>, time_etz: Option<&str>, action_time: Option<&str>, year: Option<i32>) -> Self {
+        let day = action_date.and_then(|d| parse_date(d, year));
+        let time = time_etz.and_then(parse_time).or_else(|| action_time.and_then(parse_time));
+
+        VoteTimes { day, at: day.zip(time).map(|(d, t)| to_eastern(d, t).fixed_offset()), modified: None }
+    }
+
+    /// From a Senate `vote_date` and `modify_date`.
+    pub fn senate(vote_date: Option<&str>, modify_date: Option<&str>, year: Option<i32>) -> Self {
+        let vote = vote_date.and_then(|d| parse_datetime(d, year));
+        let modified = modify_date
+            .and_then(|d| parse_datetime(d, year))
+            .and_then(|(d, t)| Some(to_eastern(d, t?).fixed_offset()));
+
+        VoteTimes {
+            day: vote.map(|(d, _)| d),
+            at: vote.and_then(|(d, t)| Some(to_eastern(d, t?).fixed_offset())),
+            modified,
+        }
+    }
+
+    /// Seconds since the Unix epoch, for ordering votes by when they were held.
+    pub fn epoch(&self) -> Option<i64> {
+        self.at.map(|t| t.timestamp())
+    }
+
+    /// "2024-01-09".
+    pub fn day_string(&self) -> Option<String> {
+        self.day.map(|d| d.format("%Y-%m-%d").to_string())
+    }
+
+    /// RFC 3339 with the Eastern offset, "2024-01-09T17:31:00-05:00".
+    pub fn at_string(&self) -> Option<String> {
+        self.at.map(|t| t.to_rfc3339())
+    }
+
+    pub fn modified_string(&self) -> Option<String> {
+        self.modified.map(|t| t.to_rfc3339())
+    }
+}
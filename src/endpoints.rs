@@ -3,7 +3,10 @@
 // When displaying the Congress struct, it should return the URL to the roll call vote it
 // represents
 
-use crate::{CURRENT_SESSION, CURRENT_CONGRESS, CURRENT_ROLL, OLDEST_CONGRESS};
+use chrono::{Datelike, NaiveDate, Weekday};
+
+use crate::{Error, Result};
+use crate::OLDEST_CONGRESS;
 
 type CongressNumber = u32;
 type CongressSession = u32;
@@ -11,7 +14,8 @@ type RollCall = u32;
 
 type Chamber = CongressChamber;
 
-#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum CongressChamber {
     #[default]
     House,
@@ -19,20 +23,20 @@ pub enum CongressChamber {
 }
 
 impl std::str::FromStr for Chamber {
-    type Err = String;
+    type Err = Error;
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
+    fn from_str(s: &str) -> Result<Self> {
         match s.to_lowercase().as_str() {
             "house" => Ok(CongressChamber::House),
             "senate" => Ok(CongressChamber::Senate),
-            _ => Err(format!("Invalid chamber: {}", s)),
+            _ => Err(Error::parse("chamber", s)),
         }
     }
 }
 
-impl Into<Chamber> for &str {
-    fn into(self) -> Chamber {
-        match self.to_lowercase().as_str() {
+impl From<&str> for Chamber {
+    fn from(s: &str) -> Chamber {
+        match s.to_lowercase().as_str() {
             "house" => CongressChamber::House,
             "senate" => CongressChamber::Senate,
             _ => CongressChamber::House,
@@ -50,6 +54,8 @@ impl std::fmt::Display for Chamber {
 }
 
 impl Chamber {
+    // Display is the base URL; this is the lowercase name used in paths and the database
+    #[allow(clippy::inherent_to_string_shadow_display)]
     pub fn to_string(&self) -> String {
         match self {
             CongressChamber::House => "house".to_string(),
@@ -79,37 +85,29 @@ impl CongressEndpoint {
         }
     }
 
+    #[allow(clippy::should_implement_trait)]
     pub fn as_mut(&mut self) -> &mut Self {
         self
     }
 
-    pub fn current(chamber: &str) -> Self {
-        CongressFields {
-            chamber: chamber.into(),
-            number: CURRENT_CONGRESS,
-            session: CURRENT_SESSION,
-            roll_call: CURRENT_ROLL,
-        }
-    }
-
     pub fn chamber(&mut self, chamber: &str) -> &mut Self {
         let chamber = chamber.into();
         self.chamber = chamber;
         self
     }
 
-    pub fn number(&mut self, number: u32) -> Result<&mut Self, String> {
-        if number < OLDEST_CONGRESS || number > CURRENT_CONGRESS {
-            return Err(format!("Invalid Congress number: {}", number));
+    pub fn number(&mut self, number: u32) -> Result<&mut Self> {
+        if !(OLDEST_CONGRESS..=latest_congress()).contains(&number) {
+            return Err(Error::Congress { congress: number, session: self.session });
         }
 
         self.number = number;
         Ok(self)
     }
 
-    pub fn session(&mut self, session: u32) -> Result<&mut Self, String> {
-        if session != 1 && session != 2 {
-            return Err(format!("Invalid Congress session: {}", session));
+    pub fn session(&mut self, session: u32) -> Result<&mut Self> {
+        if session_start(self.number, session).is_none() {
+            return Err(Error::Congress { congress: self.number, session });
         }
 
         self.session = session;
@@ -125,69 +123,158 @@ impl CongressEndpoint {
         *self
     }
 
-    // Convert Congress number + session to year
-    pub fn to_year(&self) -> u32 {
-        match (self.number, self.session) {
-            (120, 2) => 2028,
-            (120, 1) => 2027,
-            (119, 2) => 2026,
-            (119, 1) => 2025,
-            (118, 2) => 2024,
-            (118, 1) => 2023,
-            (117, 2) => 2022,
-            (117, 1) => 2021,
-            (116, 2) => 2020,
-            (116, 1) => 2019,
-            (115, 2) => 2018,
-            (115, 1) => 2017,
-            (114, 2) => 2016,
-            (114, 1) => 2015,
-            (113, 2) => 2014,
-            (113, 1) => 2013,
-            (112, 2) => 2012,
-            (112, 1) => 2011,
-            (111, 2) => 2010,
-            (111, 1) => 2009,
-            (110, 2) => 2008,
-            (110, 1) => 2007,
-            (109, 2) => 2006,
-            (109, 1) => 2005,
-            (108, 2) => 2004,
-            (108, 1) => 2003,
-            (107, 2) => 2002,
-            (107, 1) => 2001,
-            (106, 2) => 2000,
-            (106, 1) => 1999,
-            (105, 2) => 1998,
-            (105, 1) => 1997,
-            (104, 2) => 1996,
-            (104, 1) => 1995,
-            (103, 2) => 1994,
-            (103, 1) => 1993,
-            (102, 2) => 1992,
-            (102, 1) => 1991,
-            (101, 2) => 1990,
-            (101, 1) => 1989,
-            _ => {
-                panic!("Invalid Congress number and session: {} {}", self.number, self.session);
-            }
+    /// Year the session convened, which is the year clerk.house.gov files its roll calls under.
+    pub fn to_year(&self) -> Result<u32> {
+        match session_start(self.number, self.session) {
+            Some(date) => Ok(date.year() as u32),
+            None => Err(Error::Congress { congress: self.number, session: self.session }),
         }
     }
+
+    /// Endpoint for the congress and session sitting on `date`.
+    pub fn for_date(chamber: &str, date: NaiveDate, roll_call: u32) -> Self {
+        let (number, session) = congress_for_date(date);
+        CongressEndpoint::new(chamber, number, session, roll_call)
+    }
 }
 
+/// First year of the 1st Congress; every congress after it spans two years.
+const FIRST_CONGRESS_YEAR: i32 = 1789;
 
+/// First congress to meet on January 3 (20th Amendment). Earlier congresses began on March 4.
+const FIRST_JANUARY_CONGRESS: u32 = 74;
 
-impl std::fmt::Display for CongressEndpoint {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+/// Sessions that don't follow the usual pattern: special sessions called after
+/// the March 4 inauguration, third and fourth sessions, and the late-1930s
+/// sessions called in the fall. `(congress, session, year, month, day)` of the
+/// day each session convened. Congresses listed here have exactly the sessions listed.
+const IRREGULAR_SESSIONS: &[(u32, u32, i32, u32, u32)] = &[
+    (1, 1, 1789, 3, 4), (1, 2, 1790, 1, 4), (1, 3, 1790, 12, 6),
+    (5, 1, 1797, 5, 15), (5, 2, 1797, 11, 13), (5, 3, 1798, 12, 3),
+    (13, 1, 1813, 5, 24), (13, 2, 1813, 12, 6), (13, 3, 1814, 9, 19),
+    (25, 1, 1837, 9, 4), (25, 2, 1837, 12, 4), (25, 3, 1838, 12, 3),
+    (27, 1, 1841, 5, 31), (27, 2, 1841, 12, 6), (27, 3, 1842, 12, 5),
+    (40, 1, 1867, 3, 4), (40, 2, 1867, 12, 2), (40, 3, 1868, 12, 7),
+    (41, 1, 1869, 3, 4), (41, 2, 1869, 12, 6), (41, 3, 1870, 12, 5),
+    (42, 1, 1871, 3, 4), (42, 2, 1871, 12, 4), (42, 3, 1872, 12, 2),
+    (45, 1, 1877, 10, 15), (45, 2, 1877, 12, 3), (45, 3, 1878, 12, 2),
+    (46, 1, 1879, 3, 18), (46, 2, 1879, 12, 1), (46, 3, 1880, 12, 6),
+    (53, 1, 1893, 8, 7), (53, 2, 1893, 12, 4), (53, 3, 1894, 12, 3),
+    (55, 1, 1897, 3, 15), (55, 2, 1897, 12, 6), (55, 3, 1898, 12, 5),
+    (58, 1, 1903, 11, 9), (58, 2, 1903, 12, 7), (58, 3, 1904, 12, 5),
+    (61, 1, 1909, 3, 15), (61, 2, 1909, 12, 6), (61, 3, 1910, 12, 5),
+    (62, 1, 1911, 4, 4), (62, 2, 1911, 12, 4), (62, 3, 1912, 12, 2),
+    (63, 1, 1913, 4, 7), (63, 2, 1913, 12, 1), (63, 3, 1914, 12, 7),
+    (65, 1, 1917, 4, 2), (65, 2, 1917, 12, 3), (65, 3, 1918, 12, 2),
+    (66, 1, 1919, 5, 19), (66, 2, 1919, 12, 1), (66, 3, 1920, 12, 6),
+    (67, 1, 1921, 4, 11), (67, 2, 1921, 12, 5), (67, 3, 1922, 11, 20), (67, 4, 1922, 12, 4),
+    (71, 1, 1929, 4, 15), (71, 2, 1929, 12, 2), (71, 3, 1930, 12, 1),
+    (73, 1, 1933, 3, 9), (73, 2, 1934, 1, 3),
+    (75, 1, 1937, 1, 5), (75, 2, 1937, 11, 15), (75, 3, 1938, 1, 3),
+    (76, 1, 1939, 1, 3), (76, 2, 1939, 9, 21), (76, 3, 1940, 1, 3),
+];
+
+/// Year the `congress`th Congress began.
+fn congress_start_year(congress: u32) -> i32 {
+    FIRST_CONGRESS_YEAR + 2 * (congress as i32 - 1)
+}
+
+/// Day the `congress`th Congress's term began: March 4 until the 73rd, January 3 from the 74th.
+pub fn term_start(congress: u32) -> Option<NaiveDate> {
+    if congress == 0 {
+        return None;
+    }
+
+    let year = congress_start_year(congress);
+    if congress < FIRST_JANUARY_CONGRESS {
+        NaiveDate::from_ymd_opt(year, 3, 4)
+    } else {
+        NaiveDate::from_ymd_opt(year, 1, 3)
+    }
+}
+
+/// Day a session convened. Regular sessions met on the first Monday in December
+/// before the 74th Congress and on January 3 since; see `IRREGULAR_SESSIONS` for the rest.
+pub fn session_start(congress: u32, session: u32) -> Option<NaiveDate> {
+    if congress == 0 || session == 0 {
+        return None;
+    }
+
+    if IRREGULAR_SESSIONS.iter().any(|&(c, ..)| c == congress) {
+        return IRREGULAR_SESSIONS
+            .iter()
+            .find(|&&(c, s, ..)| c == congress && s == session)
+            .and_then(|&(_, _, year, month, day)| NaiveDate::from_ymd_opt(year, month, day));
+    }
+
+    if session > 2 {
+        return None;
+    }
+
+    let year = congress_start_year(congress) + session as i32 - 1;
+    if congress < FIRST_JANUARY_CONGRESS {
+        NaiveDate::from_weekday_of_month_opt(year, 12, Weekday::Mon, 1)
+    } else {
+        NaiveDate::from_ymd_opt(year, 1, 3)
+    }
+}
+
+/// Congress and session sitting on `date`. Between the start of a term and its
+/// first session (e.g. March to December before 1935) the session is 1.
+pub fn congress_for_date(date: NaiveDate) -> (u32, u32) {
+    let offset = (date.year() - FIRST_CONGRESS_YEAR).max(0);
+    let mut congress = (offset / 2 + 1) as u32;
+
+    if congress > 1 && term_start(congress).is_some_and(|start| date < start) {
+        congress -= 1;
+    }
+
+    let session = (1..=4)
+        .take_while(|&s| session_start(congress, s).is_some_and(|start| start <= date))
+        .last()
+        .unwrap_or(1);
+
+    (congress, session)
+}
+
+/// Congress sitting today, the newest one that can have roll calls.
+pub fn latest_congress() -> u32 {
+    congress_for_date(chrono::Utc::now().date_naive()).0
+}
+
+impl CongressEndpoint {
+    /// URL of the roll call XML on clerk.house.gov or senate.gov.
+    pub fn url(&self) -> Result<String> {
         match self.chamber {
             CongressChamber::House => {
-                write!(f, "{}{}/roll{roll:0>3}.xml", self.chamber, self.to_year(), roll = self.roll_call)
+                Ok(format!("{}{}/roll{roll:0>3}.xml", self.chamber, self.to_year()?, roll = self.roll_call))
             }
             CongressChamber::Senate => {
-                write!(f, "{}vote{congress}{session}/vote_{congress}_{session}_{roll:0>5}.xml", self.chamber, congress = self.number, session = self.session, roll = self.roll_call)
+                Ok(format!("{}vote{congress}{session}/vote_{congress}_{session}_{roll:0>5}.xml", self.chamber, congress = self.number, session = self.session, roll = self.roll_call))
             }
         }
     }
 }
 
+impl CongressEndpoint {
+    /// URL listing the session's roll calls: the House EVS `index.asp` for the year,
+    /// or the Senate `vote_menu` XML for the congress and session.
+    pub fn index_url(&self) -> Result<String> {
+        match self.chamber {
+            CongressChamber::House => Ok(format!("{}{}/index.asp", self.chamber, self.to_year()?)),
+            CongressChamber::Senate => {
+                Ok(format!("https://www.senate.gov/legislative/LIS/roll_call_lists/vote_menu_{}_{}.xml", self.number, self.session))
+            }
+        }
+    }
+}
 
+impl std::fmt::Display for CongressEndpoint {
+    // Use `url()` to handle endpoints without a known year; this writes the error instead
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.url() {
+            Ok(url) => write!(f, "{}", url),
+            Err(e) => write!(f, "{}", e),
+        }
+    }
+}
@@ -0,0 +1,195 @@
+// error.rs
+//
+// Single error type for the crate. Variants carry whatever context is known
+// where the failure happens (URL, file path, roll call); callers higher up can
+// attach a file path with `Error::with_path`.
+
+use std::path::{Path, PathBuf};
+
+use crate::endpoints::CongressEndpoint;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug)]
+pub enum Error {
+    /// A request to house.gov or senate.gov failed, or returned an error status.
+    Network {
+        url: String,
+        status: Option<u16>,
+        source: Option<reqwest::Error>,
+    },
+    /// The HTTP client, a progress bar or another piece of setup could not be configured.
+    Config { message: String },
+    /// A field in a roll call document or on the command line has an unexpected value.
+    Parse {
+        path: Option<PathBuf>,
+        roll_call: Option<CongressEndpoint>,
+        field: String,
+        value: String,
+    },
+    /// A document, or the database, does not have the expected structure.
+    Schema {
+        path: Option<PathBuf>,
+        roll_call: Option<CongressEndpoint>,
+        message: String,
+    },
+    /// A path does not follow the `{congress}/{chamber}/{session}/{year}_{roll}` layout.
+    Path { path: PathBuf, message: String },
+    /// No year is known for this congress and session.
+    Congress { congress: u32, session: u32 },
+    /// Reading or writing a file failed.
+    Io { path: PathBuf, source: std::io::Error },
+    /// SQLite returned an error.
+    Database {
+        path: Option<PathBuf>,
+        roll_call: Option<CongressEndpoint>,
+        source: rusqlite::Error,
+    },
+    /// A background task panicked or was cancelled before it finished.
+    Task { task: String, source: tokio::task::JoinError },
+    /// A statistical estimate could not be made from the data, e.g. too few votes or a singular system.
+    Estimation { message: String },
+}
+
+impl Error {
+    pub fn network(url: &str, source: reqwest::Error) -> Self {
+        Error::Network {
+            url: url.to_string(),
+            status: source.status().map(|s| s.as_u16()),
+            source: Some(source),
+        }
+    }
+
+    pub fn parse(field: &str, value: &str) -> Self {
+        Error::Parse {
+            path: None,
+            roll_call: None,
+            field: field.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    pub fn schema(message: impl Into<String>) -> Self {
+        Error::Schema {
+            path: None,
+            roll_call: None,
+            message: message.into(),
+        }
+    }
+
+    pub fn io(path: &Path, source: std::io::Error) -> Self {
+        Error::Io {
+            path: path.to_path_buf(),
+            source,
+        }
+    }
+
+    pub fn task(task: &str, source: tokio::task::JoinError) -> Self {
+        Error::Task {
+            task: task.to_string(),
+            source,
+        }
+    }
+
+    /// Records the file being processed, if the error does not name one yet.
+    pub fn with_path(mut self, file: &Path) -> Self {
+        match &mut self {
+            Error::Parse { path, .. } | Error::Schema { path, .. } | Error::Database { path, .. } if path.is_none() => {
+                *path = Some(file.to_path_buf());
+            }
+            _ => {}
+        }
+        self
+    }
+
+    /// Records the roll call being processed, if the error does not name one yet.
+    pub fn with_roll_call(mut self, endpoint: CongressEndpoint) -> Self {
+        match &mut self {
+            Error::Parse { roll_call, .. } | Error::Schema { roll_call, .. } | Error::Database { roll_call, .. }
+                if roll_call.is_none() =>
+            {
+                *roll_call = Some(endpoint);
+            }
+            _ => {}
+        }
+        self
+    }
+}
+
+/// Formats the optional context shared by several variants, e.g. " (senate 118-2 #1, data/json/...)".
+fn context(path: &Option<PathBuf>, roll_call: &Option<CongressEndpoint>) -> String {
+    let mut parts = vec![];
+    if let Some(rc) = roll_call {
+        parts.push(format!("{} {}-{} #{}", rc.chamber.to_string(), rc.number, rc.session, rc.roll_call));
+    }
+    if let Some(p) = path {
+        parts.push(p.display().to_string());
+    }
+
+    if parts.is_empty() {
+        String::new()
+    } else {
+        format!(" ({})", parts.join(", "))
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Error::Network { url, status: Some(status), .. } => {
+                write!(f, "Request to {} failed with status {}", url, status)
+            }
+            Error::Network { url, source: Some(source), .. } => {
+                write!(f, "Request to {} failed: {}", url, source)
+            }
+            Error::Network { url, .. } => write!(f, "Request to {} failed", url),
+            Error::Config { message } => write!(f, "Configuration error: {}", message),
+            Error::Parse { path, roll_call, field, value } => {
+                write!(f, "Invalid {} {:?}{}", field, value, context(path, roll_call))
+            }
+            Error::Schema { path, roll_call, message } => {
+                write!(f, "{}{}", message, context(path, roll_call))
+            }
+            Error::Path { path, message } => write!(f, "{}: {}", message, path.display()),
+            Error::Congress { congress, session } => {
+                write!(f, "Invalid Congress number and session: {} {}", congress, session)
+            }
+            Error::Io { path, source } => write!(f, "{}: {}", path.display(), source),
+            Error::Database { path, roll_call, source } => {
+                write!(f, "Database error: {}{}", source, context(path, roll_call))
+            }
+            Error::Task { task, source } => write!(f, "{} task failed: {}", task, source),
+            Error::Estimation { message } => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Network { source: Some(source), .. } => Some(source),
+            Error::Io { source, .. } => Some(source),
+            Error::Database { source, .. } => Some(source),
+            Error::Task { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<rusqlite::Error> for Error {
+    fn from(source: rusqlite::Error) -> Self {
+        Error::Database {
+            path: None,
+            roll_call: None,
+            source,
+        }
+    }
+}
+
+impl From<indicatif::style::TemplateError> for Error {
+    fn from(e: indicatif::style::TemplateError) -> Self {
+        Error::Config {
+            message: format!("Invalid progress bar template: {}", e),
+        }
+    }
+}
@@ -0,0 +1,467 @@
+// ideal_points.rs
+//
+// One- and two-dimensional ideal points from a `VoteMatrix`, with the EM
+// algorithm for the probit item response model of emIRT (Imai, Lo and Olmsted).
+// Each roll call j has an intercept a_j and a discrimination vector b_j, each
+// member a position x_i, and a member votes yea when the utility
+// a_j + b_j·x_i + e (e standard normal) is positive. The E step replaces each
+// vote by its expected utility; the M steps are then small ridge regressions,
+// roll calls on positions and positions on roll calls, under a standard normal
+// prior on positions and a weak prior on roll call parameters.
+//
+// Positions start from the principal components of the vote matrix. Once
+// converged they are rotated to principal axes so the first dimension carries
+// the most variance, scaled to mean 0 and standard deviation 1 on every
+// dimension, and oriented so Republicans have the higher mean. Standard errors
+// are the posterior spread of each position given the roll call parameters.
+// Present and not-voting ballots are left out, as are near-unanimous roll calls
+// and members with few votes.
+
+use rusqlite::params;
+use serde::Serialize;
+
+use crate::endpoints::CongressChamber;
+use crate::matrix::{MemberLabel, VoteMatrix};
+use crate::{Error, Result};
+
+/// A yea or nay vote: (member, roll call, +1 or -1), indexed into the members and roll calls kept.
+type Vote = (usize, usize, f64);
+
+/// Prior precision of roll call intercepts and discriminations (a standard deviation of 5).
+const ROLLCALL_PRIOR: f64 = 0.04;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IdealPointOptions {
+    /// 1 or 2.
+    pub dimensions: usize,
+    pub max_iterations: usize,
+    /// Stop once positions correlate above `1 - tolerance` with the previous iteration's, on every dimension.
+    pub tolerance: f64,
+    /// Members with fewer yea/nay votes on the roll calls kept are not scaled.
+    pub min_votes: usize,
+    /// Roll calls whose losing side has a smaller share than this are not used (NOMINATE uses 2.5%).
+    pub min_minority: f64,
+}
+
+impl Default for IdealPointOptions {
+    fn default() -> Self {
+        IdealPointOptions { dimensions: 1, max_iterations: 500, tolerance: 1e-6, min_votes: 20, min_minority: 0.025 }
+    }
+}
+
+/// A member's estimated position.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct IdealPoint {
+    pub member: MemberLabel,
+    /// Yea and nay votes the position is estimated from.
+    pub votes: u32,
+    pub coordinates: Vec<f64>,
+    pub standard_errors: Vec<f64>,
+    /// Share of the member's votes the model predicts correctly.
+    pub classified: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IdealPointFit {
+    pub dimensions: usize,
+    pub points: Vec<IdealPoint>,
+    /// Roll calls left after dropping near-unanimous ones.
+    pub rollcalls: usize,
+    pub iterations: usize,
+    pub converged: bool,
+    /// Share of all votes predicted correctly.
+    pub classified: f64,
+}
+
+impl IdealPointFit {
+    pub fn point(&self, member_id: &str) -> Option<&IdealPoint> {
+        self.points.iter().find(|p| p.member.member_id == member_id)
+    }
+}
+
+/// Solves `a x = b` for a small symmetric positive definite `a` by Gaussian elimination.
+fn solve(mut a: Vec<Vec<f64>>, mut b: Vec<f64>) -> Option<Vec<f64>> {
+    let n = b.len();
+
+    for col in 0..n {
+        let pivot = (col..n).max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))?;
+        if a[pivot][col].abs() < 1e-12 {
+            return None;
+        }
+        a.swap(col, pivot);
+        b.swap(col, pivot);
+
+        for row in col + 1..n {
+            let factor = a[row][col] / a[col][col];
+            let (upper, lower) = a.split_at_mut(row);
+            for (x, p) in lower[0][col..].iter_mut().zip(&upper[col][col..]) {
+                *x -= factor * p;
+            }
+            b[row] -= factor * b[col];
+        }
+    }
+
+    let mut x = vec![0.0; n];
+    for row in (0..n).rev() {
+        let sum: f64 = (row + 1..n).map(|k| a[row][k] * x[k]).sum();
+        x[row] = (b[row] - sum) / a[row][row];
+    }
+    Some(x)
+}
+
+/// Diagonal of the inverse of a small symmetric positive definite matrix.
+fn inverse_diagonal(a: &[Vec<f64>]) -> Option<Vec<f64>> {
+    let n = a.len();
+    (0..n)
+        .map(|i| {
+            let mut e = vec![0.0; n];
+            e[i] = 1.0;
+            solve(a.to_vec(), e).map(|column| column[i])
+        })
+        .collect()
+}
+
+fn dot(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+/// Yea/nay votes as `(member, roll call, ±1)`, after dropping lopsided roll calls and members with few votes.
+fn observations(matrix: &VoteMatrix, options: &IdealPointOptions) -> (Vec<usize>, Vec<usize>, Vec<Vote>) {
+    let rollcalls: Vec<usize> = (0..matrix.columns())
+        .filter(|&j| {
+            let (yea, nay) = (0..matrix.rows()).fold((0, 0), |(yea, nay), i| match matrix.get(i, j) {
+                Some(1) => (yea + 1, nay),
+                Some(-1) => (yea, nay + 1),
+                _ => (yea, nay),
+            });
+            yea + nay > 0 && yea.min(nay) as f64 / (yea + nay) as f64 >= options.min_minority
+        })
+        .collect();
+
+    let members: Vec<usize> = (0..matrix.rows())
+        .filter(|&i| rollcalls.iter().filter(|&&j| matches!(matrix.get(i, j), Some(1) | Some(-1))).count() >= options.min_votes)
+        .collect();
+
+    let mut votes = vec![];
+    for (mi, &i) in members.iter().enumerate() {
+        for (rj, &j) in rollcalls.iter().enumerate() {
+            if let Some(value @ (1 | -1)) = matrix.get(i, j) {
+                votes.push((mi, rj, value as f64));
+            }
+        }
+    }
+
+    (members, rollcalls, votes)
+}
+
+/// Starting positions: the leading principal components of the member × roll call
+/// matrix, centred on each roll call's mean, by power iteration.
+fn principal_components(n: usize, m: usize, votes: &[Vote], dimensions: usize) -> Vec<Vec<f64>> {
+    let mut means = vec![(0.0, 0.0); m];
+    for &(_, j, y) in votes {
+        means[j].0 += y;
+        means[j].1 += 1.0;
+    }
+    let centred: Vec<Vote> = votes.iter().map(|&(i, j, y)| (i, j, y - means[j].0 / means[j].1)).collect();
+
+    let mut components: Vec<Vec<f64>> = vec![];
+    for d in 0..dimensions {
+        // Deterministic start that isn't orthogonal to the components in practice
+        let mut u: Vec<f64> = (0..n).map(|i| 1.0 + ((i * (d + 7)) % 13) as f64 / 13.0).collect();
+
+        for _ in 0..200 {
+            let mut v = vec![0.0; m];
+            for &(i, j, y) in &centred {
+                v[j] += y * u[i];
+            }
+            let mut next = vec![0.0; n];
+            for &(i, j, y) in &centred {
+                next[i] += y * v[j];
+            }
+
+            for component in &components {
+                let overlap = dot(&next, component);
+                next.iter_mut().zip(component).for_each(|(x, c)| *x -= overlap * c);
+            }
+
+            let norm = dot(&next, &next).sqrt();
+            if norm < 1e-12 {
+                break;
+            }
+            u = next.into_iter().map(|x| x / norm).collect();
+        }
+
+        components.push(u);
+    }
+
+    (0..n).map(|i| components.iter().map(|c| c[i]).collect()).collect()
+}
+
+/// Pearson correlation of dimension `d` of two sets of positions.
+fn correlation(x: &[Vec<f64>], y: &[Vec<f64>], d: usize) -> f64 {
+    let n = x.len() as f64;
+    let (mx, my) = (x.iter().map(|p| p[d]).sum::<f64>() / n, y.iter().map(|p| p[d]).sum::<f64>() / n);
+    let (mut sxy, mut sxx, mut syy) = (0.0, 0.0, 0.0);
+    for (p, q) in x.iter().zip(y) {
+        sxy += (p[d] - mx) * (q[d] - my);
+        sxx += (p[d] - mx).powi(2);
+        syy += (q[d] - my).powi(2);
+    }
+    sxy / (sxx * syy).sqrt()
+}
+
+/// Centres every dimension on 0 and scales it to standard deviation 1.
+fn standardize(x: &mut [Vec<f64>]) {
+    let n = x.len() as f64;
+    for d in 0..x.first().map_or(0, |p| p.len()) {
+        let mean = x.iter().map(|p| p[d]).sum::<f64>() / n;
+        let sd = (x.iter().map(|p| (p[d] - mean).powi(2)).sum::<f64>() / n).sqrt();
+        for p in x.iter_mut() {
+            p[d] = if sd > 1e-12 { (p[d] - mean) / sd } else { 0.0 };
+        }
+    }
+}
+
+/// Rotates two-dimensional positions about their mean onto their principal axes.
+fn rotate_to_principal_axes(x: &mut [Vec<f64>]) {
+    let n = x.len() as f64;
+    let (mx, my) = (x.iter().map(|p| p[0]).sum::<f64>() / n, x.iter().map(|p| p[1]).sum::<f64>() / n);
+    let (sxx, syy, sxy) = x.iter().fold((0.0, 0.0, 0.0), |(sxx, syy, sxy), p| {
+        let (u, v) = (p[0] - mx, p[1] - my);
+        (sxx + u * u, syy + v * v, sxy + u * v)
+    });
+
+    let angle = 0.5 * (2.0 * sxy).atan2(sxx - syy);
+    let (sin, cos) = angle.sin_cos();
+
+    for p in x.iter_mut() {
+        let (u, v) = (p[0] - mx, p[1] - my);
+        p[0] = u * cos + v * sin;
+        p[1] = -u * sin + v * cos;
+    }
+}
+
+/// Roll calls given positions: regresses each roll call's utilities on [1, x].
+/// Returns the intercepts and discriminations.
+fn fit_rollcalls(x: &[Vec<f64>], latent: &[f64], by_rollcall: &[Vec<(usize, usize)>], k: usize) -> Result<(Vec<f64>, Vec<Vec<f64>>)> {
+    let mut a = Vec::with_capacity(by_rollcall.len());
+    let mut b = Vec::with_capacity(by_rollcall.len());
+
+    for observed in by_rollcall {
+        let mut lhs = vec![vec![0.0; k + 1]; k + 1];
+        let mut rhs = vec![0.0; k + 1];
+        for &(i, o) in observed {
+            let z: Vec<f64> = std::iter::once(1.0).chain(x[i].iter().copied()).collect();
+            for r in 0..=k {
+                rhs[r] += latent[o] * z[r];
+                for c in 0..=k {
+                    lhs[r][c] += z[r] * z[c];
+                }
+            }
+        }
+        (0..=k).for_each(|r| lhs[r][r] += ROLLCALL_PRIOR);
+
+        let solution = solve(lhs, rhs).ok_or_else(singular)?;
+        a.push(solution[0]);
+        b.push(solution[1..].to_vec());
+    }
+
+    Ok((a, b))
+}
+
+/// Standard normal distribution function, from the complementary error function
+/// (Numerical Recipes' `erfcc`, relative error below 1.2e-7).
+fn normal_cdf(x: f64) -> f64 {
+    let z = x.abs() / std::f64::consts::SQRT_2;
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let poly = -z * z - 1.26551223
+        + t * (1.00002368
+            + t * (0.37409196
+                + t * (0.09678418
+                    + t * (-0.18628806 + t * (0.27886807 + t * (-1.13520398 + t * (1.48851587 + t * (-0.82215223 + t * 0.17087277))))))));
+    let tail = 0.5 * t * poly.exp();
+    if x >= 0.0 {
+        1.0 - tail
+    } else {
+        tail
+    }
+}
+
+/// Mean of a unit-variance normal utility centred on `mean`, given which side of 0 it fell on.
+fn expected_utility(mean: f64, yea: bool) -> f64 {
+    let density = (-0.5 * mean * mean).exp() / (2.0 * std::f64::consts::PI).sqrt();
+    let (sign, mass) = if yea { (1.0, normal_cdf(mean)) } else { (-1.0, normal_cdf(-mean)) };
+
+    // Far in the wrong tail the ratio tends to |mean|
+    let ratio = if mass > 1e-12 { density / mass } else { mean.abs() };
+    mean + sign * ratio
+}
+
+/// The normal equations of a step could not be solved.
+fn singular() -> Error {
+    Error::Estimation { message: "Ideal point estimation is singular".to_string() }
+}
+
+/// Estimates ideal points for the members of `matrix`. Meant for one chamber and congress at a time.
+pub fn estimate(matrix: &VoteMatrix, options: &IdealPointOptions) -> Result<IdealPointFit> {
+    let k = options.dimensions;
+    if !(1..=2).contains(&k) {
+        return Err(Error::Config { message: format!("Ideal points are estimated in 1 or 2 dimensions, not {}", k) });
+    }
+
+    let (members, rollcalls, votes) = observations(matrix, options);
+    let (n, m) = (members.len(), rollcalls.len());
+    if n < k + 2 || m < k + 1 {
+        return Err(Error::Estimation {
+            message: format!("Too little data to scale: {} members and {} contested roll calls", n, m),
+        });
+    }
+
+    // Observations are referenced by index into `votes` and `latent`
+    let mut by_member: Vec<Vec<(usize, usize)>> = vec![vec![]; n];
+    let mut by_rollcall: Vec<Vec<(usize, usize)>> = vec![vec![]; m];
+    for (o, &(i, j, _)) in votes.iter().enumerate() {
+        by_member[i].push((j, o));
+        by_rollcall[j].push((i, o));
+    }
+
+    let mut x = principal_components(n, m, &votes, k);
+    standardize(&mut x);
+    let mut latent: Vec<f64> = votes.iter().map(|&(_, _, y)| y).collect();
+
+    let mut iterations = 0;
+    let mut converged = false;
+    while iterations < options.max_iterations {
+        iterations += 1;
+
+        let (a, b) = fit_rollcalls(&x, &latent, &by_rollcall, k)?;
+
+        // Members given roll calls: regress each member's utilities, less the intercepts, on b
+        let mut next = vec![vec![0.0; k]; n];
+        for i in 0..n {
+            let mut lhs = vec![vec![0.0; k]; k];
+            let mut rhs = vec![0.0; k];
+            for &(j, o) in &by_member[i] {
+                for r in 0..k {
+                    rhs[r] += (latent[o] - a[j]) * b[j][r];
+                    for c in 0..k {
+                        lhs[r][c] += b[j][r] * b[j][c];
+                    }
+                }
+            }
+            (0..k).for_each(|r| lhs[r][r] += 1.0);
+
+            next[i] = solve(lhs, rhs).ok_or_else(singular)?;
+        }
+
+        // E step: expected utilities given the votes
+        for (o, &(i, j, y)) in votes.iter().enumerate() {
+            latent[o] = expected_utility(a[j] + dot(&b[j], &next[i]), y > 0.0);
+        }
+
+        let stable = (0..k).all(|d| correlation(&x, &next, d) > 1.0 - options.tolerance);
+        x = next;
+
+        if stable {
+            converged = true;
+            break;
+        }
+    }
+
+    // Identify the space, then fit the roll calls to the final positions
+    if k == 2 {
+        rotate_to_principal_axes(&mut x);
+    }
+    standardize(&mut x);
+    let (a, mut b) = fit_rollcalls(&x, &latent, &by_rollcall, k)?;
+
+    for d in 0..k {
+        let party_mean = |code: &str| {
+            let (sum, count) = members
+                .iter()
+                .enumerate()
+                .filter(|(_, &row)| matrix.members[row].party.as_deref() == Some(code))
+                .fold((0.0, 0), |(sum, count), (i, _)| (sum + x[i][d], count + 1));
+            (count > 0).then(|| sum / count as f64)
+        };
+
+        if let (Some(democrats), Some(republicans)) = (party_mean("D"), party_mean("R")) {
+            if republicans < democrats {
+                x.iter_mut().for_each(|p| p[d] = -p[d]);
+                b.iter_mut().for_each(|p| p[d] = -p[d]);
+            }
+        }
+    }
+
+    // How many votes the model gets right
+    let correct = |i: usize, j: usize, y: f64| (a[j] + dot(&b[j], &x[i]) >= 0.0) == (y > 0.0);
+
+    let mut points = Vec::with_capacity(n);
+    for i in 0..n {
+        let mut precision = vec![vec![0.0; k]; k];
+        for &(j, _) in &by_member[i] {
+            for r in 0..k {
+                for c in 0..k {
+                    precision[r][c] += b[j][r] * b[j][c];
+                }
+            }
+        }
+        (0..k).for_each(|r| precision[r][r] += 1.0);
+
+        let variances = inverse_diagonal(&precision).ok_or_else(singular)?;
+        let right = by_member[i].iter().filter(|&&(j, o)| correct(i, j, votes[o].2)).count();
+
+        points.push(IdealPoint {
+            member: matrix.members[members[i]].clone(),
+            votes: by_member[i].len() as u32,
+            coordinates: x[i].clone(),
+            standard_errors: variances.iter().map(|v| v.sqrt()).collect(),
+            classified: right as f64 / by_member[i].len() as f64,
+        });
+    }
+
+    let right = votes.iter().filter(|&&(i, j, y)| correct(i, j, y)).count();
+
+    Ok(IdealPointFit {
+        dimensions: k,
+        points,
+        rollcalls: m,
+        iterations,
+        converged,
+        classified: right as f64 / votes.len() as f64,
+    })
+}
+
+/// Replaces the chamber and congress's ideal points in `fit.dimensions` dimensions.
+pub fn insert_ideal_points(tx: &rusqlite::Transaction<'_>, chamber: CongressChamber, congress: u16, fit: &IdealPointFit) -> Result<()> {
+    tx.execute(
+        "DELETE FROM member_ideal_points WHERE chamber = ?1 AND congress = ?2 AND dimensions = ?3",
+        params![chamber.to_string(), congress, fit.dimensions],
+    )?;
+
+    for point in &fit.points {
+        tx.execute(
+            "INSERT INTO member_ideal_points (
+                chamber, congress, member_id, dimensions, name, party, state, votes,
+                coord1, coord2, se1, se2, classified
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+            params![
+                chamber.to_string(),
+                congress,
+                point.member.member_id,
+                fit.dimensions,
+                point.member.name,
+                point.member.party,
+                point.member.state,
+                point.votes,
+                point.coordinates[0],
+                point.coordinates.get(1),
+                point.standard_errors[0],
+                point.standard_errors.get(1),
+                point.classified,
+            ],
+        )?;
+    }
+
+    Ok(())
+}
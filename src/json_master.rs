@@ -1,84 +1,87 @@
 use indicatif::{ProgressBar, ProgressStyle};
+use crate::dates::VoteTimes;
 use crate::responses::*;
-use std::io::{self, Write};
+use crate::model::{text, RollCall, RollCallId};
+use crate::pipeline::{collect_vote_files, parse_in_order, PathMismatch, VoteFileEntry};
+use crate::workspace::Workspace;
+use crate::xml_reader::{load_vote_file, VoteFormat};
+use crate::{Error, Result};
+use std::collections::BTreeMap;
+use std::io::{self, BufWriter, Write};
 use std::fs::File;
-use walkdir::WalkDir;
-use std::error::Error;
-use lazy_static::lazy_static;
-use std::sync::Arc;
-use tokio::sync::Mutex;
-
-
-lazy_static! {
-    pub static ref JSON_FILE_MUTEX: Arc<Mutex<()>> = Arc::new(Mutex::new(()));
-}
+use std::path::PathBuf;
+
+/// Reads one vote file into a `UnifiedVote` per legislator, from its typed `RollCall`.
+/// The roll call is identified by the document; a path that disagrees with it is returned alongside.
+pub fn read_unified_votes(entry: &VoteFileEntry) -> Result<(RollCallId, Vec<UnifiedVote>, Option<PathMismatch>)> {
+    let file = load_vote_file(&entry.path)?;
+    let (id, mismatch) = entry.identify(&file)?;
+    let rollcall = RollCall::try_from(&file).map_err(|e| e.with_path(&entry.path))?;
+
+    // The date as published, and parsed
+    let (vote_date, times) = match &file {
+        VoteFile::HouseFile(house_file) => {
+            let metadata = &house_file.rollcall_vote.vote_metadata;
+            let action_date = text(metadata.action_date.as_text().as_deref());
+            let action_time = metadata.action_time.clone().unwrap_or_default();
+            let times = VoteTimes::house(
+                action_date.as_deref(),
+                action_time.time_etz.as_deref(),
+                action_time.content.as_deref(),
+                Some(id.year as i32),
+            );
+            (action_date, times)
+        }
+        VoteFile::SenateFile(senate_file) => {
+            let vote_date = text(senate_file.roll_call_vote.vote_date.as_text().as_deref());
+            let times = VoteTimes::senate(vote_date.as_deref(), None, Some(id.year as i32));
+            (vote_date, times)
+        }
+    };
 
-/// Inserts a `UnifiedVote` into the `CombinedData` structure based on the specified mode.
-pub async fn insert_into_database(
-    vote: &UnifiedVote,
-    combined_data: &mut CombinedData,
-) -> Result<(), Box<dyn Error>> {
-    // Ensure the chamber exists
-    let chamber_entry = combined_data
-        .chambers
-        .entry(vote.chamber.to_lowercase())
-        .or_insert_with(ChamberData::default);
-
-    // Ensure the congress exists
-    let congress_entry = chamber_entry
-        .congresses
-        .entry(vote.congress_number)
-        .or_insert_with(CongressData::default);
-
-    // Ensure the session exists
-    let session_entry = congress_entry
-        .sessions
-        .entry(vote.session_number)
-        .or_insert_with(SessionData::default);
-
-    // Find the RollCallData for the given rollcall_number
-    if let Some(rollcall_data) = session_entry.rollcalls.iter_mut().find(|rc| rc.rollcall_number == vote.rollcall_number) {
-       // Append the vote to the existing RollCallData
-       rollcall_data.vote_casts.push(vote.clone());
-    } else {
-       // Create a new RollCallData and add it to the sessions
-       let new_rollcall = RollCallData {
-           rollcall_number: vote.rollcall_number,
-           vote_date: vote.vote_date.clone(),
-           vote_question: vote.vote_question.clone(),
-           vote_result: vote.vote_result.clone(),
-           vote_casts: vec![vote.clone()],
-       };
-       session_entry.rollcalls.push(new_rollcall);
+    if rollcall.ballots.is_empty() {
+        println!("⚠️ No ballots found in file: {}", entry.path.display());
     }
 
-    println!(
-        "✅ Added vote to {} -> {} -> {} -> {}: {:?}",
-        vote.chamber, vote.congress_number, vote.session_number, vote.rollcall_number, vote
-    );
+    let votes = rollcall
+        .ballots
+        .iter()
+        .map(|ballot| UnifiedVote {
+            congress_number: id.congress,
+            chamber: id.chamber.to_string(),
+            session_number: id.session,
+            rollcall_number: id.number,
+            vote_date: vote_date.clone(),
+            vote_day: times.day_string(),
+            vote_timestamp: times.at_string(),
+            vote_question: rollcall.question.clone(),
+            vote_result: rollcall.result.clone(),
+            legislator_id: ballot.member.id().map(str::to_string),
+            legislator_name: ballot.member.full_name.clone().or_else(|| ballot.member.last_name.clone()),
+            party: ballot.member.party.as_ref().map(|p| p.code().to_string()),
+            state: ballot.member.state.map(|s| s.code().to_string()),
+            vote_cast: ballot.cast.as_ref().map(|c| c.to_string()),
+        })
+        .collect();
+
+    Ok((id, votes, mismatch))
+}
 
-    Ok(())
+/// Processes all vote files of `format` in the workspace's data directory and aggregates them into `CombinedData`.
+/// Files are parsed on `workers` threads (0 for one per core) and added in path order.
+/// This holds the whole corpus in memory; use `write_vote_files_json` to build `votes.json`.
+pub async fn process_vote_files_json(workspace: &Workspace, format: VoteFormat, workers: usize) -> Result<CombinedData> {
+    let workspace = workspace.clone();
+    tokio::task::spawn_blocking(move || build_combined_data(&workspace, format, workers))
+        .await
+        .map_err(|e| Error::task("JSON processing", e))?
 }
 
-/// Processes all JSON vote files and aggregates them into `CombinedData`.
-pub async fn process_vote_files_json() -> Result<CombinedData, Box<dyn Error>> {
-    let base_path = "data/json";
-
-    // Collect all JSON file paths
-    let mut json_files = Vec::new();
-    for entry in WalkDir::new(base_path)
-        .into_iter()
-        .filter_map(|e| e.ok())
-    {
-        if entry.file_type().is_file()
-            && entry.path().extension().and_then(|s| s.to_str()) == Some("json")
-        {
-            json_files.push(entry.path().to_path_buf());
-        }
-    }
+fn build_combined_data(workspace: &Workspace, format: VoteFormat, workers: usize) -> Result<CombinedData> {
+    let vote_files = collect_vote_files(workspace, format);
 
-    let total_files = json_files.len();
-    println!("📂 Total JSON files to process: {}", total_files);
+    let total_files = vote_files.len();
+    println!("📂 Total vote files to process: {}", total_files);
 
     // Initialize the progress bar
     let pb = ProgressBar::new(total_files as u64);
@@ -90,278 +93,326 @@ pub async fn process_vote_files_json() -> Result<CombinedData, Box<dyn Error>> {
     pb.set_message("📊 Processing files");
 
     let mut combined_data = CombinedData::default();
-
-    // Iterate over the collected files with progress updates
-    for path in json_files {
-        let path_str = path.to_str().unwrap_or("❓ Invalid path");
-
-        // Extract parts of the path
-        // data/json/{congress_number}/{chamber}/{session_number}/{file}.json
-        let parts: Vec<&str> = if cfg!(windows) {
-            path_str.split('\\').collect()
-        } else {
-            path_str.split('/').collect()
-        };
-
-        if parts.len() != 6 {
-            println!("⚠️ Unexpected path format: {}", path_str);
-            pb.inc(1);
-            continue;
-        }
-
-        let congress_number: u16 = match parts[2].parse() {
-            Ok(num) => num,
-            Err(_) => {
-                println!("❌ Invalid congress number in path: {}", path_str);
-                pb.inc(1);
-                continue;
-            }
-        };
-
-        let chamber = parts[3].to_lowercase();
-        let session_number: u8 = match parts[4].parse() {
-            Ok(num) => num,
-            Err(_) => {
-                println!("❌ Invalid session number in path: {}", path_str);
-                pb.inc(1);
-                continue;
-            }
-        };
-
-        let file_name = parts[5];
-        let rollcall_number: u32 = match file_name
-            .trim_end_matches(".json")
-            .split('_')
-            .last()
-            .unwrap_or("0")
-            .parse()
-        {
-            Ok(num) => num,
-            Err(_) => {
-                println!("❌ Invalid rollcall number in file name: {}", file_name);
-                pb.inc(1);
-                continue;
-            }
-        };
-
-        // Open and parse the JSON file
-        let file = match File::open(&path) {
-            Ok(f) => f,
-            Err(e) => {
-                println!("❌ Failed to open file {}: {}", path_str, e);
-                pb.inc(1);
-                continue;
-            }
-        };
-        println!("📄 Processing file: {}", path_str);
-
-        match chamber.as_str() {
-            "house" => {
-                let house_file: HouseFile = match serde_json::from_reader(file) {
-                    Ok(hf) => hf,
-                    Err(e) => {
-                        println!("❌ Failed to parse House JSON in {}: {}", path_str, e);
-                        pb.inc(1);
-                        continue;
-                    }
-                };
-                let metadata = house_file.rollcall_vote.vote_metadata;
-
-                let vote_data = if let Some(vd) = house_file.rollcall_vote.vote_data {
-                    vd.clone()
-                } else {
-                    println!("⚠️ Missing vote data in file: {}", path_str);
-                    VoteDataHouse::default()
-                };
-
-                for recorded_vote in vote_data.recorded_vote.unwrap_or_default() {
-                    let legislator = match &recorded_vote.legislator {
-                        Some(l) => l.clone(),
-                        None => {
-                            println!("⚠️ Missing legislator information in file: {}", path_str);
-                            continue;
-                        }
-                    };
-
-                    let legislator_name = match &legislator.unaccented_name {
-                        Some(name) => name.clone(),
-                        _ => "❓ Unknown".to_string(),
-                    };
-
-                    let unified_vote = UnifiedVote {
-                        congress_number,
-                        chamber: chamber.clone(),
-                        session_number,
-                        rollcall_number,
-                        vote_date: metadata
-                            .action_date
-                            .to_string(),
-                        vote_question: metadata
-                            .vote_question
-                            .to_string(),
-                        vote_result: metadata
-                            .vote_result
-                            .to_string(),
-                        legislator_id: legislator
-                            .name_id
-                            .clone()
-                            .unwrap_or_else(|| "None".to_string()),
-                        legislator_name,
-                        party: legislator.party.clone().unwrap_or_else(|| "None".to_string()),
-                        state: legislator.state.clone().unwrap_or_else(|| "None".to_string()),
-                        vote_cast: recorded_vote
-                            .vote
-                            .clone()
-                            .unwrap_or_else(|| "None".to_string())
-                            .to_string(),
-                    };
-
-                    // Insert into database or JSON file based on mode
-                    if let Err(e) = insert_into_database(&unified_vote, &mut combined_data).await {
-                        println!("❌ Failed to insert vote: {}", e);
-                    }
+    let mut mismatched = 0;
+
+    // Parse in parallel, insert in path order
+    parse_in_order(&vote_files, workers, read_unified_votes, |_, parsed| {
+        match parsed {
+            Ok((_, votes, mismatch)) => {
+                if let Some(mismatch) = mismatch {
+                    pb.println(format!("⚠️ {}", mismatch));
+                    mismatched += 1;
                 }
-            }
-            "senate" => {
-                let senate_file: SenateFile = match serde_json::from_reader(file) {
-                    Ok(sf) => sf,
-                    Err(e) => {
-                        println!("❌ Failed to parse Senate JSON in {}: {}", path_str, e);
-                        pb.inc(1);
-                        continue;
-                    }
-                };
-                let metadata = senate_file.roll_call_vote;
-
-                let congress_number_senate = metadata.congress.parse::<u16>().unwrap_or(congress_number);
-
-                let session_number_senate = metadata.session.parse::<u8>().unwrap_or(session_number);
-
-                let rollcall_number_senate = match &metadata.vote_number {
-                    Some(vn) => vn.parse::<u32>().unwrap_or(rollcall_number),
-                    None => rollcall_number,
-                };
-
-                if let Some(members) = &metadata.members {
-                    for member in members.member.clone().unwrap_or_default() {
-                        let unified_vote = UnifiedVote {
-                            congress_number: congress_number_senate,
-                            chamber: chamber.clone(),
-                            session_number: session_number_senate,
-                            rollcall_number: rollcall_number_senate,
-                            vote_date: metadata
-                                .vote_date
-                                .to_string(),
-                            vote_question: metadata
-                                .vote_question_text
-                                .to_string(),
-                            vote_result: metadata
-                                .vote_result
-                                .clone()
-                                .unwrap_or_else(|| "None".to_string()),
-                            legislator_id: member
-                                .lis_member_id
-                                .clone()
-                                .unwrap_or_else(|| "None".to_string()),
-                            legislator_name: member
-                                .member_full
-                                .clone()
-                                .unwrap_or_else(|| "None".to_string()),
-                            party: member.party.clone().unwrap_or_else(|| "None".to_string()),
-                            state: member.state.clone().unwrap_or_else(|| "None".to_string()),
-                            vote_cast: if let Some(vote) = member.vote_cast {
-                                vote.to_string()
-                            } else {
-                                "None".to_string()
-                            },
-                        };
-
-                        // Insert into database or JSON file based on mode
-                        if let Err(e) = insert_into_database(&unified_vote, &mut combined_data).await {
-                            println!("❌ Failed to insert vote: {}", e);
-                        }
-                    }
-                } else {
-                    println!("⚠️ No members found in Senate file: {}", path_str);
+                for unified_vote in votes {
+                    combined_data.insert(&unified_vote);
                 }
             }
-            _ => {
-                println!("⚠️ Unknown chamber: {}", chamber);
-            }
+            Err(e) => pb.println(format!("❌ {}", e)),
         }
 
         // Increment the progress bar after processing each file
         pb.inc(1);
-    }
+        Ok(())
+    })?;
 
     // Finish the progress bar
     pb.finish_with_message("✅ Processing complete");
+    if mismatched > 0 {
+        println!("⚠️ {} files are filed under a different roll call than they record.", mismatched);
+    }
+    println!(
+        "📊 {} ballots from {} members, {} distinct strings",
+        combined_data.ballot_count(),
+        combined_data.members.len(),
+        combined_data.strings.len()
+    );
 
     Ok(combined_data)
 }
 
-/// Custom writer to track progress during serialization.
-struct ProgressWriter<W: Write> {
+/// Writes the master JSON structure one roll call at a time, so only the roll call
+/// being written is held in memory.
+///
+/// Roll calls must arrive ordered by chamber, congress and session (as
+/// `collect_vote_files` returns them); the writer opens and closes the nested
+/// `chambers`/`congresses`/`sessions` objects as those change.
+pub struct JsonStreamWriter<W: Write> {
     inner: W,
-    pb: ProgressBar,
+    current: Option<(String, u16, u8)>,
 }
 
-impl<W: Write> Write for ProgressWriter<W> {
-    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
-        let bytes_written = self.inner.write(buf)?;
-        self.pb.inc(bytes_written as u64);
-        Ok(bytes_written)
+impl<W: Write> JsonStreamWriter<W> {
+    pub fn new(mut inner: W) -> io::Result<Self> {
+        write!(inner, "{{\"chambers\":{{")?;
+        Ok(JsonStreamWriter { inner, current: None })
     }
 
-    fn flush(&mut self) -> io::Result<()> {
-        self.inner.flush()
+    pub fn write_rollcall(&mut self, chamber: &str, congress_number: u16, session_number: u8, rollcall: &RollCallData) -> io::Result<()> {
+        let key = (chamber.to_string(), congress_number, session_number);
+
+        match &self.current {
+            None => {
+                self.open_chamber(chamber)?;
+                self.open_congress(congress_number)?;
+                self.open_session(session_number)?;
+            }
+            Some(current) if *current > key => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Roll call {} {}-{} #{} is out of order", chamber, congress_number, session_number, rollcall.rollcall_number),
+                ));
+            }
+            Some((c, ..)) if *c != key.0 => {
+                write!(self.inner, "]}}}}}}}}}}")?;
+                write!(self.inner, ",")?;
+                self.open_chamber(chamber)?;
+                self.open_congress(congress_number)?;
+                self.open_session(session_number)?;
+            }
+            Some((_, n, _)) if *n != key.1 => {
+                write!(self.inner, "]}}}}}}")?;
+                write!(self.inner, ",")?;
+                self.open_congress(congress_number)?;
+                self.open_session(session_number)?;
+            }
+            Some((_, _, s)) if *s != key.2 => {
+                write!(self.inner, "]}}")?;
+                write!(self.inner, ",")?;
+                self.open_session(session_number)?;
+            }
+            Some(_) => write!(self.inner, ",")?,
+        }
+
+        writeln!(self.inner)?;
+        serde_json::to_writer(&mut self.inner, rollcall)?;
+
+        self.current = Some(key);
+        Ok(())
+    }
+
+    /// Closes every open object and returns the underlying writer, flushed.
+    pub fn finish(mut self) -> io::Result<W> {
+        if self.current.is_some() {
+            write!(self.inner, "]}}}}}}}}}}")?;
+        }
+        writeln!(self.inner, "}}}}")?;
+        self.inner.flush()?;
+        Ok(self.inner)
     }
+
+    fn open_chamber(&mut self, chamber: &str) -> io::Result<()> {
+        write!(self.inner, "{}:{{\"congresses\":{{", serde_json::to_string(chamber)?)
+    }
+
+    fn open_congress(&mut self, congress_number: u16) -> io::Result<()> {
+        write!(self.inner, "\"{}\":{{\"sessions\":{{", congress_number)
+    }
+
+    fn open_session(&mut self, session_number: u8) -> io::Result<()> {
+        write!(self.inner, "\"{}\":{{\"rollcalls\":[", session_number)
+    }
+}
+
+/// Groups a file's votes into its `RollCallData`, or `None` if it has no votes.
+fn to_rollcall(votes: Vec<UnifiedVote>) -> Option<RollCallData> {
+    let first = votes.first()?;
+
+    Some(RollCallData {
+        rollcall_number: first.rollcall_number,
+        vote_date: first.vote_date.clone(),
+        vote_day: first.vote_day.clone(),
+        vote_timestamp: first.vote_timestamp.clone(),
+        vote_question: first.vote_question.clone(),
+        vote_result: first.vote_result.clone(),
+        vote_casts: votes,
+    })
+}
+
+/// Where a roll call goes in the master JSON file: chamber, congress, session and number.
+type RollCallKey = (String, u16, u8, u32);
+
+/// Why a roll call was left out of the master JSON file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Skipped {
+    /// Its roll call was already written.
+    Duplicate,
+    /// It records a roll call the stream had already gone past.
+    OutOfOrder,
 }
 
-/// Serializes the `CombinedData` into a JSON file with progress indication.
-pub fn serialize_combined_data(combined_data: &CombinedData) -> Result<(), Box<dyn Error>> {
-    // Step 1: Serialize to a buffer to determine total size
-    let serialized = serde_json::to_vec_pretty(combined_data)?;
-    let total_size = serialized.len() as u64;
+/// Feeds a `JsonStreamWriter` with roll calls that mostly arrive in order. Those
+/// read from misfiled documents are held back until the stream reaches the roll
+/// call they record; a roll call already written, or already gone past, is skipped.
+pub struct RollCallMerger<W: Write> {
+    writer: JsonStreamWriter<W>,
+    held: BTreeMap<RollCallKey, (PathBuf, RollCallData)>,
+    last: Option<RollCallKey>,
+    /// Files left out so far, and why.
+    pub skipped: Vec<(PathBuf, Skipped)>,
+}
 
-    // Step 2: Initialize the progress bar
-    let pb = ProgressBar::new(total_size);
+impl<W: Write> RollCallMerger<W> {
+    pub fn new(writer: JsonStreamWriter<W>) -> Self {
+        RollCallMerger { writer, held: BTreeMap::new(), last: None, skipped: Vec::new() }
+    }
+
+    fn placed(&self, key: &RollCallKey) -> Option<Skipped> {
+        match &self.last {
+            Some(last) if last == key => Some(Skipped::Duplicate),
+            Some(last) if last > key => Some(Skipped::OutOfOrder),
+            _ => None,
+        }
+    }
+
+    /// Holds back a roll call whose document disagrees with its path.
+    pub fn hold(&mut self, id: &RollCallId, path: PathBuf, rollcall: RollCallData) {
+        let key = (id.chamber.to_string(), id.congress, id.session, id.number);
+
+        match self.placed(&key) {
+            Some(skipped) => self.skipped.push((path, skipped)),
+            None if self.held.contains_key(&key) => self.skipped.push((path, Skipped::Duplicate)),
+            None => {
+                self.held.insert(key, (path, rollcall));
+            }
+        }
+    }
+
+    /// Writes a roll call filed where its document says, after the held ones that come
+    /// before it. A held copy of the same roll call is dropped in its favour.
+    pub fn write(&mut self, id: &RollCallId, path: PathBuf, rollcall: RollCallData) -> io::Result<()> {
+        let key = (id.chamber.to_string(), id.congress, id.session, id.number);
+
+        if let Some(skipped) = self.placed(&key) {
+            self.skipped.push((path, skipped));
+            return Ok(());
+        }
+
+        while let Some(entry) = self.held.first_entry() {
+            if *entry.key() >= key {
+                break;
+            }
+            let (held_key, (_, held)) = entry.remove_entry();
+            self.emit(held_key, &held)?;
+        }
+
+        if let Some((held_path, _)) = self.held.remove(&key) {
+            self.skipped.push((held_path, Skipped::Duplicate));
+        }
+        self.emit(key, &rollcall)
+    }
+
+    fn emit(&mut self, key: RollCallKey, rollcall: &RollCallData) -> io::Result<()> {
+        self.writer.write_rollcall(&key.0, key.1, key.2, rollcall)?;
+        self.last = Some(key);
+        Ok(())
+    }
+
+    /// Writes the roll calls still held back and closes the document.
+    pub fn finish(mut self) -> io::Result<W> {
+        for (key, (_, rollcall)) in std::mem::take(&mut self.held) {
+            self.emit(key, &rollcall)?;
+        }
+        self.writer.finish()
+    }
+}
+
+impl std::fmt::Display for Skipped {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Skipped::Duplicate => write!(f, "its roll call is already in the file"),
+            Skipped::OutOfOrder => write!(f, "its roll call comes before ones already written"),
+        }
+    }
+}
+
+/// Parses every vote file of `format` on `workers` threads (0 for one per core)
+/// and streams it into the workspace's master JSON file. Roll calls are placed by
+/// the roll call their document records; misfiled copies that can't be placed are
+/// reported and left out.
+pub fn write_vote_files_json(workspace: &Workspace, format: VoteFormat, workers: usize) -> Result<()> {
+    let out_path = &workspace.master_json_path();
+    let vote_files = collect_vote_files(workspace, format);
+    println!("📂 Total vote files to process: {}", vote_files.len());
+
+    let pb = ProgressBar::new(vote_files.len() as u64);
     pb.set_style(
         ProgressStyle::default_bar()
-            .template("[{elapsed_precise}] [{bar:40.cyan/blue}] {bytes}/{total_bytes} ({percent}%) {msg}")?
+            .template("[{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} ({percent}%) {msg}")?
             .progress_chars("=> "),
     );
-    pb.set_message("🔄 Serializing data");
-
-    // Step 3: Create the file and wrap it with ProgressWriter
-    let file = match File::create("full_data/votes.json") {
-        Ok(f) => f,
-        Err(e) => {
-            eprintln!("❌ Failed to create file 'full_data/votes.json': {}", e);
-            return Err(Box::new(e));
+    pb.set_message("🔄 Writing votes");
+
+    Workspace::create_parent(out_path)?;
+    let file = File::create(out_path).map_err(|e| Error::io(out_path, e))?;
+    println!("📁 Creating file '{}'...", out_path.display());
+
+    let writer = JsonStreamWriter::new(BufWriter::new(file)).map_err(|e| Error::io(out_path, e))?;
+    let mut merger = RollCallMerger::new(writer);
+
+    let parse = |entry: &VoteFileEntry| read_unified_votes(entry).map(|(id, votes, mismatch)| (id, to_rollcall(votes), mismatch));
+    let mut mismatched = 0;
+    let mut skipped = 0;
+
+    // Files arrive in path order
+    parse_in_order(&vote_files, workers, parse, |entry, parsed| {
+        match parsed {
+            Ok((id, rollcall, mismatch)) => {
+                let misfiled = mismatch.is_some();
+                if let Some(mismatch) = mismatch {
+                    pb.println(format!("⚠️ {}", mismatch));
+                    mismatched += 1;
+                }
+                match rollcall {
+                    Some(rollcall) if misfiled => merger.hold(&id, entry.path.clone(), rollcall),
+                    Some(rollcall) => merger.write(&id, entry.path.clone(), rollcall).map_err(|e| Error::io(out_path, e))?,
+                    None => {}
+                }
+                for (path, reason) in merger.skipped.drain(..) {
+                    pb.println(format!("⏭️ Skipped '{}': {}", path.display(), reason));
+                    skipped += 1;
+                }
+            }
+            Err(e) => pb.println(format!("❌ {}", e)),
         }
-    };
-    println!("📁 Creating file 'full_data/votes.json'...");
-
-    let mut progress_writer = ProgressWriter {
-        inner: file,
-        pb: pb.clone(),
-    };
+        pb.inc(1);
+        Ok(())
+    })?;
 
-    // Step 4: Write the serialized data to the file using ProgressWriter
-    if let Err(e) = progress_writer.write_all(&serialized) {
-        eprintln!("❌ Failed to write to 'full_data/votes.json': {}", e);
-        return Err(Box::new(e));
+    merger.finish().map_err(|e| Error::io(out_path, e))?;
+    pb.finish_with_message("✅ Serialization complete");
+    if mismatched > 0 {
+        println!("⚠️ {} files are filed under a different roll call than they record.", mismatched);
     }
-    if let Err(e) = progress_writer.flush() {
-        eprintln!("❌ Failed to flush data to 'full_data/votes.json': {}", e);
-        return Err(Box::new(e));
+    if skipped > 0 {
+        println!("⏭️ {} files were left out of the master JSON file.", skipped);
     }
 
-    // Step 5: Finish the progress bar
-    pb.finish_with_message("✅ Serialization complete");
+    println!("🎉 '{}' has been successfully created.", out_path.display());
+
+    Ok(())
+}
+
+/// Serializes the `CombinedData` into the workspace's master JSON file, streaming it roll call by roll call.
+pub fn serialize_combined_data(workspace: &Workspace, combined_data: &CombinedData) -> Result<()> {
+    let out_path = &workspace.master_json_path();
+
+    Workspace::create_parent(out_path)?;
+    let file = File::create(out_path).map_err(|e| Error::io(out_path, e))?;
+    println!("📁 Creating file '{}'...", out_path.display());
+
+    let mut writer = JsonStreamWriter::new(BufWriter::new(file)).map_err(|e| Error::io(out_path, e))?;
+
+    // Roll calls are expanded back to full ballots one at a time
+    for (chamber, congress_number, session_number, rollcall_number, rollcall) in combined_data.rollcalls() {
+        let rollcall = combined_data.expand(chamber, congress_number, session_number, rollcall_number, rollcall);
+        writer
+            .write_rollcall(chamber, congress_number, session_number, &rollcall)
+            .map_err(|e| Error::io(out_path, e))?;
+    }
+
+    writer.finish().map_err(|e| Error::io(out_path, e))?;
 
-    println!("🎉 'full_data/votes.json' has been successfully created.");
+    println!("🎉 '{}' has been successfully created.", out_path.display());
 
     Ok(())
 }
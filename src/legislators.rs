@@ -0,0 +1,235 @@
+// legislators.rs
+//
+// Registry of legislators keyed on the ids the chambers publish: the bioguide
+// id on House ballots and the LIS id on Senate ballots. It is loaded from a
+// crosswalk in the unitedstates/congress-legislators format
+// (legislators-current.yaml, legislators-historical.yaml, or their JSON
+// versions), which lists both ids for anyone with a record in either chamber,
+// so a House career and a Senate career resolve to the same person.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+
+use crate::model::{text, Member};
+use crate::{Error, Result};
+
+/// Ids of one legislator. Only the bioguide id is always present.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LegislatorIds {
+    pub bioguide: String,
+    pub lis: Option<String>,
+    pub thomas: Option<String>,
+    pub govtrack: Option<u32>,
+    pub icpsr: Option<u32>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LegislatorName {
+    pub first: Option<String>,
+    pub last: Option<String>,
+    pub official_full: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LegislatorBio {
+    /// "1962-02-11"
+    pub birthday: Option<String>,
+    pub gender: Option<String>,
+}
+
+/// One term in office. `kind` is "rep" or "sen".
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LegislatorTerm {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub start: String,
+    pub end: String,
+    pub state: String,
+    /// House district, 0 for at-large seats.
+    pub district: Option<i32>,
+    /// "Democrat", "Republican", "Independent", ...
+    pub party: Option<String>,
+}
+
+impl LegislatorTerm {
+    /// "house" or "senate", as in the `votes` and `members` tables.
+    pub fn chamber(&self) -> Option<&'static str> {
+        match self.kind.as_str() {
+            "rep" => Some("house"),
+            "sen" => Some("senate"),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Legislator {
+    pub id: LegislatorIds,
+    #[serde(default)]
+    pub name: LegislatorName,
+    pub bio: Option<LegislatorBio>,
+    #[serde(default)]
+    pub terms: Vec<LegislatorTerm>,
+}
+
+/// Legislators indexed by bioguide and LIS id.
+#[derive(Debug, Clone, Default)]
+pub struct Registry {
+    legislators: Vec<Legislator>,
+    by_bioguide: HashMap<String, usize>,
+    by_lis: HashMap<String, usize>,
+}
+
+impl Registry {
+    /// Loads a crosswalk file, YAML or JSON by its extension.
+    pub fn load(path: &Path) -> Result<Self> {
+        let data = std::fs::read_to_string(path).map_err(|e| Error::io(path, e))?;
+        let json = path.extension().and_then(|s| s.to_str()) == Some("json");
+        Self::parse(&data, json).map_err(|e| e.with_path(path))
+    }
+
+    /// Parses a crosswalk in the congress-legislators YAML format.
+    pub fn from_yaml(yaml: &str) -> Result<Self> {
+        Self::parse(yaml, false)
+    }
+
+    /// Parses a crosswalk, JSON if `json` and YAML otherwise.
+    fn parse(data: &str, json: bool) -> Result<Self> {
+        let legislators: Vec<Legislator> = if json {
+            serde_json::from_str(data).map_err(|e| Error::schema(format!("Invalid legislator crosswalk: {}", e)))?
+        } else {
+            serde_yaml::from_str(data).map_err(|e| Error::schema(format!("Invalid legislator crosswalk: {}", e)))?
+        };
+
+        let mut registry = Registry::default();
+        registry.extend(legislators);
+        Ok(registry)
+    }
+
+    /// Adds legislators, e.g. the historical file after the current one.
+    /// A legislator already present is replaced, LIS id included.
+    pub fn extend(&mut self, legislators: impl IntoIterator<Item = Legislator>) {
+        for legislator in legislators {
+            let index = match self.by_bioguide.get(&legislator.id.bioguide) {
+                Some(&index) => {
+                    let old = std::mem::replace(&mut self.legislators[index], legislator);
+                    if let Some(lis) = old.id.lis {
+                        if self.by_lis.get(&lis) == Some(&index) {
+                            self.by_lis.remove(&lis);
+                        }
+                    }
+                    index
+                }
+                None => {
+                    self.legislators.push(legislator);
+                    self.legislators.len() - 1
+                }
+            };
+
+            let legislator = &self.legislators[index];
+            self.by_bioguide.insert(legislator.id.bioguide.clone(), index);
+            if let Some(lis) = &legislator.id.lis {
+                self.by_lis.insert(lis.clone(), index);
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.legislators.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.legislators.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Legislator> {
+        self.legislators.iter()
+    }
+
+    pub fn by_bioguide(&self, bioguide: &str) -> Option<&Legislator> {
+        self.by_bioguide.get(bioguide).map(|&i| &self.legislators[i])
+    }
+
+    pub fn by_lis(&self, lis: &str) -> Option<&Legislator> {
+        self.by_lis.get(lis).map(|&i| &self.legislators[i])
+    }
+
+    /// The legislator who cast a ballot, by whichever id the chamber recorded.
+    pub fn resolve(&self, member: &Member) -> Option<&Legislator> {
+        member
+            .bioguide_id
+            .as_deref()
+            .and_then(|id| self.by_bioguide(id))
+            .or_else(|| member.lis_member_id.as_deref().and_then(|id| self.by_lis(id)))
+    }
+}
+
+/// Upserts every legislator and replaces their terms. Returns the number written.
+pub fn insert_legislators(tx: &rusqlite::Transaction<'_>, registry: &Registry) -> Result<usize> {
+    for legislator in registry.iter() {
+        let bio = legislator.bio.clone().unwrap_or_default();
+
+        // An LIS id moves with the person it belongs to if an older row still holds it
+        if let Some(lis) = &legislator.id.lis {
+            tx.execute(
+                "UPDATE legislators SET lis_id = NULL WHERE lis_id = ?1 AND bioguide_id <> ?2",
+                params![lis, legislator.id.bioguide],
+            )?;
+        }
+
+        tx.execute(
+            "INSERT INTO legislators (
+                bioguide_id, lis_id, thomas_id, govtrack_id, icpsr_id,
+                first_name, last_name, official_full, birthday, gender
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+            ON CONFLICT (bioguide_id) DO UPDATE SET
+                lis_id = excluded.lis_id,
+                thomas_id = excluded.thomas_id,
+                govtrack_id = excluded.govtrack_id,
+                icpsr_id = excluded.icpsr_id,
+                first_name = excluded.first_name,
+                last_name = excluded.last_name,
+                official_full = excluded.official_full,
+                birthday = excluded.birthday,
+                gender = excluded.gender",
+            params![
+                legislator.id.bioguide,
+                text(legislator.id.lis.as_deref()),
+                text(legislator.id.thomas.as_deref()),
+                legislator.id.govtrack,
+                legislator.id.icpsr,
+                text(legislator.name.first.as_deref()),
+                text(legislator.name.last.as_deref()),
+                text(legislator.name.official_full.as_deref()),
+                text(bio.birthday.as_deref()),
+                text(bio.gender.as_deref()),
+            ],
+        )?;
+
+        tx.execute("DELETE FROM legislator_terms WHERE bioguide_id = ?1", params![legislator.id.bioguide])?;
+        for (position, term) in legislator.terms.iter().enumerate() {
+            let Some(chamber) = term.chamber() else { continue };
+
+            tx.execute(
+                "INSERT INTO legislator_terms (
+                    bioguide_id, position, chamber, start_date, end_date, state, district, party
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                params![
+                    legislator.id.bioguide,
+                    position,
+                    chamber,
+                    term.start,
+                    term.end,
+                    term.state,
+                    term.district,
+                    text(term.party.as_deref()),
+                ],
+            )?;
+        }
+    }
+
+    Ok(registry.len())
+}
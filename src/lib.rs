@@ -1,51 +1,34 @@
-pub const CURRENT_CONGRESS: u32 = 118;
-pub const CURRENT_SESSION: u32 = 2;
-pub const OLDEST_CONGRESS: u32 = 101;
-pub const CURRENT_ROLL: u32 = 255;
-
-pub mod client {
-    use reqwest::Client;
-
-    pub struct RollCallClient {
-        client: Client,
-    }
-
-    impl RollCallClient {
-        pub fn new(proxy: bool) -> Self {
-
-            let client = match proxy {
-                true => {
-                    let c = match Client::builder()
-                    .proxy(reqwest::Proxy::all("").unwrap())
-                    .build() {
-                        Ok(client) => client,
-                        Err(e) => panic!("Error creating client: {}", e),
-                    };
-                    c
-                }
-                false => Client::new(),
-            };
-
-            RollCallClient {
-                client,
-            }
-        }
-
-        pub async fn fetch_data(&self, url: &str) -> Result<String, reqwest::Error> {
-            self.client.get(url).send().await?.text().await
-        }
-    }
-}
+pub const OLDEST_CONGRESS: u32 = 1;
 
+pub mod error;
+pub use error::{Error, Result};
+
+pub mod client;
 pub mod endpoints;
 pub mod responses;
+pub mod model;
+pub mod dates;
+pub mod legislators;
+pub mod analytics;
+pub mod attendance;
+pub mod matrix;
+pub mod ideal_points;
+pub mod search;
+pub mod queries;
+pub mod server;
 
 pub mod sqllite;
+pub mod migrations;
 pub mod sql_senate;
 pub mod sql_house;
 
 pub mod json_master;
 
+pub mod pipeline;
+pub mod workspace;
+
+pub mod xml_reader;
+
 #[cfg(test)]
 pub mod tests;
 
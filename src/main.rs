@@ -1,11 +1,30 @@
 use congress_rolls::endpoints::CongressEndpoint as Congress;
-use congress_rolls::client::RollCallClient as Client;
-use congress_rolls::{sqllite, json_master};
-use std::error::Error;
+use congress_rolls::client::{DownloadJob, Downloader, RollCallClient as Client};
+use congress_rolls::{analytics, sqllite, json_master, legislators, migrations};
+use congress_rolls::attendance::{self, AttendanceFilter};
+use congress_rolls::matrix::{MatrixFilter, VoteMatrix};
+use congress_rolls::ideal_points::{self, IdealPointOptions};
+use congress_rolls::search::{self, SearchFilter};
+use congress_rolls::queries::VoteQuery;
+use congress_rolls::model::{Party, VoteCast};
+use congress_rolls::dates;
+use congress_rolls::server;
+use congress_rolls::endpoints::CongressChamber;
+use congress_rolls::legislators::Registry;
+use congress_rolls::workspace::Workspace;
+use congress_rolls::xml_reader::VoteFormat;
+use congress_rolls::{Error, Result};
 
 #[tokio::main]
-pub async fn main() -> Result<(), Box<dyn Error>> {
-    let args: Vec<String> = std::env::args().collect();
+pub async fn main() {
+    if let Err(e) = run().await {
+        eprintln!("❌ {}", e);
+        std::process::exit(1);
+    }
+}
+
+async fn run() -> Result<()> {
+    let (workspace, args) = workspace_args(std::env::args().collect())?;
 
     if args.len() < 2 {
         usage();
@@ -15,14 +34,90 @@ pub async fn main() -> Result<(), Box<dyn Error>> {
         "download_xml" => {
             // Example command: congress_rolls download_xml house 116 1 10
             println!("📥 Starting XML download...");
-            xml_download().await;
+            xml_download(&workspace, &args).await?;
             println!("✅ XML download completed!");
             Ok(())
         }
+        "import_legislators" => {
+            // Example command: congress_rolls import_legislators legislators-current.yaml legislators-historical.yaml
+            if args.len() < 3 {
+                usage();
+            }
+
+            let mut registry = Registry::default();
+            for path in &args[2..] {
+                let file = Registry::load(std::path::Path::new(path))?;
+                println!("📇 {} legislators in {}", file.len(), path);
+                registry.extend(file.iter().cloned());
+            }
+
+            let mut conn = sqllite::open_database(&workspace)?;
+            let tx = conn.transaction()?;
+            let written = legislators::insert_legislators(&tx, &registry)?;
+            tx.commit()?;
+
+            println!("✅ {} legislators imported into '{}'.", written, workspace.database_path().display());
+            Ok(())
+        }
+        "db" => {
+            // Example command: congress_rolls db status
+            match args.get(2).map(|s| s.as_str()) {
+                Some("status") => database_status(&workspace),
+                Some("migrate") => database_migrate(&workspace),
+                _ => {
+                    usage();
+                    Ok(())
+                }
+            }
+        }
+        "attendance" => {
+            // Example command: congress_rolls attendance --chamber senate --congress 118 --top 10
+            attendance_report(&workspace, &args[2..])
+        }
+        "matrix" => {
+            // Example command: congress_rolls matrix --chamber senate --congress 118 --out votes_118
+            matrix_export(&workspace, &args[2..])
+        }
+        "votes" => {
+            // Example command: congress_rolls votes ballots --member S354 --congress 115-118 --cast nay
+            if args.len() < 3 {
+                usage();
+            }
+            query_votes(&workspace, &args[2], &args[3..])
+        }
+        "search" => {
+            // Example command: congress_rolls search "border security" --chamber senate --limit 5
+            search_votes(&workspace, &args[2..])
+        }
+        "serve" => {
+            // Example command: congress_rolls serve --addr 127.0.0.1:8080
+            let addr = match args[2..] {
+                [] => server::DEFAULT_ADDR,
+                [ref option, ref addr] if option == "--addr" => addr.as_str(),
+                _ => {
+                    usage();
+                    server::DEFAULT_ADDR
+                }
+            };
+            let addr = addr.parse().map_err(|_| Error::parse("listen address", addr))?;
+            server::serve(&workspace, addr).await
+        }
+        "ideal_points" => {
+            // Example command: congress_rolls ideal_points --chamber senate --congress 118 --dimensions 2
+            ideal_points_report(&workspace, &args[2..])
+        }
+        "analyze" => {
+            // Example command: congress_rolls analyze party_unity
+            if args.len() < 3 {
+                usage();
+            }
+
+            analyze(&workspace, &args[2])
+        }
         "process_votes" => {
             // Example command: congress_rolls process_votes json
             if args.len() < 3 {
-                println!("❗ Usage: congress_rolls process_votes <json|sql> <optional: file_path>");
+                println!("❗ Usage: congress_rolls process_votes <json|sql> <optional: --json> <optional: --workers N> <optional: file_path>");
                 println!("💡 Notes: The file path is optional and can only be used with the SQL mode for adding new data to the database.");
                 println!("📄 The JSON file must be fully reconstructed with the addition of new data.");
                 std::process::exit(1);
@@ -34,26 +129,20 @@ pub async fn main() -> Result<(), Box<dyn Error>> {
                 std::process::exit(1);
             }
 
-            let file_path = if args.len() == 4 {
-                Some(args[3].clone())
-            } else {
-                None
-            };
+            let (format, workers, rest) = read_options(&args[3..])?;
+            let file_path = rest.last().map(|s| s.to_string());
 
             // Process the vote files based on the specified mode
             if mode == "json" {
                 println!("📝 Processing votes in JSON mode...");
-                let data = json_master::process_vote_files_json().await?;
-                println!("✅ Vote processing completed successfully.");
-
-                // Serialize the combined data to a JSON file
-                json_master::serialize_combined_data(&data)?;
-                println!("📁 Master JSON file 'votes.json' created successfully.");
+                // Stream each file into the master JSON file as it is parsed
+                json_master::write_vote_files_json(&workspace, format, workers)?;
+                println!("📁 Master JSON file '{}' created successfully.", workspace.master_json_path().display());
             }
 
             if mode == "sql" {
                 println!("💾 Starting SQL processing...");
-                sqllite::process_vote_files_sql(file_path.as_deref()).await?;
+                sqllite::process_vote_files_sql(&workspace, file_path.as_deref(), format, workers).await?;
             }
 
             println!("🎉 Vote processing completed successfully.");
@@ -67,90 +156,630 @@ pub async fn main() -> Result<(), Box<dyn Error>> {
     }
 }
 
+/// Reads `--json` (use the yq-converted data/json instead of data/xml; `--xml`
+/// is the default) and `--workers N` (parse threads, 0 for one per core) from
+/// `args`, and returns the other arguments.
+fn read_options(args: &[String]) -> Result<(VoteFormat, usize, Vec<&str>)> {
+    let mut format = VoteFormat::Xml;
+    let mut workers = 0;
+    let mut positional = vec![];
+
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--xml" => format = VoteFormat::Xml,
+            "--json" => format = VoteFormat::Json,
+            "--workers" => {
+                let value = rest.next().map(|s| s.as_str()).unwrap_or_default();
+                workers = value.parse::<usize>().map_err(|_| Error::parse("worker count", value))?;
+            }
+            _ => positional.push(arg.as_str()),
+        }
+    }
+
+    Ok((format, workers, positional))
+}
+
+/// Runs one analysis over the roll calls in the workspace's database and writes
+/// its tables back to the database and its CSV files to the analytics directory.
+fn analyze(workspace: &Workspace, analysis: &str) -> Result<()> {
+    let mut conn = sqllite::open_database(workspace)?;
+    let out_dir = workspace.analytics_dir();
+
+    match analysis {
+        "party_unity" => {
+            println!("📊 Computing party unity...");
+            let unity = analytics::party_unity(&conn)?;
+            let rollcalls: u32 = unity.rollcalls.values().sum();
+
+            let tx = conn.transaction()?;
+            analytics::insert_party_unity(&tx, &unity)?;
+            tx.commit()?;
+            analytics::write_party_unity_csv(&out_dir, &unity)?;
+
+            println!(
+                "✅ {} party-unity votes out of {} roll calls, {} defections. CSV files in '{}'.",
+                unity.votes.len(),
+                rollcalls,
+                unity.defections.len(),
+                out_dir.display()
+            );
+        }
+        _ => {
+            println!("🚫 Unknown analysis '{}'. Use 'party_unity'.", analysis);
+            std::process::exit(1);
+        }
+    }
+
+    Ok(())
+}
+
+/// Prints the schema version of the workspace database and which migrations it has, without changing it.
+fn database_status(workspace: &Workspace) -> Result<()> {
+    let db_path = workspace.database_path();
+    if !db_path.exists() {
+        println!("📭 No database at '{}'; {} migrations will create it.", db_path.display(), migrations::MIGRATIONS.len());
+        return Ok(());
+    }
+
+    let conn = rusqlite::Connection::open_with_flags(&db_path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|e| Error::from(e).with_path(&db_path))?;
+    let version = migrations::current_version(&conn)?;
+    println!("🗄️ '{}' is at schema version {} of {}", db_path.display(), version, sqllite::SCHEMA_VERSION);
+
+    for m in migrations::status(&conn)? {
+        let state = match (m.applied, m.applied_at) {
+            (true, Some(at)) => format!("✅ applied {}", at),
+            (true, None) => "✅ applied".to_string(),
+            (false, _) => "⏳ pending".to_string(),
+        };
+        println!("    {:>3} {:<65} {}", m.version, m.name, state);
+    }
+
+    if version > sqllite::SCHEMA_VERSION {
+        println!("⚠️ The database is newer than this build; upgrade congress_rolls to use it.");
+    } else if version < sqllite::SCHEMA_VERSION {
+        println!("💡 Run 'congress_rolls db migrate' to upgrade it.");
+    }
+
+    Ok(())
+}
+
+/// Applies the migrations the workspace database is missing, creating it if needed.
+fn database_migrate(workspace: &Workspace) -> Result<()> {
+    let db_path = workspace.database_path();
+    Workspace::create_parent(&db_path)?;
+
+    let conn = rusqlite::Connection::open(&db_path).map_err(|e| Error::from(e).with_path(&db_path))?;
+    let version = migrations::current_version(&conn)?;
+    let applied = migrations::migrate(&conn)?;
+
+    if applied.is_empty() {
+        println!("✅ '{}' is up to date at schema version {}.", db_path.display(), version);
+        return Ok(());
+    }
+
+    for m in migrations::MIGRATIONS.iter().filter(|m| applied.contains(&m.version)) {
+        println!("🔧 Applied migration {}: {}", m.version, m.name);
+    }
+    println!("✅ '{}' upgraded from schema version {} to {}.", db_path.display(), version, sqllite::SCHEMA_VERSION);
+
+    Ok(())
+}
+
+/// Prints attendance from the database: chamber-wide rates, the members with
+/// the lowest attendance, and the longest streaks of missed votes.
+fn attendance_report(workspace: &Workspace, args: &[String]) -> Result<()> {
+    let mut filter = AttendanceFilter::default();
+    let mut top = 10;
+
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        let value = rest.next().map(|s| s.as_str()).unwrap_or_default();
+        match arg.as_str() {
+            "--chamber" => filter.chamber = Some(value.parse()?),
+            "--congress" => filter.congress = Some(value.parse().map_err(|_| Error::parse("congress number", value))?),
+            "--top" => top = value.parse().map_err(|_| Error::parse("row count", value))?,
+            _ => usage(),
+        }
+    }
+
+    let conn = sqllite::open_database(workspace)?;
+    let report = attendance::attendance(&conn, &filter)?;
+
+    if report.chambers.is_empty() {
+        println!("📭 No ballots in '{}'.", workspace.database_path().display());
+        return Ok(());
+    }
+
+    let scope = |congress: u16, session: Option<u8>| match session {
+        Some(session) => format!("{}-{}", congress, session),
+        None => format!("{}", congress),
+    };
+
+    println!("🏛️ Chamber attendance");
+    for c in &report.chambers {
+        println!(
+            "    {:<7} {:<6} {:>5} roll calls {:>4} members {:>7.2}% voted (median member {:.2}%)",
+            c.chamber.to_string(),
+            scope(c.congress, c.session),
+            c.rollcalls,
+            c.members,
+            c.rate * 100.0,
+            c.median_member_rate * 100.0
+        );
+    }
+
+    println!("\n🙈 Lowest attendance per congress");
+    let mut congresses: Vec<_> = report.members.iter().filter(|m| m.session.is_none()).collect();
+    congresses.sort_by(|a, b| (a.chamber, a.congress).cmp(&(b.chamber, b.congress)).then(a.rate.total_cmp(&b.rate)));
+    for group in congresses.chunk_by(|a, b| (a.chamber, a.congress) == (b.chamber, b.congress)) {
+        println!("    {} {}", group[0].chamber.to_string(), group[0].congress);
+        for m in group.iter().take(top) {
+            println!(
+                "        {:<30} {:>4}/{:<4} missed {:>7.2}% voted",
+                m.name.as_deref().unwrap_or(&m.member_id),
+                m.missed,
+                m.rollcalls,
+                m.rate * 100.0
+            );
+        }
+    }
+
+    println!("\n⛓️ Longest streaks of missed votes");
+    for s in report.streaks.iter().take(top) {
+        println!(
+            "    {:<30} {:<7} {:>4} votes, {} #{} ({}) to {} #{} ({})",
+            s.name.as_deref().unwrap_or(&s.member_id),
+            s.chamber.to_string(),
+            s.length,
+            scope(s.first.congress, Some(s.first.session)),
+            s.first.rollcall,
+            s.first_date.as_deref().unwrap_or("?"),
+            scope(s.last.congress, Some(s.last.session)),
+            s.last.rollcall,
+            s.last_date.as_deref().unwrap_or("?")
+        );
+    }
+
+    Ok(())
+}
+
+/// Prints the roll calls or ballots matching the `VoteQuery` filters in `args`.
+fn query_votes(workspace: &Workspace, rows: &str, args: &[String]) -> Result<()> {
+    let mut query = VoteQuery::new();
+    let mut limit = 100;
+
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        let value = rest.next().map(|s| s.as_str()).unwrap_or_default();
+        let day = || dates::parse_date(value, None).ok_or_else(|| Error::parse("date", value));
+        query = match arg.as_str() {
+            "--chamber" => query.chamber(value.parse()?),
+            "--congress" => match value.split_once('-') {
+                Some((first, last)) => {
+                    let congress = |n: &str| n.trim().parse::<u16>().map_err(|_| Error::parse("congress range", value));
+                    query.congresses(congress(first)?..=congress(last)?)
+                }
+                None => query.congress(value.parse().map_err(|_| Error::parse("congress number", value))?),
+            },
+            "--session" => query.session(value.parse().map_err(|_| Error::parse("session number", value))?),
+            "--from" => query.from(day()?),
+            "--to" => query.to(day()?),
+            "--member" => query.member(value),
+            "--party" => query.party(Party::from(value)),
+            "--state" => query.state(value.parse()?),
+            "--cast" => query.vote_cast(value.parse::<VoteCast>()?),
+            "--question" => query.question(value),
+            "--result" => query.result(value),
+            "--bill" => query.bill(value),
+            "--limit" => {
+                limit = value.parse().map_err(|_| Error::parse("row count", value))?;
+                query
+            }
+            _ => {
+                usage();
+                query
+            }
+        };
+    }
+    let limit = if limit == 0 { usize::MAX } else { limit };
+
+    let conn = sqllite::open_database(workspace)?;
+    let (shown, total) = match rows {
+        "rollcalls" => {
+            let mut rollcalls = query.rollcalls(&conn)?;
+            let mut shown = 0;
+            for rollcall in rollcalls.iter()?.take(limit) {
+                let r = rollcall?;
+                println!(
+                    "📋 {} {}-{} #{} ({}) {} — {} {}-{} {}",
+                    r.chamber.to_string(),
+                    r.congress,
+                    r.session,
+                    r.rollcall,
+                    r.vote_date.as_deref().unwrap_or("?"),
+                    r.question.as_deref().unwrap_or_default(),
+                    r.result.as_deref().unwrap_or_default(),
+                    r.yea,
+                    r.nay,
+                    r.bill.as_deref().unwrap_or_default()
+                );
+                shown += 1;
+            }
+            (shown, query.count_rollcalls(&conn)?)
+        }
+        "ballots" => {
+            let mut ballots = query.ballots(&conn)?;
+            let mut shown = 0;
+            for ballot in ballots.iter()?.take(limit) {
+                let b = ballot?;
+                println!(
+                    "🗳️ {} {}-{} #{} ({}) {:<10} {:<25} {}-{} {:<10} {}",
+                    b.rollcall.chamber.to_string(),
+                    b.rollcall.congress,
+                    b.rollcall.session,
+                    b.rollcall.rollcall,
+                    b.rollcall.vote_date.as_deref().unwrap_or("?"),
+                    b.member_id,
+                    b.name.as_deref().unwrap_or_default(),
+                    b.party.map(|p| p.to_string()).unwrap_or_default(),
+                    b.state.map(|s| s.to_string()).unwrap_or_default(),
+                    b.vote_cast.map(|v| v.to_string()).unwrap_or_else(|| "?".to_string()),
+                    b.rollcall.question.as_deref().unwrap_or_default()
+                );
+                shown += 1;
+            }
+            (shown, query.count_ballots(&conn)?)
+        }
+        _ => {
+            usage();
+            (0, 0)
+        }
+    };
+
+    if total == 0 {
+        println!("📭 No {} match.", rows);
+    } else if shown < total {
+        println!("… {} of {} {} shown; use --limit 0 for all.", shown, total, rows);
+    }
+
+    Ok(())
+}
+
+/// Exports the members × roll calls matrix of the database, as dense CSV,
+/// sparse triplets and `.npy`, with row and column label files.
+fn matrix_export(workspace: &Workspace, args: &[String]) -> Result<()> {
+    let mut filter = MatrixFilter::default();
+    let mut out_dir = workspace.analytics_dir().join("matrix");
+
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        let value = rest.next().map(|s| s.as_str()).unwrap_or_default();
+        match arg.as_str() {
+            "--chamber" => filter.chamber = Some(value.parse()?),
+            "--congress" => filter.congress = Some(value.parse().map_err(|_| Error::parse("congress number", value))?),
+            "--session" => filter.session = Some(value.parse().map_err(|_| Error::parse("session number", value))?),
+            "--out" => out_dir = value.into(),
+            _ => usage(),
+        }
+    }
+
+    let conn = sqllite::open_database(workspace)?;
+    let matrix = VoteMatrix::build(&conn, &filter)?;
+    matrix.export(&out_dir)?;
+
+    println!(
+        "🧮 {} members × {} roll calls ({} votes) written to '{}'.",
+        matrix.rows(),
+        matrix.columns(),
+        matrix.triplets().count(),
+        out_dir.display()
+    );
+    Ok(())
+}
+
+/// Prints the roll calls whose text matches a query, best match first.
+fn search_votes(workspace: &Workspace, args: &[String]) -> Result<()> {
+    let mut filter = SearchFilter::default();
+    let mut limit = 20;
+    let mut words = vec![];
+
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        if !arg.starts_with("--") {
+            words.push(arg.as_str());
+            continue;
+        }
+
+        let value = rest.next().map(|s| s.as_str()).unwrap_or_default();
+        match arg.as_str() {
+            "--chamber" => filter.chamber = Some(value.parse()?),
+            "--congress" => filter.congress = Some(value.parse().map_err(|_| Error::parse("congress number", value))?),
+            "--limit" => limit = value.parse().map_err(|_| Error::parse("row count", value))?,
+            _ => usage(),
+        }
+    }
+
+    if words.is_empty() {
+        usage();
+    }
+
+    let conn = sqllite::open_database(workspace)?;
+    let query = words.join(" ");
+    let hits = search::search(&conn, &query, &filter, limit)?;
+
+    if hits.is_empty() {
+        println!("📭 No roll calls match '{}'.", query);
+        return Ok(());
+    }
+
+    for hit in &hits {
+        println!(
+            "🔎 {} {}-{} #{} ({}) {} — {}",
+            hit.chamber.to_string(),
+            hit.congress,
+            hit.session,
+            hit.rollcall,
+            hit.vote_date.as_deref().unwrap_or("?"),
+            hit.question.as_deref().unwrap_or_default(),
+            hit.result.as_deref().unwrap_or_default()
+        );
+        println!("    {}", hit.snippet);
+    }
+
+    Ok(())
+}
+
+/// Scales members of each chamber and congress in the database (or the ones
+/// asked for) and stores their positions in `member_ideal_points`.
+fn ideal_points_report(workspace: &Workspace, args: &[String]) -> Result<()> {
+    let mut filter = MatrixFilter::default();
+    let mut options = IdealPointOptions::default();
+
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        let value = rest.next().map(|s| s.as_str()).unwrap_or_default();
+        match arg.as_str() {
+            "--chamber" => filter.chamber = Some(value.parse()?),
+            "--congress" => filter.congress = Some(value.parse().map_err(|_| Error::parse("congress number", value))?),
+            "--dimensions" => options.dimensions = value.parse().map_err(|_| Error::parse("dimension count", value))?,
+            "--iterations" => options.max_iterations = value.parse().map_err(|_| Error::parse("iteration count", value))?,
+            "--min-votes" => options.min_votes = value.parse().map_err(|_| Error::parse("vote count", value))?,
+            _ => usage(),
+        }
+    }
+
+    let mut conn = sqllite::open_database(workspace)?;
+
+    let mut stmt = conn.prepare(
+        "SELECT DISTINCT chamber, congress FROM votes
+         WHERE (?1 IS NULL OR chamber = ?1) AND (?2 IS NULL OR congress = ?2)
+         ORDER BY chamber, congress",
+    )?;
+    let scopes = stmt
+        .query_map(rusqlite::params![filter.chamber.map(|c| c.to_string()), filter.congress], |row| {
+            Ok((CongressChamber::from(row.get::<_, String>(0)?.as_str()), row.get::<_, u16>(1)?))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    drop(stmt);
+
+    if scopes.is_empty() {
+        println!("📭 No roll calls in '{}'.", workspace.database_path().display());
+        return Ok(());
+    }
+
+    for (chamber, congress) in scopes {
+        let matrix = VoteMatrix::build(&conn, &MatrixFilter { chamber: Some(chamber), congress: Some(congress), session: None })?;
+        let fit = match ideal_points::estimate(&matrix, &options) {
+            Ok(fit) => fit,
+            // A scope that cannot be scaled is skipped; anything else stops the run
+            Err(e @ Error::Estimation { .. }) => {
+                println!("⚠️ {} {}: {}", chamber.to_string(), congress, e);
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+
+        let tx = conn.transaction()?;
+        ideal_points::insert_ideal_points(&tx, chamber, congress, &fit)?;
+        tx.commit()?;
+
+        println!(
+            "📐 {} {}: {} members on {} roll calls, {:.2}% of votes classified, {} iterations{}",
+            chamber.to_string(),
+            congress,
+            fit.points.len(),
+            fit.rollcalls,
+            fit.classified * 100.0,
+            fit.iterations,
+            if fit.converged { "" } else { " (not converged)" }
+        );
+    }
+
+    println!("✅ Ideal points written to '{}'.", workspace.database_path().display());
+    Ok(())
+}
+
+/// Takes the workspace options out of `args`, wherever they appear, and builds
+/// the workspace from them. The root defaults to `$CONGRESS_ROLLS_ROOT`, then the current directory.
+fn workspace_args(args: Vec<String>) -> Result<(Workspace, Vec<String>)> {
+    let mut options: Vec<(String, String)> = vec![];
+    let mut rest = Vec::with_capacity(args.len());
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if matches!(arg.as_str(), "--root" | "--db" | "--json-out" | "--analytics-out" | "--layout") {
+            let value = args.next().ok_or_else(|| Error::Config { message: format!("{} needs a value", arg) })?;
+            options.push((arg, value));
+        } else {
+            rest.push(arg);
+        }
+    }
+
+    // The root comes first, the other paths are relative to it
+    let mut workspace = match options.iter().rev().find(|(option, _)| option == "--root") {
+        Some((_, root)) => Workspace::new(root),
+        None => Workspace::from_env(),
+    };
+
+    for (option, value) in options {
+        workspace = match option.as_str() {
+            "--db" => workspace.database(value),
+            "--json-out" => workspace.master_json(value),
+            "--analytics-out" => workspace.analytics(value),
+            "--layout" => workspace.layout(&value)?,
+            _ => workspace,
+        };
+    }
+
+    Ok((workspace, rest))
+}
 
 /// Displays the usage instructions and exits the program.
 fn usage() {
     let message = "
 📜 Usage:
-    congress_rolls download_xml <chamber> <congress_number> <session> <max_roll>
-    congress_rolls process_votes <json|sql> <optional: file_path>
+    congress_rolls [workspace options] download_xml <chamber> <congress_number> <session> <max_roll|latest> <optional: --refresh>
+            - --refresh: Request existing files again, replace and re-ingest the ones that changed (see data/xml/changelog.log)
+    congress_rolls [workspace options] import_legislators <crosswalk.yaml|crosswalk.json>...
+            - Load congress-legislators files (legislators-current.yaml, legislators-historical.yaml) into the legislator registry
+              House bioguide ids and Senate LIS ids of the same person link to one legislator
+    congress_rolls [workspace options] db <status|migrate>
+            - status: The database's schema version and which migrations it has, without changing it
+            - migrate: Upgrade the database in place to the latest schema version (other commands do this too when they open it)
+    congress_rolls [workspace options] attendance <optional: --chamber house|senate> <optional: --congress N> <optional: --top N>
+            - Attendance from the database: chamber-wide rates per session and congress, the members
+              who missed the most votes, and the longest streaks of missed votes (top 10 by default)
+    congress_rolls [workspace options] matrix <optional: --chamber house|senate> <optional: --congress N> <optional: --session N> <optional: --out DIR>
+            - Members × roll calls matrix from the database, +1 yea, -1 nay, 0 present or not voting, empty when not on the roll call
+              Writes matrix.csv, matrix_triplets.csv, matrix.npy and the matrix_members.csv/matrix_rollcalls.csv labels
+              to full_data/analytics/matrix by default
+    congress_rolls [workspace options] votes <rollcalls|ballots> <optional filters> <optional: --limit N>
+            - Roll calls or ballots from the database, filtered by --chamber house|senate, --congress N or N-M, --session N,
+              --from DATE, --to DATE, --member ID, --party D|R|I, --state XX, --cast yea|nay|present|\"not voting\",
+              --question TEXT, --result TEXT and --bill NUMBER; prints the first 100 (--limit 0 for all)
+              Roll calls filtered by member, party, state or cast are those with at least one such ballot
+    congress_rolls [workspace options] search <query> <optional: --chamber house|senate> <optional: --congress N> <optional: --limit N>
+            - Full-text search of vote descriptions, titles, questions, bill numbers, documents and amendment purposes
+              The query uses SQLite FTS5 syntax (AND, OR, NOT, \"phrases\", prefix*); prints the 20 best matches with snippets
+    congress_rolls [workspace options] serve <optional: --addr HOST:PORT>
+            - Read-only JSON API over the database on http://127.0.0.1:8080 by default: /rollcalls, /rollcalls/{chamber}/{congress}/{session}/{number},
+              /members/{id}, /members/{id}/votes, /bills/{bill}/votes and /search?q=, filtered by chamber, congress, session, from and to
+              (YYYY-MM-DD) and paged with offset and limit (100 by default, at most 1000)
+    congress_rolls [workspace options] ideal_points <optional: --chamber house|senate> <optional: --congress N> <optional: --dimensions 1|2> <optional: --iterations N> <optional: --min-votes N>
+            - Ideal points of the members of each chamber and congress in the database, in 1 (default) or 2 dimensions,
+              with standard errors; Republicans score higher on every dimension. Written to the member_ideal_points table
+              Near-unanimous roll calls and members with fewer than 20 yea/nay votes are left out
+    congress_rolls [workspace options] analyze <party_unity>
+            - party_unity: Votes where most Democrats opposed most Republicans, each member's party-unity score
+              per session and congress, and every defection; written to the database and as CSV to full_data/analytics
+    congress_rolls [workspace options] process_votes <json|sql> <optional: --json> <optional: --workers N> <optional: file_path>
             - json: Process votes and create a master JSON file
             - sql: Process votes and add data to the SQLite database
-                - file_path: Optional file path for adding new data to the database, this is only used with the SQL mode
+                - Reads the downloaded XML in data/xml directly
+                - --json: Read the yq-converted files in data/json instead
+                - --workers N: Number of threads parsing files, one per core by default
+                - file_path: Optional .json or .xml file for adding new data to the database, this is only used with the SQL mode
+                    The file can be anywhere, its roll call is read from its content; use - to read it from stdin
+
+🗂️ Workspace options (may appear anywhere):
+    --root DIR: Directory holding data/ and full_data/, defaults to $CONGRESS_ROLLS_ROOT or the current directory
+    --db FILE: SQLite database, defaults to full_data/votes.db under the root
+    --json-out FILE: Master JSON file, defaults to full_data/votes.json under the root
+    --analytics-out DIR: Directory for analytics CSV files, defaults to full_data/analytics under the root
+    --layout TEMPLATE: Vote file layout below data/json and data/xml, defaults to {congress}/{chamber}/{session}/{year}_{roll}.{ext}
 
 📝 Examples:
     congress_rolls download_xml house 116 1 10
+    congress_rolls download_xml senate 118 2 latest
+    congress_rolls download_xml senate 118 2 latest --refresh
+    congress_rolls import_legislators legislators-current.yaml legislators-historical.yaml
+    congress_rolls db status
+    congress_rolls --db full_data/votes.db db migrate
+    congress_rolls analyze party_unity
+    congress_rolls attendance --chamber house --congress 118
+    congress_rolls matrix --chamber senate --congress 118 --out votes_118
+    congress_rolls ideal_points --chamber senate --congress 118 --dimensions 2
+    congress_rolls search \"border security\" --chamber senate --limit 5
+    congress_rolls votes ballots --member S354 --congress 115-118 --cast nay --question cloture
+    congress_rolls votes rollcalls --bill \"H.R. 2882\"
+    congress_rolls serve --addr 127.0.0.1:8080
     congress_rolls process_votes json
     congress_rolls process_votes sql data/json/118/house/2/2024_1.json
+    congress_rolls process_votes sql --json
+    congress_rolls process_votes json --workers 8
+    congress_rolls process_votes sql data/xml/118/senate/2/2024_1.xml
+    curl -s https://clerk.house.gov/evs/2024/roll010.xml | congress_rolls process_votes sql -
+    congress_rolls --root /srv/rollcalls process_votes sql
     ";
     println!("{}", message);
     std::process::exit(1);
 }
 
-/// Asynchronously downloads XML files for the specified roll calls.
-async fn xml_download() {
-    let args: Vec<String> = std::env::args().collect();
+/// Asynchronously downloads XML files for the specified roll calls into the workspace.
+async fn xml_download(workspace: &Workspace, all_args: &[String]) -> Result<()> {
+    // Re-request files that already exist and re-ingest the ones that changed
+    let refresh = all_args.iter().any(|a| a == "--refresh");
+    let args: Vec<&str> = all_args.iter().map(|a| a.as_str()).filter(|a| *a != "--refresh").collect();
 
     if args.len() != 6 {
         usage();
     }
 
-    let chamber = &args[2];
-    let congress_number = args[3].parse::<u32>().expect("❌ Invalid congress number");
-    let session = args[4].parse::<u32>().expect("❌ Invalid session number");
-    let max_roll = args[5].parse::<u32>().expect("❌ Invalid max roll number");
-
-    let client = Client::new(false);
-
-    for roll in 1..=max_roll {
-        let congress = Congress::new(chamber, congress_number, session, roll);
+    let chamber = args[2];
+    let congress_number = args[3].parse::<u32>().map_err(|_| Error::parse("congress number", args[3]))?;
+    let session = args[4].parse::<u32>().map_err(|_| Error::parse("session number", args[4]))?;
+    let client = Client::new(None)?;
 
-        // Create directories
-        let base_dir = format!("data/xml/{}/{}", congress.number, chamber.to_lowercase());
-        let session_dir = format!("{}/{}", base_dir, congress.session);
+    // `latest` looks the number up on the House EVS index or the Senate vote menu
+    let max_roll = if args[5] == "latest" {
+        let latest = client.latest_roll(&Congress::new(chamber, congress_number, session, 0)).await?;
+        println!("🔎 Latest roll call for {} {}-{}: {}", chamber, congress_number, session, latest);
+        latest
+    } else {
+        args[5].parse::<u32>().map_err(|_| Error::parse("max roll number", args[5]))?
+    };
 
-        // Check if the session directory exists
-        // If it does, print nothing anc continue
-        // If it doesn't, create the directories
-        let path = std::path::Path::new(&session_dir);
-        let exists = path.exists();
-
-        // Create directories if they don't exist
-        if let Err(e) = std::fs::create_dir_all(&session_dir) {
-            eprintln!("❌ Failed to create directories {}: {}", session_dir, e);
-            continue;
-        }
-
-        if !exists {
-            println!("📁 Created directories: {}", session_dir);
-        }
+    let jobs = (1..=max_roll)
+        .map(|roll| {
+            let congress = Congress::new(chamber, congress_number, session, roll);
+            let path = workspace.vote_file_path(VoteFormat::Xml, &congress)?;
 
-        let file_name = format!("{}/{}_{}.xml", session_dir, congress.to_year(), congress.roll_call);
+            Ok(DownloadJob { url: congress.url()?, path })
+        })
+        .collect::<Result<Vec<_>>>()?;
 
-        // Skip if file already exists
-        if std::path::Path::new(&file_name).exists() {
-            println!("⚠️ {} already exists", file_name);
-            continue;
-        }
+    let report = Downloader::new(client)
+        .refresh(refresh)
+        .manifest(workspace.manifest_path())
+        .changelog(workspace.changelog_path())
+        .run(jobs)
+        .await?;
 
-        // Fetch data
-        match client.fetch_data(&congress.to_string()).await {
-            Ok(response) => {
-                if response.contains("Webmaster") || response.contains("redirect") {
-                    println!("🛑 Possible error: Redirect or webmaster blocking authentication for {}", file_name);
-                    continue;
-                }
+    println!(
+        "📄 {} downloaded, 🔁 {} changed, ⚠️ {} already present, ❌ {} failed",
+        report.downloaded,
+        report.changed.len(),
+        report.skipped + report.unchanged,
+        report.failed.len()
+    );
+    for (job, e) in &report.failed {
+        eprintln!("❌ {}: {}", job.path.display(), e);
+    }
 
-                // Write to file
-                match std::fs::write(&file_name, response) {
-                    Ok(_) => println!("📄 {} written successfully.", file_name),
-                    Err(e) => eprintln!("❌ Error writing {}: {}", file_name, e),
-                }
-            }
-            Err(e) => {
-                eprintln!("❌ Error fetching data for {}: {}", file_name, e);
-            }
-        }
+    // Corrected roll calls replace their old rows, keyed on chamber, congress, session and roll call
+    if !report.changed.is_empty() {
+        let entries = report
+            .changed
+            .iter()
+            .map(|change| {
+                println!("🔁 Re-ingesting {}", change.path.display());
+                workspace.parse_vote_path(&change.path)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        sqllite::insert_vote_entries_sql(workspace, entries, 1).await?;
     }
-}
 
+    Ok(())
+}
@@ -0,0 +1,264 @@
+// matrix.rs
+//
+// Members × roll calls vote matrix, read from the SQLite store, for scaling and
+// clustering. Cells are +1 (yea), -1 (nay), 0 (on the roll but no position:
+// present or not voting) or missing (not on the roll call at all, e.g. not yet
+// sworn in). Rows are keyed on member ids (bioguide or LIS), columns on roll
+// call ids of the form `house-118-2-10`.
+
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::Path;
+
+use rusqlite::{params, Connection};
+use serde::Serialize;
+
+use crate::analytics::{csv_error, write_csv};
+use crate::endpoints::CongressChamber;
+use crate::model::VoteCast;
+use crate::{Error, Result};
+
+/// Which roll calls become columns. `None` means all of them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MatrixFilter {
+    pub chamber: Option<CongressChamber>,
+    pub congress: Option<u16>,
+    pub session: Option<u8>,
+}
+
+/// Row label.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MemberLabel {
+    pub row: usize,
+    pub member_id: String,
+    pub chamber: CongressChamber,
+    pub name: Option<String>,
+    /// Party and state on the member's last ballot in the matrix.
+    pub party: Option<String>,
+    pub state: Option<String>,
+}
+
+/// Column label.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RollCallLabel {
+    pub column: usize,
+    pub rollcall_id: String,
+    pub chamber: CongressChamber,
+    pub congress: u16,
+    pub session: u8,
+    pub rollcall: u32,
+    /// "2024-01-09", or the date as published if it could not be parsed.
+    pub vote_date: Option<String>,
+    pub question: Option<String>,
+}
+
+/// One non-missing cell, by row and column index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Triplet {
+    pub row: usize,
+    pub column: usize,
+    pub value: i8,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VoteMatrix {
+    pub members: Vec<MemberLabel>,
+    pub rollcalls: Vec<RollCallLabel>,
+    /// Non-missing cells by (row, column); a dense array is only laid out row by row on export.
+    cells: BTreeMap<(usize, usize), i8>,
+}
+
+/// Stable id of a roll call, e.g. `house-118-2-10`.
+pub fn rollcall_id(chamber: CongressChamber, congress: u16, session: u8, rollcall: u32) -> String {
+    format!("{}-{}-{}-{}", chamber.to_string(), congress, session, rollcall)
+}
+
+/// Cell value of a recorded vote: +1 yea, -1 nay, 0 for anything else.
+pub fn encode(vote_cast: Option<&str>) -> i8 {
+    vote_cast.and_then(|v| v.parse::<VoteCast>().ok()).and_then(|v| v.position()).unwrap_or(0)
+}
+
+impl VoteMatrix {
+    /// Builds the matrix of the roll calls matching `filter` from the database.
+    pub fn build(conn: &Connection, filter: &MatrixFilter) -> Result<Self> {
+        let chamber = filter.chamber.map(|c| c.to_string());
+
+        // Every matching roll call is a column, even one without ballots
+        let mut columns: BTreeMap<(CongressChamber, u16, u8, u32), RollCallLabel> = BTreeMap::new();
+        let mut stmt = conn.prepare(
+            "SELECT chamber, congress, session, rollcall, COALESCE(vote_day, vote_date), question FROM votes
+             WHERE (?1 IS NULL OR chamber = ?1) AND (?2 IS NULL OR congress = ?2) AND (?3 IS NULL OR session = ?3)",
+        )?;
+        let mut rows = stmt.query(params![chamber, filter.congress, filter.session])?;
+        while let Some(row) = rows.next()? {
+            let chamber = CongressChamber::from(row.get::<_, String>(0)?.as_str());
+            let (congress, session, rollcall) = (row.get(1)?, row.get(2)?, row.get(3)?);
+            columns.insert(
+                (chamber, congress, session, rollcall),
+                RollCallLabel {
+                    column: 0,
+                    rollcall_id: rollcall_id(chamber, congress, session, rollcall),
+                    chamber,
+                    congress,
+                    session,
+                    rollcall,
+                    vote_date: row.get(4)?,
+                    question: row.get(5)?,
+                },
+            );
+        }
+
+        let mut members: BTreeMap<(CongressChamber, String), MemberLabel> = BTreeMap::new();
+        let mut ballots = vec![];
+        let mut stmt = conn.prepare(
+            "SELECT v.chamber, v.congress, v.session, v.rollcall, mv.member_id, m.full_name, mv.party, mv.state, mv.vote_cast
+             FROM member_votes mv
+             JOIN votes v ON v.vote_hash = mv.vote_hash
+             JOIN members m ON m.member_id = mv.member_id
+             WHERE (?1 IS NULL OR v.chamber = ?1) AND (?2 IS NULL OR v.congress = ?2) AND (?3 IS NULL OR v.session = ?3)
+             ORDER BY v.congress, v.session, v.rollcall",
+        )?;
+        let mut rows = stmt.query(params![chamber, filter.congress, filter.session])?;
+        while let Some(row) = rows.next()? {
+            let chamber = CongressChamber::from(row.get::<_, String>(0)?.as_str());
+            let column = (chamber, row.get(1)?, row.get(2)?, row.get(3)?);
+            let member_id: String = row.get(4)?;
+
+            let label = members.entry((chamber, member_id.clone())).or_insert_with(|| MemberLabel {
+                row: 0,
+                member_id,
+                chamber,
+                name: None,
+                party: None,
+                state: None,
+            });
+            label.name = row.get(5)?;
+            label.party = row.get(6)?;
+            label.state = row.get(7)?;
+
+            let vote_cast: Option<String> = row.get(8)?;
+            ballots.push(((chamber, label.member_id.clone()), column, encode(vote_cast.as_deref())));
+        }
+
+        let mut matrix = VoteMatrix::default();
+        let mut row_index = BTreeMap::new();
+        for (i, (key, mut label)) in members.into_iter().enumerate() {
+            label.row = i;
+            row_index.insert(key, i);
+            matrix.members.push(label);
+        }
+        let mut column_index = BTreeMap::new();
+        for (i, (key, mut label)) in columns.into_iter().enumerate() {
+            label.column = i;
+            column_index.insert(key, i);
+            matrix.rollcalls.push(label);
+        }
+
+        for (member, column, value) in ballots {
+            matrix.cells.insert((row_index[&member], column_index[&column]), value);
+        }
+
+        Ok(matrix)
+    }
+
+    pub fn rows(&self) -> usize {
+        self.members.len()
+    }
+
+    pub fn columns(&self) -> usize {
+        self.rollcalls.len()
+    }
+
+    pub fn get(&self, row: usize, column: usize) -> Option<i8> {
+        self.cells.get(&(row, column)).copied()
+    }
+
+    /// Row of a member, by member id.
+    pub fn row_of(&self, member_id: &str) -> Option<usize> {
+        self.members.iter().position(|m| m.member_id == member_id)
+    }
+
+    /// Column of a roll call, by roll call id.
+    pub fn column_of(&self, rollcall_id: &str) -> Option<usize> {
+        self.rollcalls.iter().position(|r| r.rollcall_id == rollcall_id)
+    }
+
+    /// The non-missing cells, row by row.
+    pub fn triplets(&self) -> impl Iterator<Item = Triplet> + '_ {
+        self.cells.iter().map(|(&(row, column), &value)| Triplet { row, column, value })
+    }
+
+    /// One row laid out densely, missing cells `None`.
+    fn dense_row(&self, row: usize) -> Vec<Option<i8>> {
+        let mut cells = vec![None; self.columns()];
+        for (&(_, column), &value) in self.cells.range((row, 0)..(row + 1, 0)) {
+            cells[column] = Some(value);
+        }
+        cells
+    }
+
+    /// Writes a header of roll call ids and a row per member, missing cells empty.
+    pub fn write_dense_csv(&self, path: &Path) -> Result<()> {
+        let csv_err = csv_error(path);
+        let mut writer = csv::Writer::from_path(path).map_err(csv_err)?;
+
+        let header = std::iter::once("member_id").chain(self.rollcalls.iter().map(|r| r.rollcall_id.as_str()));
+        writer.write_record(header).map_err(csv_err)?;
+
+        for (row, member) in self.members.iter().enumerate() {
+            let cells = self.dense_row(row).into_iter().map(|cell| match cell {
+                Some(value) => value.to_string(),
+                None => String::new(),
+            });
+            writer.write_record(std::iter::once(member.member_id.clone()).chain(cells)).map_err(csv_err)?;
+        }
+
+        writer.flush().map_err(|e| Error::io(path, e))
+    }
+
+    /// Writes `row,column,value` for every non-missing cell, indices from 0.
+    pub fn write_triplets(&self, path: &Path) -> Result<()> {
+        write_csv(path, self.triplets())
+    }
+
+    /// Writes a NumPy `.npy` array of `float32`, missing cells as NaN.
+    pub fn write_npy(&self, path: &Path) -> Result<()> {
+        let io_err = |e| Error::io(path, e);
+        let mut out = std::io::BufWriter::new(std::fs::File::create(path).map_err(io_err)?);
+
+        // Format 1.0: magic, version, header length, then a dict padded so the data starts on a 64 byte boundary
+        let mut header = format!(
+            "{{'descr': '<f4', 'fortran_order': False, 'shape': ({}, {}), }}",
+            self.rows(),
+            self.columns()
+        );
+        let unpadded = 10 + header.len() + 1;
+        header.push_str(&" ".repeat((64 - unpadded % 64) % 64));
+        header.push('\n');
+
+        out.write_all(b"\x93NUMPY\x01\x00").map_err(io_err)?;
+        out.write_all(&(header.len() as u16).to_le_bytes()).map_err(io_err)?;
+        out.write_all(header.as_bytes()).map_err(io_err)?;
+
+        for row in 0..self.rows() {
+            for cell in self.dense_row(row) {
+                let value = cell.map(f32::from).unwrap_or(f32::NAN);
+                out.write_all(&value.to_le_bytes()).map_err(io_err)?;
+            }
+        }
+
+        out.flush().map_err(io_err)
+    }
+
+    /// Writes `matrix.csv`, `matrix_triplets.csv`, `matrix.npy` and the label files
+    /// `matrix_members.csv` and `matrix_rollcalls.csv` to `dir`.
+    pub fn export(&self, dir: &Path) -> Result<()> {
+        std::fs::create_dir_all(dir).map_err(|e| Error::io(dir, e))?;
+
+        self.write_dense_csv(&dir.join("matrix.csv"))?;
+        self.write_triplets(&dir.join("matrix_triplets.csv"))?;
+        self.write_npy(&dir.join("matrix.npy"))?;
+        write_csv(&dir.join("matrix_members.csv"), &self.members)?;
+        write_csv(&dir.join("matrix_rollcalls.csv"), &self.rollcalls)
+    }
+}
@@ -0,0 +1,219 @@
+// migrations.rs
+//
+// Ordered schema migrations for votes.db. Each migration brings the database
+// from the previous version to its own, in one transaction, and is recorded in
+// the `schema_version` table; `migrate` applies the ones a database is missing,
+// so a new database runs them all and an existing one (the prebuilt
+// `full_data/votes.db`) is upgraded in place. Their DDL is in
+// `full_data/migrations`, next to `schema.sql`, which is where they end up.
+//
+// Before `schema_version` existed the version was only kept in
+// `PRAGMA user_version`. Such databases are taken to have applied every
+// migration up to it; `user_version` still mirrors the latest version for
+// tools that read it.
+//
+// Migrations are written so that re-applying one to a database that already
+// has it changes nothing, which keeps a half-recorded upgrade recoverable.
+
+use std::collections::BTreeMap;
+
+use rusqlite::{params, Connection};
+
+use crate::dates::VoteTimes;
+use crate::{Error, Result};
+
+pub struct Migration {
+    pub version: i32,
+    pub name: &'static str,
+    apply: fn(&Connection) -> Result<()>,
+}
+
+/// Every migration, in the order they are applied.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "roll calls, totals, documents, amendments, members and ballots",
+        apply: |conn| Ok(conn.execute_batch(include_str!("../full_data/migrations/001_initial.sql"))?),
+    },
+    Migration {
+        version: 2,
+        name: "House Speaker election candidate totals",
+        apply: |conn| Ok(conn.execute_batch(include_str!("../full_data/migrations/002_candidate_totals.sql"))?),
+    },
+    Migration {
+        version: 3,
+        name: "legislator registry and Senate members keyed on LIS id",
+        apply: |conn| Ok(conn.execute_batch(include_str!("../full_data/migrations/003_legislators.sql"))?),
+    },
+    Migration {
+        version: 4,
+        name: "party unity votes, scores and defections",
+        apply: |conn| Ok(conn.execute_batch(include_str!("../full_data/migrations/004_party_unity.sql"))?),
+    },
+    Migration {
+        version: 5,
+        name: "member ideal points",
+        apply: |conn| Ok(conn.execute_batch(include_str!("../full_data/migrations/005_ideal_points.sql"))?),
+    },
+    Migration {
+        version: 6,
+        name: "parsed vote days and Eastern-time timestamps",
+        apply: |conn| {
+            add_vote_time_columns(conn)?;
+            conn.execute_batch(include_str!("../full_data/migrations/006_vote_times.sql"))?;
+            backfill_vote_times(conn)
+        },
+    },
+    Migration {
+        version: 7,
+        name: "full-text search index",
+        apply: |conn| {
+            conn.execute_batch(include_str!("../full_data/migrations/007_search.sql"))?;
+            crate::search::rebuild_index(conn)?;
+            Ok(())
+        },
+    },
+];
+
+/// Version of the schema in `full_data/schema.sql`: that of the last migration.
+pub const SCHEMA_VERSION: i32 = MIGRATIONS[MIGRATIONS.len() - 1].version;
+
+const SCHEMA_VERSION_SQL: &str = "CREATE TABLE IF NOT EXISTS schema_version (
+    version    INTEGER PRIMARY KEY NOT NULL,
+    name       TEXT NOT NULL,
+    applied_at TEXT
+);";
+
+/// Whether a migration has been applied to a database, and when.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationStatus {
+    pub version: i32,
+    pub name: &'static str,
+    pub applied: bool,
+    /// UTC, "2024-01-09T22:31:00Z"; `None` if applied before `schema_version` existed, or pending.
+    pub applied_at: Option<String>,
+}
+
+fn has_schema_version_table(conn: &Connection) -> Result<bool> {
+    let count: i32 = conn.query_row("SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'schema_version'", [], |row| row.get(0))?;
+    Ok(count > 0)
+}
+
+/// The versions applied to a database and when, reading `PRAGMA user_version` for databases from before `schema_version`.
+fn applied(conn: &Connection) -> Result<BTreeMap<i32, Option<String>>> {
+    if has_schema_version_table(conn)? {
+        let mut stmt = conn.prepare("SELECT version, applied_at FROM schema_version")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?.collect::<rusqlite::Result<_>>()?;
+        return Ok(rows);
+    }
+
+    let version: i32 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    Ok((1..=version).map(|v| (v, None)).collect())
+}
+
+/// The latest version applied to a database, 0 for a new one.
+pub fn current_version(conn: &Connection) -> Result<i32> {
+    Ok(applied(conn)?.keys().next_back().copied().unwrap_or(0))
+}
+
+/// Every migration and whether the database has it. Reads the database without changing it.
+pub fn status(conn: &Connection) -> Result<Vec<MigrationStatus>> {
+    let applied = applied(conn)?;
+    Ok(MIGRATIONS
+        .iter()
+        .map(|m| MigrationStatus {
+            version: m.version,
+            name: m.name,
+            applied: applied.contains_key(&m.version),
+            applied_at: applied.get(&m.version).cloned().flatten(),
+        })
+        .collect())
+}
+
+/// Applies the migrations a database is missing, in order, and returns their versions.
+/// A database from a newer version of the crate is an error.
+pub fn migrate(conn: &Connection) -> Result<Vec<i32>> {
+    let version = current_version(conn)?;
+    if version > SCHEMA_VERSION {
+        return Err(Error::Schema {
+            path: conn.path().map(std::path::PathBuf::from),
+            roll_call: None,
+            message: format!("Database schema version {} is newer than supported version {}", version, SCHEMA_VERSION),
+        });
+    }
+
+    // Outside a transaction, where SQLite ignores it
+    conn.pragma_update(None, "foreign_keys", true)?;
+
+    // Record what a database from before `schema_version` already has
+    if !has_schema_version_table(conn)? {
+        let tx = conn.unchecked_transaction()?;
+        tx.execute_batch(SCHEMA_VERSION_SQL)?;
+        for m in MIGRATIONS.iter().filter(|m| m.version <= version) {
+            tx.execute("INSERT INTO schema_version (version, name) VALUES (?1, ?2)", params![m.version, m.name])?;
+        }
+        tx.commit()?;
+    }
+
+    let mut applied = vec![];
+    for m in MIGRATIONS.iter().filter(|m| m.version > version) {
+        let tx = conn.unchecked_transaction()?;
+        (m.apply)(&tx).map_err(|e| match e {
+            Error::Database { source, .. } => Error::Schema {
+                path: conn.path().map(std::path::PathBuf::from),
+                roll_call: None,
+                message: format!("Migration {} ({}) failed: {}", m.version, m.name, source),
+            },
+            e => e,
+        })?;
+        tx.execute(
+            "INSERT OR REPLACE INTO schema_version (version, name, applied_at) VALUES (?1, ?2, strftime('%Y-%m-%dT%H:%M:%SZ', 'now'))",
+            params![m.version, m.name],
+        )?;
+        tx.pragma_update(None, "user_version", m.version)?;
+        tx.commit()?;
+        applied.push(m.version);
+    }
+
+    Ok(applied)
+}
+
+/// Version 6 adds the parsed vote times to `votes`; `backfill_vote_times` fills them in.
+fn add_vote_time_columns(conn: &Connection) -> Result<()> {
+    let mut stmt = conn.prepare("SELECT name FROM pragma_table_info('votes')")?;
+    let columns = stmt.query_map([], |row| row.get::<_, String>(0))?.collect::<rusqlite::Result<Vec<_>>>()?;
+
+    for (column, kind) in [("vote_day", "TEXT"), ("vote_timestamp", "TEXT"), ("vote_epoch", "INTEGER"), ("modified_timestamp", "TEXT")] {
+        if !columns.iter().any(|c| c == column) {
+            conn.execute_batch(&format!("ALTER TABLE votes ADD COLUMN {} {};", column, kind))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Parses the raw dates and times of every roll call into the vote time columns.
+fn backfill_vote_times(conn: &Connection) -> Result<()> {
+    let mut stmt = conn.prepare("SELECT vote_hash, chamber, year, vote_date, action_time_etz, action_time, modify_date FROM votes")?;
+    let rows = stmt
+        .query_map([], |row| {
+            let chamber: String = row.get(1)?;
+            let year: Option<i32> = row.get(2)?;
+            let text = |i| row.get::<_, Option<String>>(i);
+            let times = match chamber.as_str() {
+                "house" => VoteTimes::house(text(3)?.as_deref(), text(4)?.as_deref(), text(5)?.as_deref(), year),
+                _ => VoteTimes::senate(text(3)?.as_deref(), text(6)?.as_deref(), year),
+            };
+            Ok((row.get::<_, String>(0)?, times))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    for (vote_hash, times) in rows {
+        conn.execute(
+            "UPDATE votes SET vote_day = ?2, vote_timestamp = ?3, vote_epoch = ?4, modified_timestamp = ?5 WHERE vote_hash = ?1",
+            params![vote_hash, times.day_string(), times.at_string(), times.epoch(), times.modified_string()],
+        )?;
+    }
+
+    Ok(())
+}
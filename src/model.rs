@@ -0,0 +1,677 @@
+// model.rs
+//
+// Typed, chamber-neutral view of a roll call. Missing values are `None`, and
+// the free-text fields that have a closed vocabulary become enums. Both the
+// SQL and the master JSON paths are built from it, writing `None` as NULL.
+
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, NaiveTime};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
+use crate::dates;
+use crate::endpoints::{session_start, CongressChamber, CongressEndpoint};
+use crate::responses::*;
+use crate::{Error, Result};
+
+/// A single roll call vote from either chamber.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RollCall {
+    pub chamber: CongressChamber,
+    pub congress: u16,
+    pub session: u8,
+    pub number: u32,
+    pub date: Option<NaiveDate>,
+    pub time: Option<NaiveTime>,
+    /// Senate `modify_date`; the clerk does not publish one.
+    pub modified: Option<NaiveDateTime>,
+    /// "On Passage", "On the Cloture Motion", ...
+    pub question: Option<String>,
+    /// House `vote-desc`, Senate `vote_title`.
+    pub description: Option<String>,
+    pub result: Option<String>,
+    /// House `vote-type` ("YEA-AND-NAY", "2/3 RECORDED VOTE", ...).
+    pub vote_type: Option<String>,
+    /// Senate `majority_requirement` ("1/2", "3/5", ...).
+    pub majority_requirement: Option<String>,
+    /// House `legis-num`, or the first Senate document name ("H.R. 2882", "PN1042").
+    pub bill: Option<String>,
+    /// Senate `amendment_number` of the first amendment, if any.
+    pub amendment: Option<String>,
+    pub ballots: Vec<Ballot>,
+}
+
+impl RollCall {
+    /// When the vote was held, in Eastern time. `None` without a published time of day.
+    pub fn timestamp(&self) -> Option<DateTime<Tz>> {
+        Some(dates::to_eastern(self.date?, self.time?))
+    }
+}
+
+/// Which roll call a document records, read from the document rather than where it is stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct RollCallId {
+    pub chamber: CongressChamber,
+    pub congress: u16,
+    pub session: u8,
+    pub number: u32,
+    /// Year the session convened, which roll calls are filed under.
+    /// Falls back to the year of the vote for sessions missing from the calendar.
+    pub year: u16,
+}
+
+impl RollCallId {
+    pub fn endpoint(&self) -> CongressEndpoint {
+        CongressEndpoint::new(&self.chamber.to_string(), self.congress as u32, self.session as u32, self.number)
+    }
+}
+
+impl std::fmt::Display for RollCallId {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} {}-{} #{} ({})", self.chamber.to_string(), self.congress, self.session, self.number, self.year)
+    }
+}
+
+/// Year a session convened, or the year of `date` if the calendar doesn't know the session.
+fn session_year(congress: u16, session: u8, date: Option<NaiveDate>) -> Option<u16> {
+    session_start(congress as u32, session as u32)
+        .or(date)
+        .and_then(|d| u16::try_from(d.year()).ok())
+}
+
+/// A legislator as they appear on a ballot.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Member {
+    /// House `name-id`.
+    pub bioguide_id: Option<String>,
+    /// Senate `lis_member_id`.
+    pub lis_member_id: Option<String>,
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+    /// Display name, e.g. "Baldwin (D-WI)" or "Adams".
+    pub full_name: Option<String>,
+    pub party: Option<Party>,
+    pub state: Option<State>,
+}
+
+impl Member {
+    /// The chamber-issued identifier: bioguide for the House, LIS for the Senate.
+    pub fn id(&self) -> Option<&str> {
+        self.bioguide_id.as_deref().or(self.lis_member_id.as_deref())
+    }
+}
+
+/// One member's ballot on a roll call.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Ballot {
+    pub member: Member,
+    /// `None` when the ballot records no vote at all.
+    pub cast: Option<VoteCast>,
+    /// Senate pairing note, when the vote was a live pair.
+    pub paired_with: Option<String>,
+}
+
+/// How a member voted.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum VoteCast {
+    /// "Yea", "Aye" or "Yes".
+    Yea,
+    /// "Nay" or "No".
+    Nay,
+    Present,
+    NotVoting,
+    /// Impeachment trials.
+    Guilty,
+    NotGuilty,
+    /// Speaker elections record the candidate's name instead of a position.
+    Candidate(String),
+}
+
+impl VoteCast {
+    /// +1 for yea, -1 for nay, `None` for anything that is not a position on the question.
+    pub fn position(&self) -> Option<i8> {
+        match self {
+            VoteCast::Yea | VoteCast::Guilty => Some(1),
+            VoteCast::Nay | VoteCast::NotGuilty => Some(-1),
+            _ => None,
+        }
+    }
+}
+
+/// Any text other than the positions is a candidate's name; blank text is not a vote cast.
+impl std::str::FromStr for VoteCast {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s.trim().to_lowercase().as_str() {
+            "" => return Err(Error::parse("vote cast", s)),
+            "yea" | "aye" | "yes" => VoteCast::Yea,
+            "nay" | "no" => VoteCast::Nay,
+            "not voting" => VoteCast::NotVoting,
+            "guilty" => VoteCast::Guilty,
+            "not guilty" => VoteCast::NotGuilty,
+            p if p.starts_with("present") => VoteCast::Present,
+            _ => VoteCast::Candidate(s.trim().to_string()),
+        })
+    }
+}
+
+impl TryFrom<String> for VoteCast {
+    type Error = Error;
+
+    fn try_from(s: String) -> Result<Self> {
+        s.parse()
+    }
+}
+
+impl From<VoteCast> for String {
+    fn from(v: VoteCast) -> Self {
+        v.to_string()
+    }
+}
+
+impl std::fmt::Display for VoteCast {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            VoteCast::Yea => write!(f, "Yea"),
+            VoteCast::Nay => write!(f, "Nay"),
+            VoteCast::Present => write!(f, "Present"),
+            VoteCast::NotVoting => write!(f, "Not Voting"),
+            VoteCast::Guilty => write!(f, "Guilty"),
+            VoteCast::NotGuilty => write!(f, "Not Guilty"),
+            VoteCast::Candidate(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+/// Party affiliation as printed on ballots ("D") or in House totals ("Democratic").
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum Party {
+    Democrat,
+    Republican,
+    Independent,
+    Other(String),
+}
+
+impl Party {
+    pub fn code(&self) -> &str {
+        match self {
+            Party::Democrat => "D",
+            Party::Republican => "R",
+            Party::Independent => "I",
+            Party::Other(s) => s,
+        }
+    }
+}
+
+impl From<&str> for Party {
+    fn from(s: &str) -> Self {
+        match s.trim().to_lowercase().as_str() {
+            "d" | "democrat" | "democratic" => Party::Democrat,
+            "r" | "republican" => Party::Republican,
+            "i" | "id" | "independent" => Party::Independent,
+            _ => Party::Other(s.trim().to_string()),
+        }
+    }
+}
+
+impl From<String> for Party {
+    fn from(s: String) -> Self {
+        Party::from(s.as_str())
+    }
+}
+
+impl From<Party> for String {
+    fn from(p: Party) -> Self {
+        p.code().to_string()
+    }
+}
+
+impl std::fmt::Display for Party {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.code())
+    }
+}
+
+/// States, the District of Columbia and the territories that send delegates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum State {
+    Alabama,
+    Alaska,
+    Arizona,
+    Arkansas,
+    California,
+    Colorado,
+    Connecticut,
+    Delaware,
+    Florida,
+    Georgia,
+    Hawaii,
+    Idaho,
+    Illinois,
+    Indiana,
+    Iowa,
+    Kansas,
+    Kentucky,
+    Louisiana,
+    Maine,
+    Maryland,
+    Massachusetts,
+    Michigan,
+    Minnesota,
+    Mississippi,
+    Missouri,
+    Montana,
+    Nebraska,
+    Nevada,
+    NewHampshire,
+    NewJersey,
+    NewMexico,
+    NewYork,
+    NorthCarolina,
+    NorthDakota,
+    Ohio,
+    Oklahoma,
+    Oregon,
+    Pennsylvania,
+    RhodeIsland,
+    SouthCarolina,
+    SouthDakota,
+    Tennessee,
+    Texas,
+    Utah,
+    Vermont,
+    Virginia,
+    Washington,
+    WestVirginia,
+    Wisconsin,
+    Wyoming,
+    DistrictOfColumbia,
+    AmericanSamoa,
+    Guam,
+    NorthernMarianaIslands,
+    PuertoRico,
+    VirginIslands,
+}
+
+impl State {
+    /// USPS code, as used on ballots.
+    pub fn code(&self) -> &'static str {
+        match self {
+            State::Alabama => "AL",
+            State::Alaska => "AK",
+            State::Arizona => "AZ",
+            State::Arkansas => "AR",
+            State::California => "CA",
+            State::Colorado => "CO",
+            State::Connecticut => "CT",
+            State::Delaware => "DE",
+            State::Florida => "FL",
+            State::Georgia => "GA",
+            State::Hawaii => "HI",
+            State::Idaho => "ID",
+            State::Illinois => "IL",
+            State::Indiana => "IN",
+            State::Iowa => "IA",
+            State::Kansas => "KS",
+            State::Kentucky => "KY",
+            State::Louisiana => "LA",
+            State::Maine => "ME",
+            State::Maryland => "MD",
+            State::Massachusetts => "MA",
+            State::Michigan => "MI",
+            State::Minnesota => "MN",
+            State::Mississippi => "MS",
+            State::Missouri => "MO",
+            State::Montana => "MT",
+            State::Nebraska => "NE",
+            State::Nevada => "NV",
+            State::NewHampshire => "NH",
+            State::NewJersey => "NJ",
+            State::NewMexico => "NM",
+            State::NewYork => "NY",
+            State::NorthCarolina => "NC",
+            State::NorthDakota => "ND",
+            State::Ohio => "OH",
+            State::Oklahoma => "OK",
+            State::Oregon => "OR",
+            State::Pennsylvania => "PA",
+            State::RhodeIsland => "RI",
+            State::SouthCarolina => "SC",
+            State::SouthDakota => "SD",
+            State::Tennessee => "TN",
+            State::Texas => "TX",
+            State::Utah => "UT",
+            State::Vermont => "VT",
+            State::Virginia => "VA",
+            State::Washington => "WA",
+            State::WestVirginia => "WV",
+            State::Wisconsin => "WI",
+            State::Wyoming => "WY",
+            State::DistrictOfColumbia => "DC",
+            State::AmericanSamoa => "AS",
+            State::Guam => "GU",
+            State::NorthernMarianaIslands => "MP",
+            State::PuertoRico => "PR",
+            State::VirginIslands => "VI",
+        }
+    }
+}
+
+impl std::str::FromStr for State {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s.trim().to_uppercase().as_str() {
+            "AL" => State::Alabama,
+            "AK" => State::Alaska,
+            "AZ" => State::Arizona,
+            "AR" => State::Arkansas,
+            "CA" => State::California,
+            "CO" => State::Colorado,
+            "CT" => State::Connecticut,
+            "DE" => State::Delaware,
+            "FL" => State::Florida,
+            "GA" => State::Georgia,
+            "HI" => State::Hawaii,
+            "ID" => State::Idaho,
+            "IL" => State::Illinois,
+            "IN" => State::Indiana,
+            "IA" => State::Iowa,
+            "KS" => State::Kansas,
+            "KY" => State::Kentucky,
+            "LA" => State::Louisiana,
+            "ME" => State::Maine,
+            "MD" => State::Maryland,
+            "MA" => State::Massachusetts,
+            "MI" => State::Michigan,
+            "MN" => State::Minnesota,
+            "MS" => State::Mississippi,
+            "MO" => State::Missouri,
+            "MT" => State::Montana,
+            "NE" => State::Nebraska,
+            "NV" => State::Nevada,
+            "NH" => State::NewHampshire,
+            "NJ" => State::NewJersey,
+            "NM" => State::NewMexico,
+            "NY" => State::NewYork,
+            "NC" => State::NorthCarolina,
+            "ND" => State::NorthDakota,
+            "OH" => State::Ohio,
+            "OK" => State::Oklahoma,
+            "OR" => State::Oregon,
+            "PA" => State::Pennsylvania,
+            "RI" => State::RhodeIsland,
+            "SC" => State::SouthCarolina,
+            "SD" => State::SouthDakota,
+            "TN" => State::Tennessee,
+            "TX" => State::Texas,
+            "UT" => State::Utah,
+            "VT" => State::Vermont,
+            "VA" => State::Virginia,
+            "WA" => State::Washington,
+            "WV" => State::WestVirginia,
+            "WI" => State::Wisconsin,
+            "WY" => State::Wyoming,
+            "DC" => State::DistrictOfColumbia,
+            "AS" => State::AmericanSamoa,
+            "GU" => State::Guam,
+            "MP" => State::NorthernMarianaIslands,
+            "PR" => State::PuertoRico,
+            "VI" => State::VirginIslands,
+            _ => return Err(Error::parse("state", s)),
+        })
+    }
+}
+
+impl TryFrom<String> for State {
+    type Error = Error;
+
+    fn try_from(s: String) -> Result<Self> {
+        s.parse()
+    }
+}
+
+impl From<State> for String {
+    fn from(s: State) -> Self {
+        s.code().to_string()
+    }
+}
+
+impl std::fmt::Display for State {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.code())
+    }
+}
+
+/// Trims a value and drops it when empty.
+pub(crate) fn text(value: Option<&str>) -> Option<String> {
+    value.map(str::trim).filter(|s| !s.is_empty()).map(str::to_string)
+}
+
+/// Reads the leading digits of values such as "118", "2nd" or "00255".
+fn leading_number<T: std::str::FromStr>(value: &str) -> Option<T> {
+    let digits: String = value.trim().chars().take_while(|c| c.is_ascii_digit()).collect();
+    digits.parse().ok()
+}
+
+/// Parses a House `action-date` such as "24-Jul-2008".
+pub fn parse_house_date(value: &str) -> Option<NaiveDate> {
+    dates::parse_date(value, None)
+}
+
+/// Parses a House `time-etz` attribute such as "14:36".
+pub fn parse_house_time(value: &str) -> Option<NaiveTime> {
+    dates::parse_time(value)
+}
+
+/// Parses a Senate `vote_date`/`modify_date` such as "January 9, 2024, 05:31 PM".
+/// `None` when the value has no time of day.
+pub fn parse_senate_datetime(value: &str) -> Option<NaiveDateTime> {
+    let (date, time) = dates::parse_datetime(value, None)?;
+    Some(date.and_time(time?))
+}
+
+impl TryFrom<&HouseFile> for RollCallId {
+    type Error = Error;
+
+    fn try_from(file: &HouseFile) -> Result<Self> {
+        let metadata = &file.rollcall_vote.vote_metadata;
+
+        let congress = leading_number(&metadata.congress)
+            .ok_or_else(|| Error::parse("vote-metadata.congress", &metadata.congress))?;
+        let session = leading_number(&metadata.session)
+            .ok_or_else(|| Error::parse("vote-metadata.session", &metadata.session))?;
+        let number = metadata
+            .rollcall_num
+            .as_text()
+            .and_then(|n| leading_number(&n))
+            .ok_or_else(|| Error::parse("vote-metadata.rollcall-num", &metadata.rollcall_num.to_string()))?;
+
+        let date = metadata.action_date.as_text().as_deref().and_then(parse_house_date);
+        let year = session_year(congress, session, date)
+            .ok_or_else(|| Error::parse("vote-metadata.action-date", &metadata.action_date.to_string()))?;
+
+        Ok(RollCallId { chamber: CongressChamber::House, congress, session, number, year })
+    }
+}
+
+impl TryFrom<&SenateFile> for RollCallId {
+    type Error = Error;
+
+    fn try_from(file: &SenateFile) -> Result<Self> {
+        let vote = &file.roll_call_vote;
+
+        let congress = leading_number(&vote.congress)
+            .ok_or_else(|| Error::parse("roll_call_vote.congress", &vote.congress))?;
+        let session = leading_number(&vote.session)
+            .ok_or_else(|| Error::parse("roll_call_vote.session", &vote.session))?;
+        let number = vote
+            .vote_number
+            .as_deref()
+            .and_then(leading_number)
+            .ok_or_else(|| Error::parse("roll_call_vote.vote_number", vote.vote_number.as_deref().unwrap_or_default()))?;
+
+        // `congress_year` is the session's year; the calendar is only needed without it
+        let year = match vote.congress_year.as_deref().and_then(leading_number) {
+            Some(year) => year,
+            None => {
+                let date = vote.vote_date.as_text().and_then(|d| dates::parse_datetime(&d, None)).map(|(d, _)| d);
+                session_year(congress, session, date)
+                    .ok_or_else(|| Error::parse("roll_call_vote.congress_year", vote.congress_year.as_deref().unwrap_or_default()))?
+            }
+        };
+
+        Ok(RollCallId { chamber: CongressChamber::Senate, congress, session, number, year })
+    }
+}
+
+impl TryFrom<&VoteFile> for RollCallId {
+    type Error = Error;
+
+    fn try_from(file: &VoteFile) -> Result<Self> {
+        match file {
+            VoteFile::HouseFile(house) => RollCallId::try_from(house),
+            VoteFile::SenateFile(senate) => RollCallId::try_from(senate),
+        }
+    }
+}
+
+impl TryFrom<&HouseFile> for RollCall {
+    type Error = Error;
+
+    fn try_from(file: &HouseFile) -> Result<Self> {
+        let metadata = &file.rollcall_vote.vote_metadata;
+        let RollCallId { congress, session, number, .. } = RollCallId::try_from(file)?;
+
+        let time = metadata
+            .action_time
+            .as_ref()
+            .and_then(|t| t.time_etz.as_deref())
+            .and_then(parse_house_time);
+
+        let ballots = file
+            .rollcall_vote
+            .vote_data
+            .as_ref()
+            .and_then(|data| data.recorded_vote.as_ref())
+            .map(|votes| {
+                votes
+                    .iter()
+                    .map(|recorded| {
+                        let legislator = recorded.legislator.clone().unwrap_or_default();
+                        Ballot {
+                            member: Member {
+                                bioguide_id: text(legislator.name_id.as_deref()),
+                                lis_member_id: None,
+                                first_name: None,
+                                last_name: text(legislator.unaccented_name.as_deref())
+                                    .or_else(|| text(legislator.sort_field.as_deref())),
+                                full_name: text(legislator.content.as_deref()),
+                                party: text(legislator.party.as_deref()).map(Party::from),
+                                state: legislator.state.as_deref().and_then(|s| s.parse().ok()),
+                            },
+                            cast: recorded.vote.as_deref().and_then(|v| v.parse().ok()),
+                            paired_with: None,
+                        }
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(RollCall {
+            chamber: CongressChamber::House,
+            congress,
+            session,
+            number,
+            date: metadata.action_date.as_text().as_deref().and_then(parse_house_date),
+            time,
+            modified: None,
+            question: text(metadata.vote_question.as_text().as_deref()),
+            description: text(metadata.vote_desc.as_text().as_deref()),
+            result: text(metadata.vote_result.as_text().as_deref()),
+            vote_type: text(metadata.vote_type.as_text().as_deref()),
+            majority_requirement: None,
+            bill: text(metadata.legis_num.as_deref()),
+            amendment: None,
+            ballots,
+        })
+    }
+}
+
+impl TryFrom<&SenateFile> for RollCall {
+    type Error = Error;
+
+    fn try_from(file: &SenateFile) -> Result<Self> {
+        let vote = &file.roll_call_vote;
+        let RollCallId { congress, session, number, year, .. } = RollCallId::try_from(file)?;
+
+        let vote_date = vote.vote_date.as_text().and_then(|d| dates::parse_datetime(&d, Some(year as i32)));
+
+        let bill = vote.document.iter().find_map(|doc| doc.document_name.as_text());
+        let amendment = vote.amendment.iter().find_map(|amend| amend.amendment_number.clone());
+
+        let ballots = vote
+            .members
+            .as_ref()
+            .and_then(|members| members.member.as_ref())
+            .map(|members| {
+                members
+                    .iter()
+                    .map(|member| {
+                        let (cast, paired_with) = match &member.vote_cast {
+                            Some(ResponseSpecific::VoteCastMap(cast)) => (cast.name.as_deref(), text(cast.paired_with.as_deref())),
+                            Some(ResponseSpecific::String(cast)) => (Some(cast.as_str()), None),
+                            _ => (None, None),
+                        };
+
+                        Ballot {
+                            member: Member {
+                                bioguide_id: None,
+                                lis_member_id: text(member.lis_member_id.as_deref()),
+                                first_name: text(member.first_name.as_deref()),
+                                last_name: text(member.last_name.as_deref()),
+                                full_name: text(member.member_full.as_deref()),
+                                party: text(member.party.as_deref()).map(Party::from),
+                                state: member.state.as_deref().and_then(|s| s.parse().ok()),
+                            },
+                            cast: cast.and_then(|c| c.parse().ok()),
+                            paired_with,
+                        }
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(RollCall {
+            chamber: CongressChamber::Senate,
+            congress,
+            session,
+            number,
+            date: vote_date.map(|(d, _)| d),
+            time: vote_date.and_then(|(_, t)| t),
+            modified: vote.modify_date.as_deref().and_then(parse_senate_datetime),
+            question: text(vote.question.as_text().as_deref()),
+            description: text(vote.vote_title.as_deref()),
+            result: text(vote.vote_result.as_deref()),
+            vote_type: None,
+            majority_requirement: text(vote.majority_requirement.as_deref()),
+            bill: text(bill.as_deref()),
+            amendment: text(amendment.as_deref()),
+            ballots,
+        })
+    }
+}
+
+impl TryFrom<&VoteFile> for RollCall {
+    type Error = Error;
+
+    fn try_from(file: &VoteFile) -> Result<Self> {
+        match file {
+            VoteFile::HouseFile(house) => RollCall::try_from(house),
+            VoteFile::SenateFile(senate) => RollCall::try_from(senate),
+        }
+    }
+}
@@ -0,0 +1,262 @@
+// pipeline.rs
+//
+// Lists vote files in a fixed order, parses them on several threads and hands
+// the results, in input order, to a single consumer on the calling thread (the
+// SQLite writer, or the JSON builder). Output is the same as a serial run,
+// whatever the worker count.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Condvar, Mutex, PoisonError};
+
+use walkdir::WalkDir;
+
+use crate::model::{RollCall, RollCallId};
+use crate::responses::VoteFile;
+use crate::workspace::Workspace;
+use crate::xml_reader::{load_vote_file, VoteFormat};
+use crate::{Error, Result};
+
+/// Worker count used when none is given: one per available core.
+pub fn default_workers() -> usize {
+    std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+}
+
+/// Runs `parse` over `items` on `workers` threads (0 for `default_workers`) and
+/// calls `consume` for each item with its result, in the order of `items`.
+///
+/// Workers stay at most `workers * 4` items ahead of `consume`, so a slow file
+/// holds back at most that many parsed results rather than the rest of the run.
+///
+/// Stops at the first error returned by `consume`; errors from `parse` are
+/// values of `T` for `consume` to handle.
+pub fn parse_in_order<I, T, P, C>(items: &[I], workers: usize, parse: P, mut consume: C) -> Result<()>
+where
+    I: Sync,
+    T: Send,
+    P: Fn(&I) -> T + Sync,
+    C: FnMut(&I, T) -> Result<()>,
+{
+    let workers = match workers {
+        0 => default_workers(),
+        n => n,
+    }
+    .min(items.len().max(1));
+
+    let window = workers * 4;
+    let next = AtomicUsize::new(0);
+    let (tx, rx) = mpsc::sync_channel::<(usize, T)>(window);
+
+    // Items consumed so far, and whether the consumer has stopped
+    let progress = (Mutex::new((0usize, false)), Condvar::new());
+
+    std::thread::scope(|scope| {
+        // Owned here so it is dropped, unblocking the workers, if `consume` fails
+        let rx = rx;
+
+        for _ in 0..workers {
+            let tx = tx.clone();
+            let (next, parse, progress) = (&next, &parse, &progress);
+
+            scope.spawn(move || loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                if index >= items.len() {
+                    break;
+                }
+
+                // Wait for the consumer to come within `window` of this item
+                let (lock, ready) = progress;
+                let state = ready
+                    .wait_while(lock.lock().unwrap_or_else(PoisonError::into_inner), |(consumed, stopped)| {
+                        !*stopped && index >= *consumed + window
+                    })
+                    .unwrap_or_else(PoisonError::into_inner);
+                if state.1 {
+                    break;
+                }
+                drop(state);
+
+                // The consumer hung up after an error
+                if tx.send((index, parse(&items[index]))).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(tx);
+
+        // Results arrive in completion order; hold them until their turn
+        let mut pending = BTreeMap::new();
+        let mut expected = 0;
+
+        let result = rx.iter().try_for_each(|(index, result)| {
+            pending.insert(index, result);
+
+            while let Some(result) = pending.remove(&expected) {
+                consume(&items[expected], result)?;
+                expected += 1;
+
+                let (lock, ready) = &progress;
+                lock.lock().unwrap_or_else(PoisonError::into_inner).0 = expected;
+                ready.notify_all();
+            }
+            Ok(())
+        });
+
+        // Release workers still waiting for their turn
+        let (lock, ready) = &progress;
+        lock.lock().unwrap_or_else(PoisonError::into_inner).1 = true;
+        ready.notify_all();
+
+        result
+    })
+}
+
+/// A vote file and where its path places it.
+/// Ordered by chamber, congress, session and roll call, the order of the master JSON file.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct VoteFileEntry {
+    pub chamber: String,
+    pub congress_number: u16,
+    pub session_number: u8,
+    pub rollcall_number: u32,
+    pub year: u16,
+    pub path: PathBuf,
+}
+
+impl VoteFileEntry {
+    /// Reads the position from data/{json,xml}/{congress_number}/{chamber}/{session_number}/{year}_{roll}.{json,xml}
+    /// See `Workspace::parse_vote_path` for other layouts.
+    pub fn from_path(path: &Path) -> Result<Self> {
+        Workspace::default().parse_vote_path(path)
+    }
+
+    /// Reads the position from the document itself, for files stored outside the layout.
+    pub fn from_document(path: &Path) -> Result<Self> {
+        let id = RollCallId::try_from(&load_vote_file(path)?).map_err(|e| e.with_path(path))?;
+        Ok(VoteFileEntry::from_id(&id, path))
+    }
+
+    pub fn from_id(id: &RollCallId, path: &Path) -> Self {
+        VoteFileEntry {
+            chamber: id.chamber.to_string(),
+            congress_number: id.congress,
+            session_number: id.session,
+            rollcall_number: id.number,
+            year: id.year,
+            path: path.to_path_buf(),
+        }
+    }
+
+    /// Identifies the roll call in `file`, which was read from this entry's path.
+    /// The document is authoritative; a path that disagrees with it comes back as a
+    /// `PathMismatch` for the caller to report, count or turn into an error.
+    pub fn identify(&self, file: &VoteFile) -> Result<(RollCallId, Option<PathMismatch>)> {
+        let id = RollCallId::try_from(file).map_err(|e| e.with_path(&self.path))?;
+
+        let mismatch = (*self != VoteFileEntry::from_id(&id, &self.path)).then(|| PathMismatch { entry: self.clone(), document: id });
+        Ok((id, mismatch))
+    }
+}
+
+/// A vote file whose path places it at a different roll call than its document records.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathMismatch {
+    /// Where the path places the file.
+    pub entry: VoteFileEntry,
+    /// The roll call the document records, which the file is stored as.
+    pub document: RollCallId,
+}
+
+impl std::fmt::Display for PathMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let entry = &self.entry;
+        write!(
+            f,
+            "{}: path says {} {}-{} #{} ({}), document says {}",
+            entry.path.display(),
+            entry.chamber,
+            entry.congress_number,
+            entry.session_number,
+            entry.rollcall_number,
+            entry.year,
+            self.document
+        )
+    }
+}
+
+impl From<PathMismatch> for Error {
+    fn from(mismatch: PathMismatch) -> Self {
+        Error::schema(format!(
+            "Path places the file at {} {}-{} #{} ({}), but the document records {}",
+            mismatch.entry.chamber,
+            mismatch.entry.congress_number,
+            mismatch.entry.session_number,
+            mismatch.entry.rollcall_number,
+            mismatch.entry.year,
+            mismatch.document
+        ))
+        .with_path(&mismatch.entry.path)
+        .with_roll_call(mismatch.document.endpoint())
+    }
+}
+
+/// Lists all vote files of `format` in the workspace's data directory, in master JSON order.
+/// Files whose path doesn't follow the workspace layout are placed by their content,
+/// or reported and left out if they can't be read.
+pub fn collect_vote_files(workspace: &Workspace, format: VoteFormat) -> Vec<VoteFileEntry> {
+    let mut vote_files = Vec::new();
+
+    for entry in WalkDir::new(workspace.data_dir(format))
+        .into_iter()
+        .filter_map(|e| e.ok())
+    {
+        if !entry.file_type().is_file() || VoteFormat::from_path(entry.path()) != Some(format) {
+            continue;
+        }
+
+        // Files outside the layout are placed by what they contain
+        match workspace.parse_vote_path(entry.path()).or_else(|_| VoteFileEntry::from_document(entry.path())) {
+            Ok(vote_file) => vote_files.push(vote_file),
+            Err(e) => println!("⚠️ {}", e),
+        }
+    }
+
+    vote_files.sort();
+    vote_files
+}
+
+/// Parses every vote file of `format` in the workspace into a `RollCall` and hands
+/// them to `consume` in master JSON order. Files that fail to parse are reported and
+/// skipped; files filed under a different roll call than they record are reported
+/// and read as the roll call they record. Returns the number of roll calls consumed.
+pub fn read_rollcalls<C>(workspace: &Workspace, format: VoteFormat, workers: usize, mut consume: C) -> Result<usize>
+where
+    C: FnMut(RollCall) -> Result<()>,
+{
+    let vote_files = collect_vote_files(workspace, format);
+    let mut read = 0;
+
+    let parse = |entry: &VoteFileEntry| {
+        let file = load_vote_file(&entry.path)?;
+        let (_, mismatch) = entry.identify(&file)?;
+        let rollcall = RollCall::try_from(&file).map_err(|e| e.with_path(&entry.path))?;
+        Ok::<_, Error>((rollcall, mismatch))
+    };
+
+    parse_in_order(&vote_files, workers, parse, |_, parsed| {
+        match parsed {
+            Ok((rollcall, mismatch)) => {
+                if let Some(mismatch) = mismatch {
+                    println!("⚠️ {}", mismatch);
+                }
+                consume(rollcall)?;
+                read += 1;
+            }
+            Err(e) => println!("❌ {}", e),
+        }
+        Ok(())
+    })?;
+
+    Ok(read)
+}
@@ -0,0 +1,528 @@
+// queries.rs
+//
+// Typed reads from the SQLite store, so callers don't need to know its
+// schema. `VoteQuery` filters roll calls and ballots by chamber, congresses,
+// session, dates, member, party, state, vote cast, question, result and bill,
+// and reads them as typed rows: an iterator over a prepared statement for
+// whole congresses, or one page at a time with the total for APIs.
+//
+// Dates are the parsed `vote_day`, falling back to the date as published.
+// Vote casts and parties are compared the way `VoteCast` and `Party` read them,
+// so `VoteCast::Yea` matches "Yea", "Aye" and "Yes". Bills are matched on their
+// letters and digits, so "H.R. 2882", "H R 2882" and "hr2882" are the same bill,
+// whether it is the House `legis_num` or one of a Senate vote's documents.
+
+use std::ops::{Bound, RangeBounds};
+
+use chrono::NaiveDate;
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection, Row, Statement};
+use serde::Serialize;
+
+use crate::endpoints::CongressChamber;
+use crate::model::{Party, State, VoteCast};
+use crate::{Error, Result};
+
+/// Most rows one page can hold.
+pub const MAX_PAGE: usize = 1000;
+
+/// Furthest a page can start; larger offsets are read as this one.
+pub const MAX_OFFSET: usize = i32::MAX as usize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Page {
+    pub offset: usize,
+    pub limit: usize,
+}
+
+impl Default for Page {
+    fn default() -> Self {
+        Page { offset: 0, limit: 100 }
+    }
+}
+
+impl Page {
+    pub fn new(offset: usize, limit: usize) -> Self {
+        Page { offset: offset.min(MAX_OFFSET), limit: limit.clamp(1, MAX_PAGE) }
+    }
+}
+
+/// One page of rows and the number of rows on all pages.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Paged<T> {
+    pub total: usize,
+    pub offset: usize,
+    pub limit: usize,
+    pub items: Vec<T>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RollCallSummary {
+    pub chamber: CongressChamber,
+    pub congress: u16,
+    pub session: u8,
+    pub rollcall: u32,
+    pub vote_date: Option<String>,
+    /// Eastern time, when published.
+    pub vote_timestamp: Option<String>,
+    pub question: Option<String>,
+    /// House `vote-desc`, Senate vote title.
+    pub title: Option<String>,
+    pub result: Option<String>,
+    /// House `legis-num`, or the first Senate document name.
+    pub bill: Option<String>,
+    pub yea: u32,
+    pub nay: u32,
+    pub present: u32,
+    pub not_voting: u32,
+}
+
+/// A member's ballot on a roll call.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Ballot {
+    #[serde(flatten)]
+    pub rollcall: RollCallSummary,
+    pub member_id: String,
+    pub name: Option<String>,
+    /// Party and state on this ballot, which may differ from the member's current ones.
+    pub party: Option<Party>,
+    pub state: Option<State>,
+    /// `None` when no vote is recorded.
+    pub vote_cast: Option<VoteCast>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BallotRecord {
+    pub member_id: String,
+    pub name: Option<String>,
+    pub party: Option<String>,
+    pub state: Option<String>,
+    pub vote_cast: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RollCallDetail {
+    #[serde(flatten)]
+    pub summary: RollCallSummary,
+    pub ballots: Vec<BallotRecord>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MemberRecord {
+    pub member_id: String,
+    pub chamber: CongressChamber,
+    pub bioguide_id: Option<String>,
+    pub lis_member_id: Option<String>,
+    pub name: Option<String>,
+    pub party: Option<String>,
+    pub state: Option<String>,
+}
+
+/// A bill number without spaces, dots, dashes or underscores, upper case: "H.R. 2882" is "HR2882".
+pub fn normalize_bill(bill: &str) -> String {
+    bill.chars().filter(|c| !matches!(c, ' ' | '.' | '-' | '_')).collect::<String>().to_ascii_uppercase()
+}
+
+/// The same normalization in SQL, whose `upper` only folds ASCII letters.
+fn bill_sql(column: &str) -> String {
+    format!("upper(replace(replace(replace(replace({}, ' ', ''), '.', ''), '-', ''), '_', ''))", column)
+}
+
+/// `mv.vote_cast` spelled as `VoteCast` displays it, NULL when no vote is recorded.
+const CAST_SQL: &str = "CASE
+    WHEN mv.vote_cast IS NULL OR trim(mv.vote_cast) = '' THEN NULL
+    WHEN lower(trim(mv.vote_cast)) = 'not voting' THEN 'Not Voting'
+    WHEN lower(trim(mv.vote_cast)) IN ('yea', 'aye', 'yes') THEN 'Yea'
+    WHEN lower(trim(mv.vote_cast)) IN ('nay', 'no') THEN 'Nay'
+    WHEN lower(trim(mv.vote_cast)) = 'guilty' THEN 'Guilty'
+    WHEN lower(trim(mv.vote_cast)) = 'not guilty' THEN 'Not Guilty'
+    WHEN lower(trim(mv.vote_cast)) LIKE 'present%' THEN 'Present'
+    ELSE trim(mv.vote_cast) END";
+
+/// `mv.party` as `Party::code` gives it.
+const PARTY_SQL: &str = "CASE lower(trim(mv.party))
+    WHEN 'd' THEN 'D' WHEN 'democrat' THEN 'D' WHEN 'democratic' THEN 'D'
+    WHEN 'r' THEN 'R' WHEN 'republican' THEN 'R'
+    WHEN 'i' THEN 'I' WHEN 'id' THEN 'I' WHEN 'independent' THEN 'I'
+    ELSE trim(mv.party) END";
+
+const SUMMARY_COLUMNS: &str = "v.chamber, v.congress, v.session, v.rollcall, COALESCE(v.vote_day, v.vote_date), v.vote_timestamp,
+    COALESCE(v.question_text, v.question), COALESCE(v.vote_desc, v.title), v.result,
+    COALESCE(v.legis_num, (SELECT d.document_name FROM documents d WHERE d.vote_hash = v.vote_hash ORDER BY d.position LIMIT 1)),
+    COALESCE(c.yea, 0), COALESCE(c.nay, 0), COALESCE(c.present, 0), COALESCE(c.not_voting, 0)";
+
+fn summary(row: &Row) -> rusqlite::Result<RollCallSummary> {
+    Ok(RollCallSummary {
+        chamber: CongressChamber::from(row.get::<_, String>(0)?.as_str()),
+        congress: row.get(1)?,
+        session: row.get(2)?,
+        rollcall: row.get(3)?,
+        vote_date: row.get(4)?,
+        vote_timestamp: row.get(5)?,
+        question: row.get(6)?,
+        title: row.get(7)?,
+        result: row.get(8)?,
+        bill: row.get(9)?,
+        yea: row.get(10)?,
+        nay: row.get(11)?,
+        present: row.get(12)?,
+        not_voting: row.get(13)?,
+    })
+}
+
+fn ballot(row: &Row) -> rusqlite::Result<Ballot> {
+    Ok(Ballot {
+        rollcall: summary(row)?,
+        member_id: row.get(14)?,
+        name: row.get(15)?,
+        party: row.get::<_, Option<String>>(16)?.map(Party::from),
+        state: row.get::<_, Option<String>>(17)?.and_then(|s| s.parse().ok()),
+        vote_cast: row.get::<_, Option<String>>(18)?.and_then(|v| v.parse().ok()),
+    })
+}
+
+/// Rows of a prepared query, read as `T` one at a time.
+pub struct TypedRows<'c, T> {
+    stmt: Statement<'c>,
+    values: Vec<Value>,
+    read: fn(&Row) -> rusqlite::Result<T>,
+}
+
+impl<T> TypedRows<'_, T> {
+    /// Runs the query; each call starts from the first row again.
+    pub fn iter(&mut self) -> Result<impl Iterator<Item = Result<T>> + '_> {
+        let rows = self.stmt.query_map(params_from_iter(self.values.iter()), self.read)?;
+        Ok(rows.map(|row| row.map_err(Error::from)))
+    }
+
+    /// Every row, in order.
+    pub fn collect_all(&mut self) -> Result<Vec<T>> {
+        self.iter()?.collect()
+    }
+}
+
+/// Filters on roll calls and ballots, built up one at a time and all applied
+/// together. Reading roll calls with a ballot filter (member, party, state or
+/// vote cast) gives the roll calls where at least one ballot matches.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VoteQuery {
+    chamber: Option<CongressChamber>,
+    first_congress: Option<u16>,
+    last_congress: Option<u16>,
+    session: Option<u8>,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    member: Option<String>,
+    party: Option<Party>,
+    state: Option<State>,
+    vote_cast: Option<VoteCast>,
+    question: Option<String>,
+    result: Option<String>,
+    bill: Option<String>,
+    page: Option<Page>,
+}
+
+impl VoteQuery {
+    /// Every roll call and ballot.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn chamber(mut self, chamber: CongressChamber) -> Self {
+        self.chamber = Some(chamber);
+        self
+    }
+
+    pub fn congress(self, congress: u16) -> Self {
+        self.congresses(congress..=congress)
+    }
+
+    /// Congresses in a range, e.g. `115..=118` or `110..`.
+    pub fn congresses(mut self, range: impl RangeBounds<u16>) -> Self {
+        self.first_congress = match range.start_bound() {
+            Bound::Included(&n) => Some(n),
+            Bound::Excluded(&n) => Some(n.saturating_add(1)),
+            Bound::Unbounded => None,
+        };
+        self.last_congress = match range.end_bound() {
+            Bound::Included(&n) => Some(n),
+            Bound::Excluded(&n) => Some(n.saturating_sub(1)),
+            Bound::Unbounded => None,
+        };
+        self
+    }
+
+    pub fn session(mut self, session: u8) -> Self {
+        self.session = Some(session);
+        self
+    }
+
+    /// Roll calls on or after `day`.
+    pub fn from(mut self, day: NaiveDate) -> Self {
+        self.from = Some(day);
+        self
+    }
+
+    /// Roll calls on or before `day`.
+    pub fn to(mut self, day: NaiveDate) -> Self {
+        self.to = Some(day);
+        self
+    }
+
+    /// Ballots of one member, by the id on their ballots (bioguide id or LIS id).
+    pub fn member(mut self, member_id: impl Into<String>) -> Self {
+        self.member = Some(member_id.into());
+        self
+    }
+
+    /// Ballots cast under a party.
+    pub fn party(mut self, party: impl Into<Party>) -> Self {
+        self.party = Some(party.into());
+        self
+    }
+
+    /// Ballots cast for a state.
+    pub fn state(mut self, state: State) -> Self {
+        self.state = Some(state);
+        self
+    }
+
+    pub fn vote_cast(mut self, vote_cast: impl Into<VoteCast>) -> Self {
+        self.vote_cast = Some(vote_cast.into());
+        self
+    }
+
+    /// Roll calls whose question contains `text`, ignoring case ("passage", "cloture", "motion to table").
+    pub fn question(mut self, text: &str) -> Self {
+        self.question = Some(text.to_lowercase());
+        self
+    }
+
+    /// Roll calls whose result contains `text`, ignoring case ("passed", "agreed to", "rejected").
+    pub fn result(mut self, text: &str) -> Self {
+        self.result = Some(text.to_lowercase());
+        self
+    }
+
+    /// Roll calls on a bill or nomination, however its number is written.
+    pub fn bill(mut self, bill: &str) -> Self {
+        self.bill = Some(normalize_bill(bill));
+        self
+    }
+
+    /// Reads only one page of the rows.
+    pub fn page(mut self, page: Page) -> Self {
+        self.page = Some(page);
+        self
+    }
+
+    /// Conditions on `votes v`; their values are added to `values` in order.
+    fn rollcall_conditions(&self, values: &mut Vec<Value>) -> Vec<String> {
+        let mut conditions = vec![];
+        let mut add = |condition: String, value: Value| {
+            values.extend(std::iter::repeat_n(value, condition.matches('?').count()));
+            conditions.push(condition);
+        };
+
+        if let Some(chamber) = self.chamber {
+            add("v.chamber = ?".to_string(), Value::Text(chamber.to_string()));
+        }
+        if let Some(congress) = self.first_congress {
+            add("v.congress >= ?".to_string(), Value::Integer(congress.into()));
+        }
+        if let Some(congress) = self.last_congress {
+            add("v.congress <= ?".to_string(), Value::Integer(congress.into()));
+        }
+        if let Some(session) = self.session {
+            add("v.session = ?".to_string(), Value::Integer(session.into()));
+        }
+        if let Some(day) = self.from {
+            add("COALESCE(v.vote_day, v.vote_date) >= ?".to_string(), Value::Text(day.format("%Y-%m-%d").to_string()));
+        }
+        if let Some(day) = self.to {
+            add("COALESCE(v.vote_day, v.vote_date) <= ?".to_string(), Value::Text(day.format("%Y-%m-%d").to_string()));
+        }
+        if let Some(text) = &self.question {
+            add("instr(lower(concat_ws(' ', v.question, v.question_text)), ?) > 0".to_string(), Value::Text(text.clone()));
+        }
+        if let Some(text) = &self.result {
+            add("instr(lower(v.result), ?) > 0".to_string(), Value::Text(text.clone()));
+        }
+        if let Some(bill) = &self.bill {
+            add(
+                format!(
+                    "({} = ? OR EXISTS (SELECT 1 FROM documents d WHERE d.vote_hash = v.vote_hash AND {} = ?))",
+                    bill_sql("v.legis_num"),
+                    bill_sql("d.document_name")
+                ),
+                Value::Text(bill.clone()),
+            );
+        }
+
+        conditions
+    }
+
+    /// Conditions on `member_votes mv`; their values are added to `values` in order.
+    fn ballot_conditions(&self, values: &mut Vec<Value>) -> Vec<String> {
+        let mut conditions = vec![];
+        let mut add = |condition: String, value: Value| {
+            conditions.push(condition);
+            values.push(value);
+        };
+
+        if let Some(member) = &self.member {
+            add("mv.member_id = ?".to_string(), Value::Text(member.clone()));
+        }
+        if let Some(party) = &self.party {
+            add(format!("{} = ?", PARTY_SQL), Value::Text(party.code().to_string()));
+        }
+        if let Some(state) = self.state {
+            add("upper(trim(mv.state)) = ?".to_string(), Value::Text(state.code().to_string()));
+        }
+        if let Some(vote_cast) = &self.vote_cast {
+            add(format!("{} = ?", CAST_SQL), Value::Text(vote_cast.to_string()));
+        }
+
+        conditions
+    }
+
+    /// The `FROM ... WHERE ...` of the query and the values of its `?` placeholders.
+    fn body(&self, ballots: bool) -> (String, Vec<Value>) {
+        let mut values = vec![];
+        let mut conditions = self.rollcall_conditions(&mut values);
+        let ballot_conditions = self.ballot_conditions(&mut values);
+
+        let from = if ballots {
+            conditions.extend(ballot_conditions);
+            "FROM member_votes mv
+             JOIN votes v ON v.vote_hash = mv.vote_hash
+             LEFT JOIN members m ON m.member_id = mv.member_id
+             LEFT JOIN vote_counts c ON c.vote_hash = v.vote_hash"
+        } else {
+            if !ballot_conditions.is_empty() {
+                conditions.push(format!(
+                    "EXISTS (SELECT 1 FROM member_votes mv WHERE mv.vote_hash = v.vote_hash AND {})",
+                    ballot_conditions.join(" AND ")
+                ));
+            }
+            "FROM votes v LEFT JOIN vote_counts c ON c.vote_hash = v.vote_hash"
+        };
+
+        let clause = if conditions.is_empty() { String::new() } else { format!("WHERE {}", conditions.join(" AND ")) };
+        (format!("{} {}", from, clause), values)
+    }
+
+    fn prepare<'c, T>(&self, conn: &'c Connection, ballots: bool, read: fn(&Row) -> rusqlite::Result<T>) -> Result<TypedRows<'c, T>> {
+        let (body, mut values) = self.body(ballots);
+        let columns = if ballots {
+            format!("{}, mv.member_id, m.full_name, mv.party, mv.state, mv.vote_cast", SUMMARY_COLUMNS)
+        } else {
+            SUMMARY_COLUMNS.to_string()
+        };
+        let order = if ballots {
+            "ORDER BY v.chamber, v.congress, v.session, v.rollcall, mv.member_id"
+        } else {
+            "ORDER BY v.chamber, v.congress, v.session, v.rollcall"
+        };
+
+        let mut sql = format!("SELECT {} {} {}", columns, body, order);
+        if let Some(page) = self.page {
+            sql.push_str(" LIMIT ? OFFSET ?");
+            values.push(Value::Integer(i64::try_from(page.limit).unwrap_or(i64::MAX)));
+            values.push(Value::Integer(i64::try_from(page.offset).unwrap_or(i64::MAX)));
+        }
+
+        Ok(TypedRows { stmt: conn.prepare(&sql)?, values, read })
+    }
+
+    fn count(&self, conn: &Connection, ballots: bool) -> Result<usize> {
+        let (body, values) = self.body(ballots);
+        let count = conn.query_row(&format!("SELECT COUNT(*) {}", body), params_from_iter(values.iter()), |row| row.get(0))?;
+        Ok(count)
+    }
+
+    /// The matching roll calls, in chamber, congress, session and number order.
+    pub fn rollcalls<'c>(&self, conn: &'c Connection) -> Result<TypedRows<'c, RollCallSummary>> {
+        self.prepare(conn, false, summary)
+    }
+
+    /// The matching ballots, by roll call and then member id.
+    pub fn ballots<'c>(&self, conn: &'c Connection) -> Result<TypedRows<'c, Ballot>> {
+        self.prepare(conn, true, ballot)
+    }
+
+    /// Number of matching roll calls, ignoring the page.
+    pub fn count_rollcalls(&self, conn: &Connection) -> Result<usize> {
+        self.count(conn, false)
+    }
+
+    /// Number of matching ballots, ignoring the page.
+    pub fn count_ballots(&self, conn: &Connection) -> Result<usize> {
+        self.count(conn, true)
+    }
+
+    /// One page of roll calls (the first 100 unless `page` was given) and the total.
+    pub fn rollcall_page(&self, conn: &Connection) -> Result<Paged<RollCallSummary>> {
+        let page = self.page.unwrap_or_default();
+        let items = self.clone().page(page).rollcalls(conn)?.collect_all()?;
+        Ok(Paged { total: self.count_rollcalls(conn)?, offset: page.offset, limit: page.limit, items })
+    }
+
+    /// One page of ballots (the first 100 unless `page` was given) and the total.
+    pub fn ballot_page(&self, conn: &Connection) -> Result<Paged<Ballot>> {
+        let page = self.page.unwrap_or_default();
+        let items = self.clone().page(page).ballots(conn)?.collect_all()?;
+        Ok(Paged { total: self.count_ballots(conn)?, offset: page.offset, limit: page.limit, items })
+    }
+}
+
+/// One roll call with every ballot, ordered by member id.
+pub fn rollcall(conn: &Connection, chamber: CongressChamber, congress: u16, session: u8, number: u32) -> Result<Option<RollCallDetail>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {}, v.vote_hash FROM votes v LEFT JOIN vote_counts c ON c.vote_hash = v.vote_hash
+         WHERE v.chamber = ?1 AND v.congress = ?2 AND v.session = ?3 AND v.rollcall = ?4",
+        SUMMARY_COLUMNS
+    ))?;
+    let mut rows = stmt.query(params![chamber.to_string(), congress, session, number])?;
+    let Some(row) = rows.next()? else { return Ok(None) };
+    let summary = summary(row)?;
+    let vote_hash: String = row.get(14)?;
+
+    let mut stmt = conn.prepare(
+        "SELECT mv.member_id, m.full_name, mv.party, mv.state, mv.vote_cast
+         FROM member_votes mv LEFT JOIN members m ON m.member_id = mv.member_id
+         WHERE mv.vote_hash = ?1 ORDER BY mv.member_id",
+    )?;
+    let ballots = stmt
+        .query_map(params![vote_hash], |row| {
+            Ok(BallotRecord {
+                member_id: row.get(0)?,
+                name: row.get(1)?,
+                party: row.get(2)?,
+                state: row.get(3)?,
+                vote_cast: row.get(4)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    Ok(Some(RollCallDetail { summary, ballots }))
+}
+
+/// A member by the id on their ballots (bioguide id or LIS id).
+pub fn member(conn: &Connection, member_id: &str) -> Result<Option<MemberRecord>> {
+    let mut stmt = conn.prepare(
+        "SELECT member_id, chamber, bioguide_id, lis_member_id, full_name, party, state FROM members WHERE member_id = ?1",
+    )?;
+    let mut rows = stmt.query(params![member_id])?;
+    let Some(row) = rows.next()? else { return Ok(None) };
+
+    Ok(Some(MemberRecord {
+        member_id: row.get(0)?,
+        chamber: CongressChamber::from(row.get::<_, String>(1)?.as_str()),
+        bioguide_id: row.get(2)?,
+        lis_member_id: row.get(3)?,
+        name: row.get(4)?,
+        party: row.get(5)?,
+        state: row.get(6)?,
+    }))
+}
@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use crate::dates::VoteTimes;
+use crate::model::{RollCall, RollCallId};
 
 // Serde generic type
 #[derive(Debug, Clone, Deserialize, Serialize, Default)]
@@ -22,10 +24,39 @@ pub enum ResponseSpecific {
     None,
     String(String),
     VoteCastMap(VoteCastMap),
-    DocumentSenate(Box<DocumentSenate>),
-    AmendmentSenate(Box<AmendmentSenate>),
-    DSMap(Vec<DocumentSenate>),
-    ASMap(Vec<AmendmentSenate>),
+}
+
+/// Reads an element that holds one entry or a list of them as a list. The XML
+/// reader always produces lists; yq output has a bare object for a single entry.
+/// Empty elements are left out.
+fn one_or_many<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany<T> {
+        Many(Vec<Option<T>>),
+        One(T),
+    }
+
+    Ok(match Option::<OneOrMany<T>>::deserialize(deserializer)? {
+        Some(OneOrMany::Many(list)) => list.into_iter().flatten().collect(),
+        Some(OneOrMany::One(one)) => vec![one],
+        None => vec![],
+    })
+}
+
+impl Response {
+    /// Returns the scalar value as text, `None` for maps, lists and missing values.
+    pub fn as_text(&self) -> Option<String> {
+        match self {
+            Response::String(s) => Some(s.clone()),
+            Response::U32(u) => Some(u.to_string()),
+            _ => None,
+        }
+    }
 }
 
 impl std::fmt::Display for Response {
@@ -71,40 +102,6 @@ impl std::fmt::Display for ResponseSpecific {
                 }
                 write!(f, "}}")
             }
-            ResponseSpecific::DocumentSenate(d) => {
-                write!(f, "{{")?;
-                    write!(f, "document_congress: {}, ", d.document_congress)?;
-                    write!(f, "document_type: {}, ", d.document_type)?;
-                    write!(f, "document_number: {}, ", d.document_number)?;
-                    write!(f, "document_name: {}, ", d.document_name)?;
-                    write!(f, "document_title: {}, ", d.document_title)?;
-                    write!(f, "document_short_title: {}, ", d.document_short_title.clone().unwrap_or("null".to_string()))?;
-                write!(f, "}}")
-            }
-            ResponseSpecific::AmendmentSenate(a) => {
-                write!(f, "{{")?;
-                    write!(f, "amendment_number: {}, ", a.amendment_number.clone().unwrap_or("null".to_string()))?;
-                    write!(f, "amendment_to_amendment_number: {}, ", a.amendment_to_amendment_number.clone().unwrap_or(Response::Null))?;
-                    write!(f, "amendment_to_amendment_to_amendment_number: {}, ", a.amendment_to_amendment_to_amendment_number.clone().unwrap_or(Response::Null))?;
-                    write!(f, "amendment_to_document_number: {}, ", a.amendment_to_document_number.clone().unwrap_or(Response::Null))?;
-                    write!(f, "amendment_to_document_short_title: {}, ", a.amendment_to_document_short_title.clone().unwrap_or(Response::Null))?;
-                    write!(f, "amendment_purpose: {}, ", a.amendment_purpose.clone().unwrap_or("null".to_string()))?;
-                write!(f, "}}")
-            }
-            ResponseSpecific::DSMap(m) => {
-                write!(f, "[")?;
-                for v in m.iter() {
-                    write!(f, "{}, ", v)?;
-                }
-                write!(f, "]")
-            }
-            ResponseSpecific::ASMap(m) => {
-                write!(f, "{{")?;
-                for v in m.iter() {
-                    write!(f, "{}, ", v)?;
-                }
-                write!(f, "}}")
-            }
         }
     }
 }
@@ -120,49 +117,381 @@ pub enum VoteFile {
 }
 
 // Unified Struct for Database
+//
+// One ballot of the master JSON file. Values missing from the vote file are null.
 #[derive(Debug, Clone, Deserialize, Serialize, Default)]
 pub struct UnifiedVote {
     pub congress_number: u16,
     pub chamber: String,
     pub session_number: u8,
     pub rollcall_number: u32,
-    pub vote_date: String,
-    pub vote_question: String,
-    pub vote_result: String,
-    pub legislator_id: String,
-    pub legislator_name: String,
-    pub party: String,
-    pub state: String,
-    pub vote_cast: String,
+    /// As published, e.g. "17-Jan-2024" or "January 9, 2024, 05:31 PM".
+    pub vote_date: Option<String>,
+    /// `vote_date` parsed: the day ("2024-01-09") and the Eastern time, when published ("2024-01-09T17:31:00-05:00").
+    #[serde(default)]
+    pub vote_day: Option<String>,
+    #[serde(default)]
+    pub vote_timestamp: Option<String>,
+    pub vote_question: Option<String>,
+    pub vote_result: Option<String>,
+    pub legislator_id: Option<String>,
+    pub legislator_name: Option<String>,
+    pub party: Option<String>,
+    pub state: Option<String>,
+    pub vote_cast: Option<String>,
 }
 
 // Combined Data Struct
-#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+//
+// Everything repeated across ballots is stored once: strings are interned, each
+// legislator is a row in `members`, and a ballot is just two indices. Roll calls
+// are kept in ordered maps, so the data serializes in chamber, congress, session
+// and roll call order, in the same shape as the master JSON file.
+
+/// Index of a string in an `Interner`.
+pub type Sym = u32;
+
+/// Stores each distinct string once and hands out `Sym`s for it.
+#[derive(Debug, Clone, Default)]
+pub struct Interner {
+    strings: Vec<String>,
+    index: HashMap<String, Sym>,
+}
+
+impl Interner {
+    pub fn intern(&mut self, value: &str) -> Sym {
+        if let Some(&sym) = self.index.get(value) {
+            return sym;
+        }
+
+        let sym = self.strings.len() as Sym;
+        self.strings.push(value.to_string());
+        self.index.insert(value.to_string(), sym);
+        sym
+    }
+
+    /// Interns a value that may be missing; a missing value stays `None`.
+    pub fn intern_opt(&mut self, value: Option<&str>) -> Option<Sym> {
+        value.map(|v| self.intern(v))
+    }
+
+    pub fn resolve(&self, sym: Sym) -> &str {
+        &self.strings[sym as usize]
+    }
+
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+}
+
+/// A legislator as they appear on ballots: id, name, party and state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MemberRecord {
+    pub legislator_id: Option<Sym>,
+    pub legislator_name: Option<Sym>,
+    pub party: Option<Sym>,
+    pub state: Option<Sym>,
+}
+
+/// One ballot: an index into `CombinedData::members` and the vote cast.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompactBallot {
+    pub member: u32,
+    pub vote_cast: Option<Sym>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CompactRollCall {
+    pub vote_date: Option<Sym>,
+    pub vote_day: Option<Sym>,
+    pub vote_timestamp: Option<Sym>,
+    pub vote_question: Option<Sym>,
+    pub vote_result: Option<Sym>,
+    pub ballots: Vec<CompactBallot>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(from = "MasterJson")]
 pub struct CombinedData {
-    pub chambers: HashMap<String, ChamberData>, // "house" or "senate"
+    pub strings: Interner,
+    pub members: Vec<MemberRecord>,
+    member_index: HashMap<MemberRecord, u32>,
+    pub chambers: BTreeMap<String, ChamberData>, // "house" or "senate"
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+/// One chamber's roll calls. Its symbols index the `CombinedData` it belongs to,
+/// so it is read as part of `CombinedData` or on its own as a `ChamberJson`.
+#[derive(Debug, Clone, Default)]
 pub struct ChamberData {
-    pub congresses: HashMap<u16, CongressData>, // e.g., 116, 117
+    pub congresses: BTreeMap<u16, CongressData>, // e.g., 116, 117
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+#[derive(Debug, Clone, Default)]
 pub struct CongressData {
-    pub sessions: HashMap<u8, SessionData>, // e.g., 1, 2
+    pub sessions: BTreeMap<u8, SessionData>, // e.g., 1, 2
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+#[derive(Debug, Clone, Default)]
 pub struct SessionData {
+    pub rollcalls: BTreeMap<u32, CompactRollCall>, // keyed by roll call number
+}
+
+impl CombinedData {
+    /// Adds a ballot, creating its chamber, congress, session and roll call as needed.
+    /// The roll call's date, question and result come from its first ballot.
+    pub fn insert(&mut self, vote: &UnifiedVote) {
+        let ballot = self.ballot(vote);
+        let strings = &mut self.strings;
+
+        self.chambers
+            .entry(vote.chamber.to_lowercase())
+            .or_default()
+            .congresses
+            .entry(vote.congress_number)
+            .or_default()
+            .sessions
+            .entry(vote.session_number)
+            .or_default()
+            .rollcalls
+            .entry(vote.rollcall_number)
+            .or_insert_with(|| CompactRollCall {
+                vote_date: strings.intern_opt(vote.vote_date.as_deref()),
+                vote_day: strings.intern_opt(vote.vote_day.as_deref()),
+                vote_timestamp: strings.intern_opt(vote.vote_timestamp.as_deref()),
+                vote_question: strings.intern_opt(vote.vote_question.as_deref()),
+                vote_result: strings.intern_opt(vote.vote_result.as_deref()),
+                ballots: Vec::new(),
+            })
+            .ballots
+            .push(ballot);
+    }
+
+    /// Adds a roll call as read from the master JSON file, placed by `chamber`,
+    /// `congress_number` and `session_number` rather than its ballots' copies of them.
+    pub fn insert_rollcall(&mut self, chamber: &str, congress_number: u16, session_number: u8, rollcall: &RollCallData) {
+        let ballots: Vec<CompactBallot> = rollcall.vote_casts.iter().map(|vote| self.ballot(vote)).collect();
+        let strings = &mut self.strings;
+
+        self.chambers
+            .entry(chamber.to_lowercase())
+            .or_default()
+            .congresses
+            .entry(congress_number)
+            .or_default()
+            .sessions
+            .entry(session_number)
+            .or_default()
+            .rollcalls
+            .entry(rollcall.rollcall_number)
+            .or_insert_with(|| CompactRollCall {
+                vote_date: strings.intern_opt(rollcall.vote_date.as_deref()),
+                vote_day: strings.intern_opt(rollcall.vote_day.as_deref()),
+                vote_timestamp: strings.intern_opt(rollcall.vote_timestamp.as_deref()),
+                vote_question: strings.intern_opt(rollcall.vote_question.as_deref()),
+                vote_result: strings.intern_opt(rollcall.vote_result.as_deref()),
+                ballots: Vec::new(),
+            })
+            .ballots
+            .extend(ballots);
+    }
+
+    /// Interns a ballot's member and vote cast, adding the member to `members` if new.
+    fn ballot(&mut self, vote: &UnifiedVote) -> CompactBallot {
+        let member = MemberRecord {
+            legislator_id: self.strings.intern_opt(vote.legislator_id.as_deref()),
+            legislator_name: self.strings.intern_opt(vote.legislator_name.as_deref()),
+            party: self.strings.intern_opt(vote.party.as_deref()),
+            state: self.strings.intern_opt(vote.state.as_deref()),
+        };
+
+        let member = match self.member_index.get(&member) {
+            Some(&index) => index,
+            None => {
+                let index = self.members.len() as u32;
+                self.members.push(member);
+                self.member_index.insert(member, index);
+                index
+            }
+        };
+
+        CompactBallot { member, vote_cast: self.strings.intern_opt(vote.vote_cast.as_deref()) }
+    }
+
+    /// Number of ballots across all roll calls.
+    pub fn ballot_count(&self) -> usize {
+        self.rollcalls().map(|(.., rc)| rc.ballots.len()).sum()
+    }
+
+    /// Every roll call, in chamber, congress, session and roll call order.
+    pub fn rollcalls(&self) -> impl Iterator<Item = (&str, u16, u8, u32, &CompactRollCall)> + '_ {
+        self.chambers.iter().flat_map(|(chamber, chamber_data)| {
+            chamber_data.congresses.iter().flat_map(move |(&congress, congress_data)| {
+                congress_data.sessions.iter().flat_map(move |(&session, session_data)| {
+                    session_data
+                        .rollcalls
+                        .iter()
+                        .map(move |(&roll, rc)| (chamber.as_str(), congress, session, roll, rc))
+                })
+            })
+        })
+    }
+
+    /// Expands a roll call back into the `RollCallData` written to the master JSON file.
+    pub fn expand(&self, chamber: &str, congress_number: u16, session_number: u8, rollcall_number: u32, rollcall: &CompactRollCall) -> RollCallData {
+        let s = |sym| self.strings.resolve(sym).to_string();
+
+        RollCallData {
+            rollcall_number,
+            vote_date: rollcall.vote_date.map(s),
+            vote_day: rollcall.vote_day.map(s),
+            vote_timestamp: rollcall.vote_timestamp.map(s),
+            vote_question: rollcall.vote_question.map(s),
+            vote_result: rollcall.vote_result.map(s),
+            vote_casts: rollcall
+                .ballots
+                .iter()
+                .map(|ballot| {
+                    let member = &self.members[ballot.member as usize];
+                    UnifiedVote {
+                        congress_number,
+                        chamber: chamber.to_string(),
+                        session_number,
+                        rollcall_number,
+                        vote_date: rollcall.vote_date.map(s),
+                        vote_day: rollcall.vote_day.map(s),
+                        vote_timestamp: rollcall.vote_timestamp.map(s),
+                        vote_question: rollcall.vote_question.map(s),
+                        vote_result: rollcall.vote_result.map(s),
+                        legislator_id: member.legislator_id.map(s),
+                        legislator_name: member.legislator_name.map(s),
+                        party: member.party.map(s),
+                        state: member.state.map(s),
+                        vote_cast: ballot.vote_cast.map(s),
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+// Serializes in the master JSON shape, expanding one roll call at a time:
+// {"chambers": {"house": {"congresses": {"118": {"sessions": {"2": {"rollcalls": [...]}}}}}}}
+impl Serialize for CombinedData {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeMap;
+
+        struct Nested<T: Serialize>(&'static str, T);
+        impl<T: Serialize> Serialize for Nested<T> {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry(self.0, &self.1)?;
+                map.end()
+            }
+        }
+
+        struct Chambers<'a>(&'a CombinedData);
+        impl Serialize for Chambers<'_> {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                let mut map = serializer.serialize_map(Some(self.0.chambers.len()))?;
+                for (chamber, data) in &self.0.chambers {
+                    map.serialize_entry(chamber, &Nested("congresses", Congresses(self.0, chamber, data)))?;
+                }
+                map.end()
+            }
+        }
+
+        struct Congresses<'a>(&'a CombinedData, &'a str, &'a ChamberData);
+        impl Serialize for Congresses<'_> {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                let mut map = serializer.serialize_map(Some(self.2.congresses.len()))?;
+                for (congress, data) in &self.2.congresses {
+                    map.serialize_entry(congress, &Nested("sessions", Sessions(self.0, self.1, *congress, data)))?;
+                }
+                map.end()
+            }
+        }
+
+        struct Sessions<'a>(&'a CombinedData, &'a str, u16, &'a CongressData);
+        impl Serialize for Sessions<'_> {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                let mut map = serializer.serialize_map(Some(self.3.sessions.len()))?;
+                for (session, data) in &self.3.sessions {
+                    map.serialize_entry(session, &Nested("rollcalls", RollCalls(self.0, self.1, self.2, *session, data)))?;
+                }
+                map.end()
+            }
+        }
+
+        struct RollCalls<'a>(&'a CombinedData, &'a str, u16, u8, &'a SessionData);
+        impl Serialize for RollCalls<'_> {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                use serde::ser::SerializeSeq;
+
+                let mut seq = serializer.serialize_seq(Some(self.4.rollcalls.len()))?;
+                for (roll, rollcall) in &self.4.rollcalls {
+                    seq.serialize_element(&self.0.expand(self.1, self.2, self.3, *roll, rollcall))?;
+                }
+                seq.end()
+            }
+        }
+
+        Nested("chambers", Chambers(self)).serialize(serializer)
+    }
+}
+
+// Reads the master JSON shape back, interning each roll call as it goes.
+#[derive(Deserialize)]
+struct MasterJson {
+    chambers: BTreeMap<String, ChamberJson>,
+}
+
+/// One chamber of the master JSON file, with its roll calls expanded.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ChamberJson {
+    pub congresses: BTreeMap<u16, CongressJson>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct CongressJson {
+    pub sessions: BTreeMap<u8, SessionJson>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct SessionJson {
     pub rollcalls: Vec<RollCallData>,
 }
 
+impl From<MasterJson> for CombinedData {
+    fn from(master: MasterJson) -> Self {
+        let mut data = CombinedData::default();
+        for (chamber, chamber_data) in &master.chambers {
+            for (&congress, congress_data) in &chamber_data.congresses {
+                for (&session, session_data) in &congress_data.sessions {
+                    for rollcall in &session_data.rollcalls {
+                        data.insert_rollcall(chamber, congress, session, rollcall);
+                    }
+                }
+            }
+        }
+        data
+    }
+}
+
+/// One roll call as written to the master JSON file.
 #[derive(Debug, Clone, Deserialize, Serialize, Default)]
 pub struct RollCallData {
     pub rollcall_number: u32,
-    pub vote_date: String,
-    pub vote_question: String,
-    pub vote_result: String,
+    pub vote_date: Option<String>,
+    #[serde(default)]
+    pub vote_day: Option<String>,
+    #[serde(default)]
+    pub vote_timestamp: Option<String>,
+    pub vote_question: Option<String>,
+    pub vote_result: Option<String>,
     pub vote_casts: Vec<UnifiedVote>,
 }
 
@@ -353,8 +682,10 @@ pub struct RollCallVoteSenate {
     pub majority_requirement: Option<String>,
     #[serde(rename = "vote_result")]
     pub vote_result: Option<String>,
-    pub document: Option<ResponseSpecific>, // DocumentSenate expected
-    pub amendment: Option<ResponseSpecific>, // AmendmentSenate expected
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub document: Vec<DocumentSenate>,
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub amendment: Vec<AmendmentSenate>,
     pub count: Option<CountSenate>,
     #[serde(rename = "tie_breaker")]
     pub tie_breaker: Option<TieBreakerSenate>,
@@ -480,41 +811,51 @@ pub struct VoteCastMap {
     pub paired_with: Option<String>,
 }
 
-// Struct for General Congressional Member
-#[derive(Debug, Clone, Deserialize, Serialize, Default)]
-pub struct CongressionalMember {
-    pub last_name: String,
-    pub party: String,
-    pub state: String,
-    // this should be seperate to the unique id applied by a database
-    pub generated_id: String,
-}
-
-
-#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+/// A Senate roll call as written to the database: the typed roll call and the
+/// parts of the file the model leaves out, as published.
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct SenateInfo {
-    pub congress_number: u16,
-    pub session_number: u8,
-    pub rollcall_number: u32,
-    pub chamber: String,
-    pub year: u16,
+    pub id: RollCallId,
+    pub rollcall: RollCall,
     pub vote_hash: String,
-    pub vote_date: String,
-    pub vote_modify_date: String,
-    pub vote_question: String,
-    pub vote_question_text: String,
-    pub vote_document_text: String,
-    pub vote_result: String,
-    pub vote_title: String,
-    pub majority_requirement: String,
+    pub vote_date: Option<String>,
+    pub vote_modify_date: Option<String>,
+    /// `vote_date` and `vote_modify_date`, parsed.
+    pub times: VoteTimes,
+    pub vote_question_text: Option<String>,
+    pub vote_document_text: Option<String>,
     pub documents: Vec<DocumentSenate>,
     pub amendments: Vec<AmendmentSenate>,
     pub yay: u32,
     pub nay: u32,
     pub present: u32,
     pub absent: u32,
-    pub tie_breaker_name: String,
-    pub tie_breaker_paired: String,
-    pub members_as_is: Vec<MemberSenate>,
-    pub members_to_gen: Vec<CongressionalMember>
-}
\ No newline at end of file
+    pub tie_breaker_name: Option<String>,
+    pub tie_breaker_paired: Option<String>,
+    /// `members.member_id` of each ballot, in ballot order.
+    pub member_ids: Vec<String>,
+}
+
+/// A House roll call as written to the database: the typed roll call and the
+/// parts of the file the model leaves out, as published.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct HouseInfo {
+    pub id: RollCallId,
+    pub rollcall: RollCall,
+    pub vote_hash: String,
+    pub majority: Option<String>,
+    pub committee: Option<String>,
+    pub action_date: Option<String>,
+    pub action_time: Option<String>,
+    pub action_time_etz: Option<String>,
+    /// `action_date` and `action_time_etz`, parsed.
+    pub times: VoteTimes,
+    pub yea: u32,
+    pub nay: u32,
+    pub present: u32,
+    pub not_voting: u32,
+    pub totals_by_party: Vec<TotalsByPartyHouse>,
+    pub totals_by_candidate: Vec<TotalsByCandidateHouse>,
+    /// `members.member_id` of each ballot, in ballot order.
+    pub member_ids: Vec<String>,
+}
@@ -0,0 +1,139 @@
+// search.rs
+//
+// Full-text search over the text of each roll call: House `vote_desc` and
+// `legis_num`; Senate vote titles, question and document text, document titles
+// and amendment purposes. The FTS5 table `vote_search` has one row per vote,
+// written in the same transaction as the vote (`index_vote`) and dropped by a
+// trigger when the vote is replaced or deleted. Queries use FTS5 syntax
+// ("cloture AND nomination", "appropriat*", "\"border security\""); text that
+// isn't valid FTS5, such as "H.R. 2882", is searched for word by word.
+
+use rusqlite::{params, Connection};
+use serde::Serialize;
+
+use crate::endpoints::CongressChamber;
+use crate::Result;
+
+/// Weights of `vote_search`'s columns in the ranking: vote_hash, title, question, bill, documents, amendments.
+const COLUMN_WEIGHTS: &str = "0.0, 10.0, 4.0, 8.0, 3.0, 3.0";
+
+/// Rewrites `vote_search`'s row for a vote from what is stored for it.
+pub fn index_vote(conn: &Connection, vote_hash: &str) -> Result<()> {
+    conn.execute("DELETE FROM vote_search WHERE vote_hash = ?1", params![vote_hash])?;
+    conn.execute(
+        "INSERT INTO vote_search (vote_hash, title, question, bill, documents, amendments)
+         SELECT v.vote_hash,
+                COALESCE(v.vote_desc, v.title),
+                concat_ws(' ', v.question, v.question_text),
+                concat_ws(' ', v.legis_num, (SELECT group_concat(document_name, ' ') FROM documents d WHERE d.vote_hash = v.vote_hash)),
+                concat_ws(' ', v.document_text, (SELECT group_concat(document_title, ' ') FROM documents d WHERE d.vote_hash = v.vote_hash)),
+                (SELECT group_concat(amendment_purpose, ' ') FROM amendments a WHERE a.vote_hash = v.vote_hash)
+         FROM votes v WHERE v.vote_hash = ?1",
+        params![vote_hash],
+    )?;
+    Ok(())
+}
+
+/// Rebuilds the whole index, e.g. for a database written before it existed. Returns the number of votes indexed.
+pub fn rebuild_index(conn: &Connection) -> Result<usize> {
+    conn.execute("DELETE FROM vote_search", [])?;
+
+    let mut stmt = conn.prepare("SELECT vote_hash FROM votes")?;
+    let hashes = stmt.query_map([], |row| row.get::<_, String>(0))?.collect::<rusqlite::Result<Vec<_>>>()?;
+    for vote_hash in &hashes {
+        index_vote(conn, vote_hash)?;
+    }
+
+    Ok(hashes.len())
+}
+
+/// Which roll calls to search. `None` means all of them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SearchFilter {
+    pub chamber: Option<CongressChamber>,
+    pub congress: Option<u16>,
+}
+
+/// A roll call matching a search, best match first.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SearchHit {
+    pub chamber: CongressChamber,
+    pub congress: u16,
+    pub session: u8,
+    pub rollcall: u32,
+    /// "2024-01-09", or the date as published if it could not be parsed.
+    pub vote_date: Option<String>,
+    pub question: Option<String>,
+    pub result: Option<String>,
+    /// The best matching passage, matches in [brackets].
+    pub snippet: String,
+    /// BM25 score; lower is a better match.
+    pub rank: f64,
+}
+
+/// Turns free text into an FTS5 query that matches every word, e.g. `"H" "R" "2882"`.
+pub fn quote_terms(text: &str) -> String {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| format!("\"{}\"", w))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Searches the roll calls for `query`, returning at most `limit` of them, best match first.
+pub fn search(conn: &Connection, query: &str, filter: &SearchFilter, limit: usize) -> Result<Vec<SearchHit>> {
+    if is_valid_query(conn, query)? {
+        run_search(conn, query, filter, limit)
+    } else {
+        run_search(conn, &quote_terms(query), filter, limit)
+    }
+}
+
+/// Whether FTS5 accepts `query`, tried against an empty scratch table with
+/// `vote_search`'s columns so that no other failure can be mistaken for bad syntax.
+fn is_valid_query(conn: &Connection, query: &str) -> Result<bool> {
+    conn.execute_batch(
+        "CREATE VIRTUAL TABLE IF NOT EXISTS temp.search_syntax USING fts5(
+            vote_hash UNINDEXED, title, question, bill, documents, amendments, tokenize = 'porter unicode61'
+        );",
+    )?;
+    Ok(conn.query_row("SELECT COUNT(*) FROM temp.search_syntax WHERE search_syntax MATCH ?1", params![query], |_| Ok(())).is_ok())
+}
+
+fn run_search(conn: &Connection, query: &str, filter: &SearchFilter, limit: usize) -> Result<Vec<SearchHit>> {
+    if query.trim().is_empty() {
+        return Ok(vec![]);
+    }
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT v.chamber, v.congress, v.session, v.rollcall, COALESCE(v.vote_day, v.vote_date),
+                COALESCE(v.question_text, v.question), v.result,
+                snippet(vote_search, -1, '[', ']', '…', 16), bm25(vote_search, {}) AS score
+         FROM vote_search
+         JOIN votes v ON v.vote_hash = vote_search.vote_hash
+         WHERE vote_search MATCH ?1
+           AND (?2 IS NULL OR v.chamber = ?2) AND (?3 IS NULL OR v.congress = ?3)
+         ORDER BY score, v.vote_epoch DESC, v.congress DESC, v.rollcall DESC
+         LIMIT ?4",
+        COLUMN_WEIGHTS
+    ))?;
+
+    let chamber = filter.chamber.map(|c| c.to_string());
+    let hits = stmt
+        .query_map(params![query, chamber, filter.congress, i64::try_from(limit).unwrap_or(i64::MAX)], |row| {
+            Ok(SearchHit {
+                chamber: CongressChamber::from(row.get::<_, String>(0)?.as_str()),
+                congress: row.get(1)?,
+                session: row.get(2)?,
+                rollcall: row.get(3)?,
+                vote_date: row.get(4)?,
+                question: row.get(5)?,
+                result: row.get(6)?,
+                snippet: row.get(7)?,
+                rank: row.get(8)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    Ok(hits)
+}
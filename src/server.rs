@@ -0,0 +1,268 @@
+// server.rs
+//
+// A local, read-only HTTP API over the SQLite store, answering in JSON from
+// the typed reads in `queries` and `search`:
+//
+//   GET /rollcalls
+//   GET /rollcalls/{chamber}/{congress}/{session}/{number}
+//   GET /members/{member_id}
+//   GET /members/{member_id}/votes
+//   GET /bills/{bill}/votes
+//   GET /search?q=&chamber=&congress=&offset=&limit=
+//
+// Lists take the `VoteQuery` filters chamber, congress, session, from, to
+// (YYYY-MM-DD), party, state, cast, question and result, and offset/limit
+// paging. Roll call lists filtered by party, state or cast give the roll calls
+// with at least one such ballot. Lists come back as `{"total", "offset",
+// "limit", "items"}`; errors as `{"error": "..."}` with a 4xx or 500 status.
+// The database is migrated once at startup and then opened read-only, so
+// serving never writes to it.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use chrono::NaiveDate;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{header, Body, Method, Request, Response, Server, StatusCode};
+use rusqlite::{Connection, OpenFlags};
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::endpoints::CongressChamber;
+use crate::model::{Party, VoteCast};
+use crate::queries::{self, Page, VoteQuery};
+use crate::search::{self, SearchFilter};
+use crate::workspace::Workspace;
+use crate::{sqllite, Error, Result};
+
+/// Address `serve` listens on unless told otherwise.
+pub const DEFAULT_ADDR: &str = "127.0.0.1:8080";
+
+/// An error answered to the client.
+#[derive(Debug)]
+struct ApiError {
+    status: StatusCode,
+    message: String,
+}
+
+impl ApiError {
+    fn bad_request(message: impl Into<String>) -> Self {
+        ApiError { status: StatusCode::BAD_REQUEST, message: message.into() }
+    }
+
+    fn not_found(message: impl Into<String>) -> Self {
+        ApiError { status: StatusCode::NOT_FOUND, message: message.into() }
+    }
+}
+
+impl From<Error> for ApiError {
+    fn from(e: Error) -> Self {
+        ApiError { status: StatusCode::INTERNAL_SERVER_ERROR, message: e.to_string() }
+    }
+}
+
+type Answer = std::result::Result<Value, ApiError>;
+
+/// Query string parameters, last value wins.
+struct Params(HashMap<String, String>);
+
+impl Params {
+    fn parse(query: Option<&str>) -> Self {
+        Params(form_urlencoded::parse(query.unwrap_or_default().as_bytes()).into_owned().collect())
+    }
+
+    fn get(&self, name: &str) -> Option<&str> {
+        self.0.get(name).map(|s| s.as_str()).filter(|s| !s.is_empty())
+    }
+
+    fn number<T: std::str::FromStr>(&self, name: &str) -> std::result::Result<Option<T>, ApiError> {
+        self.get(name)
+            .map(|v| v.parse().map_err(|_| ApiError::bad_request(format!("'{}' is not a valid {}", v, name))))
+            .transpose()
+    }
+
+    fn date(&self, name: &str) -> std::result::Result<Option<NaiveDate>, ApiError> {
+        self.get(name)
+            .map(|v| {
+                NaiveDate::parse_from_str(v, "%Y-%m-%d")
+                    .map_err(|_| ApiError::bad_request(format!("'{}' is not a YYYY-MM-DD date for {}", v, name)))
+            })
+            .transpose()
+    }
+
+    fn page(&self) -> std::result::Result<Page, ApiError> {
+        let default = Page::default();
+        Ok(Page::new(self.number("offset")?.unwrap_or(default.offset), self.number("limit")?.unwrap_or(default.limit)))
+    }
+
+    fn chamber(&self) -> std::result::Result<Option<CongressChamber>, ApiError> {
+        self.get("chamber").map(parse_chamber).transpose()
+    }
+
+    /// The list filters: chamber, congress, session, from, to, party, state, cast, question, result, and the page.
+    fn query(&self) -> std::result::Result<VoteQuery, ApiError> {
+        let mut query = VoteQuery::new().page(self.page()?);
+        if let Some(chamber) = self.chamber()? {
+            query = query.chamber(chamber);
+        }
+        if let Some(congress) = self.number("congress")? {
+            query = query.congress(congress);
+        }
+        if let Some(session) = self.number("session")? {
+            query = query.session(session);
+        }
+        if let Some(day) = self.date("from")? {
+            query = query.from(day);
+        }
+        if let Some(day) = self.date("to")? {
+            query = query.to(day);
+        }
+        if let Some(party) = self.get("party") {
+            query = query.party(Party::from(party));
+        }
+        if let Some(state) = self.get("state") {
+            query = query.state(state.parse().map_err(|_| ApiError::bad_request(format!("'{}' is not a state", state)))?);
+        }
+        if let Some(cast) = self.get("cast") {
+            query = query.vote_cast(cast.parse::<VoteCast>().map_err(|_| ApiError::bad_request(format!("'{}' is not a vote cast", cast)))?);
+        }
+        if let Some(text) = self.get("question") {
+            query = query.question(text);
+        }
+        if let Some(text) = self.get("result") {
+            query = query.result(text);
+        }
+        Ok(query)
+    }
+}
+
+fn parse_chamber(value: &str) -> std::result::Result<CongressChamber, ApiError> {
+    value.parse().map_err(|_| ApiError::bad_request(format!("'{}' is not a chamber", value)))
+}
+
+fn segment<T: std::str::FromStr>(value: &str, what: &str) -> std::result::Result<T, ApiError> {
+    value.parse().map_err(|_| ApiError::bad_request(format!("'{}' is not a valid {}", value, what)))
+}
+
+fn to_json(value: impl Serialize) -> Answer {
+    serde_json::to_value(value).map_err(|e| ApiError { status: StatusCode::INTERNAL_SERVER_ERROR, message: e.to_string() })
+}
+
+/// Answers a GET of `path` with its `query` string: the status and the JSON body.
+pub fn handle(conn: &Connection, path: &str, query: Option<&str>) -> (StatusCode, Value) {
+    match route(conn, path, &Params::parse(query)) {
+        Ok(body) => (StatusCode::OK, body),
+        Err(e) => (e.status, json!({ "error": e.message })),
+    }
+}
+
+fn route(conn: &Connection, path: &str, params: &Params) -> Answer {
+    let segments: Vec<String> = path
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .map(|s| percent_encoding::percent_decode_str(s).decode_utf8_lossy().into_owned())
+        .collect();
+    let segments: Vec<&str> = segments.iter().map(|s| s.as_str()).collect();
+
+    match segments.as_slice() {
+        [] => Ok(json!({
+            "endpoints": [
+                "/rollcalls",
+                "/rollcalls/{chamber}/{congress}/{session}/{number}",
+                "/members/{member_id}",
+                "/members/{member_id}/votes",
+                "/bills/{bill}/votes",
+                "/search?q=",
+            ]
+        })),
+        ["rollcalls"] => to_json(params.query()?.rollcall_page(conn)?),
+        ["rollcalls", chamber, congress, session, number] => {
+            let detail = queries::rollcall(
+                conn,
+                parse_chamber(chamber)?,
+                segment(congress, "congress")?,
+                segment(session, "session")?,
+                segment(number, "roll call number")?,
+            )?;
+            match detail {
+                Some(detail) => to_json(detail),
+                None => Err(ApiError::not_found(format!("no {} roll call {} in session {} of congress {}", chamber, number, session, congress))),
+            }
+        }
+        ["members", member_id] => match queries::member(conn, member_id)? {
+            Some(member) => to_json(member),
+            None => Err(ApiError::not_found(format!("no member '{}'", member_id))),
+        },
+        ["members", member_id, "votes"] => {
+            if queries::member(conn, member_id)?.is_none() {
+                return Err(ApiError::not_found(format!("no member '{}'", member_id)));
+            }
+            to_json(params.query()?.member(*member_id).ballot_page(conn)?)
+        }
+        ["bills", bill, "votes"] => to_json(params.query()?.bill(bill).rollcall_page(conn)?),
+        ["search"] => {
+            let query = params.get("q").ok_or_else(|| ApiError::bad_request("search needs a query: /search?q=..."))?;
+            let filter = SearchFilter { chamber: params.chamber()?, congress: params.number("congress")? };
+            let page = params.page()?;
+
+            // ranked, so there is no total: read through the page and drop the rows before it
+            let hits = search::search(conn, query, &filter, page.offset.saturating_add(page.limit))?;
+            let items: Vec<_> = hits.into_iter().skip(page.offset).collect();
+            Ok(json!({ "query": query, "offset": page.offset, "limit": page.limit, "items": to_json(items)? }))
+        }
+        _ => Err(ApiError::not_found(format!("no such endpoint '{}'", path))),
+    }
+}
+
+async fn respond(conn: Arc<Mutex<Connection>>, request: Request<Body>) -> std::result::Result<Response<Body>, hyper::Error> {
+    let (status, body) = if request.method() != Method::GET {
+        (StatusCode::METHOD_NOT_ALLOWED, json!({ "error": "only GET is supported" }))
+    } else {
+        let path = request.uri().path().to_string();
+        let query = request.uri().query().map(|q| q.to_string());
+        tokio::task::spawn_blocking(move || {
+            let conn = conn.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            handle(&conn, &path, query.as_deref())
+        })
+        .await
+        .unwrap_or_else(|e| (StatusCode::INTERNAL_SERVER_ERROR, json!({ "error": e.to_string() })))
+    };
+
+    let response = Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(body.to_string()))
+        .expect("static response parts are valid");
+    Ok(response)
+}
+
+/// Opens the database at `db_path` read-only and binds `addr`. Returns the
+/// bound address (useful with port 0) and the future that serves requests.
+pub fn bind(db_path: &Path, addr: SocketAddr) -> Result<(SocketAddr, impl std::future::Future<Output = Result<()>>)> {
+    let conn = Connection::open_with_flags(db_path, OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX)
+        .map_err(|e| Error::from(e).with_path(db_path))?;
+    let conn = Arc::new(Mutex::new(conn));
+
+    let make_service = make_service_fn(move |_| {
+        let conn = conn.clone();
+        async move { Ok::<_, hyper::Error>(service_fn(move |request| respond(conn.clone(), request))) }
+    });
+
+    let server = Server::try_bind(&addr)
+        .map_err(|e| Error::Config { message: format!("Cannot listen on {}: {}", addr, e) })?
+        .serve(make_service);
+    let local = server.local_addr();
+
+    Ok((local, async move { server.await.map_err(|e| Error::Config { message: format!("Server failed: {}", e) }) }))
+}
+
+/// Brings the workspace database up to date, then serves it on `addr` until the process is stopped.
+pub async fn serve(workspace: &Workspace, addr: SocketAddr) -> Result<()> {
+    drop(sqllite::open_database(workspace)?);
+
+    let (local, server) = bind(&workspace.database_path(), addr)?;
+    println!("🌐 Serving '{}' on http://{}", workspace.database_path().display(), local);
+    server.await
+}
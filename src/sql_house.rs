@@ -1,84 +1,195 @@
-use core::time;
-use std::collections::HashMap;
+// sql_house.rs
 
-use crate::responses::*;
-use rusqlite::Result;
-
-pub async fn get_house_vote(
-    tx: &rusqlite::Transaction<'_>,
-    info_pulled: &(u16, u8, u32, String, u16),
-    vote: &RollCallVoteHouse
-) -> Result<()> {
-    
-    let (congress, session, roll_call, chamber, year) = info_pulled;
-
-    let vote_metadata = vote.vote_metadata.clone();
-
-    let vote_data = if let Some(vote_data) = vote.vote_data.clone() {
-        vote_data
-    } else {
-        VoteDataHouse::default()
-    };
-
-    let majority =  vote_metadata.majority.unwrap_or("null".to_string());
-
-    let committee = vote_metadata.committee.unwrap_or("null".to_string());
-
-    let legis_num = vote_metadata.legis_num.unwrap_or("null".to_string());
-
-    let vote_question = match vote_metadata.vote_question {
-        Response::String(vote_question) => vote_question,
-        _ => "null".to_string()
-    };
-
-    let vote_type =  match vote_metadata.vote_type {
-        Response::String(vote_type) => vote_type,
-        _ => "null".to_string()
-    };
-
-    let vote_result = match vote_metadata.vote_result {
-        Response::String(vote_result) => vote_result,
-        _ => "null".to_string()
-    };
-
-    let action_date = match vote_metadata.action_date {
-        Response::String(action_date) => action_date,
-        _ => "null".to_string()
-    };
-
-    let action_time = if let Some(action_time) = vote_metadata.action_time {
-        let content = if let Some(content) = action_time.content {
-            content
-        } else {
-            "null".to_string()
-        };
-
-        let time = if let Some(time) = action_time.time_etz {
-            time
-        } else {
-            "null".to_string()
-        };
+use rusqlite::params;
 
-        (content, time)
-    } else {
-        ("null".to_string(), "null".to_string())
-    };
+use crate::dates::VoteTimes;
+use crate::model::{text, RollCall, RollCallId};
+use crate::responses::*;
+use crate::sqllite::{generate_json_hash, insert_ballots, member_id, replace_previous_version};
+use crate::{Error, Result};
+
+/// Parses a House total such as "212"; missing or empty totals are zero.
+fn parse_total(field: &str, total: &Option<String>) -> Result<u32> {
+    match total.as_deref().map(str::trim) {
+        Some(s) if !s.is_empty() => s.parse::<u32>().map_err(|_| Error::parse(field, s)),
+        _ => Ok(0),
+    }
+}
 
-    let vote_desc = match vote_metadata.vote_desc {
-        Response::String(vote_desc) => vote_desc,
-        _ => "null".to_string()
-    };
+/// Upserts the roll call row and the chamber-wide totals, replacing any previous version.
+pub fn insert_vote_metadata_house(tx: &rusqlite::Transaction<'_>, info: &HouseInfo) -> Result<()> {
+    replace_previous_version(tx, &info.id, &info.vote_hash)?;
+
+    tx.execute(
+        "INSERT INTO votes (
+            vote_hash, chamber, congress, session, rollcall, year,
+            vote_date, question, result, majority, committee, legis_num,
+            vote_type, action_time, action_time_etz, vote_desc,
+            vote_day, vote_timestamp, vote_epoch
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19)
+        ON CONFLICT (vote_hash) DO UPDATE SET
+            chamber = excluded.chamber,
+            congress = excluded.congress,
+            session = excluded.session,
+            rollcall = excluded.rollcall,
+            year = excluded.year,
+            vote_date = excluded.vote_date,
+            question = excluded.question,
+            result = excluded.result,
+            majority = excluded.majority,
+            committee = excluded.committee,
+            legis_num = excluded.legis_num,
+            vote_type = excluded.vote_type,
+            action_time = excluded.action_time,
+            action_time_etz = excluded.action_time_etz,
+            vote_desc = excluded.vote_desc,
+            vote_day = excluded.vote_day,
+            vote_timestamp = excluded.vote_timestamp,
+            vote_epoch = excluded.vote_epoch",
+        params![
+            info.vote_hash,
+            info.id.chamber.to_string(),
+            info.id.congress,
+            info.id.session,
+            info.id.number,
+            info.id.year,
+            info.action_date,
+            info.rollcall.question,
+            info.rollcall.result,
+            info.majority,
+            info.committee,
+            info.rollcall.bill,
+            info.rollcall.vote_type,
+            info.action_time,
+            info.action_time_etz,
+            info.rollcall.description,
+            info.times.day_string(),
+            info.times.at_string(),
+            info.times.epoch(),
+        ],
+    )?;
+
+    tx.execute(
+        "INSERT INTO vote_counts (
+            vote_hash, yea, nay, present, not_voting
+        ) VALUES (?1, ?2, ?3, ?4, ?5)
+        ON CONFLICT (vote_hash) DO UPDATE SET
+            yea = excluded.yea,
+            nay = excluded.nay,
+            present = excluded.present,
+            not_voting = excluded.not_voting",
+        params![info.vote_hash, info.yea, info.nay, info.present, info.not_voting],
+    )?;
 
-    let vote_totals = vote_metadata.vote_totals.unwrap_or(VoteTotalsHouse::default());
+    Ok(())
+}
 
-    let totals_by_candidate = vote_totals.totals_by_candidate.unwrap_or(Vec::from([TotalsByCandidateHouse::default()]));
+/// Upserts the `totals-by-party` and `totals-by-candidate` rows.
+pub fn insert_vote_totals_house(tx: &rusqlite::Transaction<'_>, info: &HouseInfo) -> Result<()> {
+    let endpoint = info.id.endpoint();
 
-    let totals_by_party = vote_totals.totals_by_party.unwrap_or(Vec::from([TotalsByPartyHouse::default()]));
+    for totals in &info.totals_by_party {
+        let Some(party) = text(totals.party.as_deref()) else {
+            continue;
+        };
 
-    // total, yea, nay, present, absent
-    let totals_by_vote = vote_totals.totals_by_vote.unwrap_or(TotalsByVoteHouse::default());
+        tx.execute(
+            "INSERT INTO party_totals (
+                vote_hash, party, yea, nay, present, not_voting
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            ON CONFLICT (vote_hash, party) DO UPDATE SET
+                yea = excluded.yea,
+                nay = excluded.nay,
+                present = excluded.present,
+                not_voting = excluded.not_voting",
+            params![
+                info.vote_hash,
+                party,
+                parse_total("totals-by-party.yea-total", &totals.yea_total).map_err(|e| e.with_roll_call(endpoint))?,
+                parse_total("totals-by-party.nay-total", &totals.nay_total).map_err(|e| e.with_roll_call(endpoint))?,
+                parse_total("totals-by-party.present-total", &totals.present_total).map_err(|e| e.with_roll_call(endpoint))?,
+                parse_total("totals-by-party.not-voting-total", &totals.not_voting_total).map_err(|e| e.with_roll_call(endpoint))?,
+            ],
+        )?;
+    }
+
+    for totals in &info.totals_by_candidate {
+        let Some(candidate) = text(totals.candidate.as_deref()) else {
+            continue;
+        };
 
+        tx.execute(
+            "INSERT INTO candidate_totals (vote_hash, candidate, total) VALUES (?1, ?2, ?3)
+            ON CONFLICT (vote_hash, candidate) DO UPDATE SET total = excluded.total",
+            params![
+                info.vote_hash,
+                candidate,
+                parse_total("totals-by-candidate.candidate-total", &totals.candidate_total).map_err(|e| e.with_roll_call(endpoint))?,
+            ],
+        )?;
+    }
 
     Ok(())
 }
 
+/// Upserts every representative on the roll call into `members` and their ballot into `member_votes`.
+pub fn insert_vote_members_house(tx: &rusqlite::Transaction<'_>, info: &HouseInfo) -> Result<()> {
+    insert_ballots(tx, &info.vote_hash, &info.rollcall, &info.member_ids)
+}
+
+/// Reads a House file into what the insert functions take, from its typed
+/// `RollCall` and the clerk's fields the model leaves out.
+pub fn get_house_vote(id: &RollCallId, file: &HouseFile) -> Result<HouseInfo> {
+    let endpoint = id.endpoint();
+    let vote = &file.rollcall_vote;
+
+    let vote_hash = generate_json_hash(vote).map_err(|e| e.with_roll_call(endpoint))?;
+    let rollcall = RollCall::try_from(file).map_err(|e| e.with_roll_call(endpoint))?;
+
+    let metadata = &vote.vote_metadata;
+    let action_date = text(metadata.action_date.as_text().as_deref());
+    let action_time = metadata.action_time.clone().unwrap_or_default();
+    let action_time_etz = text(action_time.time_etz.as_deref());
+    let action_time = text(action_time.content.as_deref());
+    let times = VoteTimes::house(action_date.as_deref(), action_time_etz.as_deref(), action_time.as_deref(), Some(id.year as i32));
+
+    let vote_totals = metadata.vote_totals.clone().unwrap_or_default();
+    let totals_by_vote = vote_totals.totals_by_vote.unwrap_or_default();
+    let (yea, nay, present, not_voting) = (|| {
+        Ok::<_, Error>((
+            parse_total("totals-by-vote.yea-total", &totals_by_vote.yea_total)?,
+            parse_total("totals-by-vote.nay-total", &totals_by_vote.nay_total)?,
+            parse_total("totals-by-vote.present-total", &totals_by_vote.present_total)?,
+            parse_total("totals-by-vote.not-voting-total", &totals_by_vote.not_voting_total)?,
+        ))
+    })()
+    .map_err(|e| e.with_roll_call(endpoint))?;
+
+    // the bioguide id is present on every modern clerk file
+    let member_ids = rollcall
+        .ballots
+        .iter()
+        .map(|ballot| member_id(&ballot.member))
+        .collect::<Result<Vec<_>>>()
+        .map_err(|e| e.with_roll_call(endpoint))?;
+
+    Ok(HouseInfo {
+        id: *id,
+        // hash generated from the vote object
+        vote_hash,
+        majority: text(metadata.majority.as_deref()),
+        committee: text(metadata.committee.as_deref()),
+        action_date,
+        action_time,
+        action_time_etz,
+        times,
+        yea,
+        nay,
+        present,
+        not_voting,
+        totals_by_party: vote_totals.totals_by_party.unwrap_or_default(),
+        totals_by_candidate: vote_totals.totals_by_candidate.unwrap_or_default(),
+        member_ids,
+        rollcall,
+    })
+}
@@ -1,377 +1,216 @@
-// sqllite.rs
+// sql_senate.rs
 
-use crate::responses::*;
-use crate::sqllite::generate_hash;
+use rusqlite::params;
 
-pub async fn insert_vote_metadata_senate(tx: &rusqlite::Transaction<'_>, metadata: (u16, u8, u32, String, u16, String, String, String, String, String, String, String, String, Vec<DocumentSenate>, Vec<AmendmentSenate>, String, (u32, u32, u32, u32, String, String)))
-{
-    println!("Inserting vote metadata into senate_votes table");
+use crate::dates::VoteTimes;
+use crate::model::{text, RollCall, RollCallId};
+use crate::responses::*;
+use crate::sqllite::{generate_json_hash, insert_ballots, member_id, replace_previous_version};
+use crate::{Error, Result};
+
+/// Parses a Senate count; empty counts (common for `present`) are zero.
+fn parse_count(field: &str, value: Option<&Response>) -> Result<u32> {
+    match value {
+        Some(Response::String(s)) if !s.trim().is_empty() => {
+            s.trim().parse::<u32>().map_err(|_| Error::parse(field, s))
+        }
+        Some(Response::U32(n)) => Ok(*n),
+        _ => Ok(0),
+    }
 }
 
-pub async fn insert_vote_count_senate(tx: &rusqlite::Transaction<'_>, count_data: (String, u16, u8, u32, String, u16, u32, u32, u32, u32, String, String))
-{
-    println!("Inserting vote count into senate_votes table");
+/// Upserts the roll call row, its documents and its amendments, replacing any previous version.
+pub fn insert_vote_metadata_senate(tx: &rusqlite::Transaction<'_>, info: &SenateInfo) -> Result<()> {
+    replace_previous_version(tx, &info.id, &info.vote_hash)?;
+
+    tx.execute(
+        "INSERT INTO votes (
+            vote_hash, chamber, congress, session, rollcall, year,
+            vote_date, modify_date, question, question_text, document_text,
+            result, title, majority_requirement,
+            vote_day, vote_timestamp, vote_epoch, modified_timestamp
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18)
+        ON CONFLICT (vote_hash) DO UPDATE SET
+            chamber = excluded.chamber,
+            congress = excluded.congress,
+            session = excluded.session,
+            rollcall = excluded.rollcall,
+            year = excluded.year,
+            vote_date = excluded.vote_date,
+            modify_date = excluded.modify_date,
+            question = excluded.question,
+            question_text = excluded.question_text,
+            document_text = excluded.document_text,
+            result = excluded.result,
+            title = excluded.title,
+            majority_requirement = excluded.majority_requirement,
+            vote_day = excluded.vote_day,
+            vote_timestamp = excluded.vote_timestamp,
+            vote_epoch = excluded.vote_epoch,
+            modified_timestamp = excluded.modified_timestamp",
+        params![
+            info.vote_hash,
+            info.id.chamber.to_string(),
+            info.id.congress,
+            info.id.session,
+            info.id.number,
+            info.id.year,
+            info.vote_date,
+            info.vote_modify_date,
+            info.rollcall.question,
+            info.vote_question_text,
+            info.vote_document_text,
+            info.rollcall.result,
+            info.rollcall.description,
+            info.rollcall.majority_requirement,
+            info.times.day_string(),
+            info.times.at_string(),
+            info.times.epoch(),
+            info.times.modified_string(),
+        ],
+    )?;
+
+    tx.execute("DELETE FROM documents WHERE vote_hash = ?1", params![info.vote_hash])?;
+    let documents = info.documents.iter().map(|doc| {
+        [
+            doc.document_congress.as_text(),
+            doc.document_type.as_text(),
+            doc.document_number.as_text(),
+            doc.document_name.as_text(),
+            doc.document_title.as_text(),
+            doc.document_short_title.clone(),
+        ]
+    });
+    // Empty `<document>` elements carry no information and are not stored
+    for (position, doc) in documents.filter(|doc| doc.iter().any(Option::is_some)).enumerate() {
+        tx.execute(
+            "INSERT INTO documents (
+                vote_hash, position, document_congress, document_type, document_number,
+                document_name, document_title, document_short_title
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![info.vote_hash, position, doc[0], doc[1], doc[2], doc[3], doc[4], doc[5]],
+        )?;
+    }
+
+    tx.execute("DELETE FROM amendments WHERE vote_hash = ?1", params![info.vote_hash])?;
+    let amendments = info.amendments.iter().map(|amend| {
+        [
+            amend.amendment_number.clone(),
+            amend.amendment_to_amendment_number.as_ref().and_then(Response::as_text),
+            amend.amendment_to_amendment_to_amendment_number.as_ref().and_then(Response::as_text),
+            amend.amendment_to_document_number.as_ref().and_then(Response::as_text),
+            amend.amendment_to_document_short_title.as_ref().and_then(Response::as_text),
+            amend.amendment_purpose.clone(),
+        ]
+    });
+    // Most votes carry an `<amendment>` element with every field empty
+    for (position, amend) in amendments.filter(|amend| amend.iter().any(Option::is_some)).enumerate() {
+        tx.execute(
+            "INSERT INTO amendments (
+                vote_hash, position, amendment_number, amendment_to_amendment_number,
+                amendment_to_amendment_to_amendment_number, amendment_to_document_number,
+                amendment_to_document_short_title, amendment_purpose
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![info.vote_hash, position, amend[0], amend[1], amend[2], amend[3], amend[4], amend[5]],
+        )?;
+    }
+
+    Ok(())
 }
 
-pub async fn insert_vote_members_senate(tx: &rusqlite::Transaction<'_>, member_data: (String, u16, u8, u32, String, u16, Vec<MemberSenate>, Vec<CongressionalMember>))
-{
-    println!("Inserting vote members into senate_votes table");
+/// Upserts the yea/nay/present/absent totals and the tie breaker, if any.
+pub fn insert_vote_count_senate(tx: &rusqlite::Transaction<'_>, info: &SenateInfo) -> Result<()> {
+    tx.execute(
+        "INSERT INTO vote_counts (
+            vote_hash, yea, nay, present, not_voting, tie_breaker_by, tie_breaker_vote
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+        ON CONFLICT (vote_hash) DO UPDATE SET
+            yea = excluded.yea,
+            nay = excluded.nay,
+            present = excluded.present,
+            not_voting = excluded.not_voting,
+            tie_breaker_by = excluded.tie_breaker_by,
+            tie_breaker_vote = excluded.tie_breaker_vote",
+        params![
+            info.vote_hash,
+            info.yay,
+            info.nay,
+            info.present,
+            info.absent,
+            info.tie_breaker_name,
+            info.tie_breaker_paired,
+        ],
+    )?;
+
+    Ok(())
 }
 
-pub async fn get_senate_vote(
-    tx: &rusqlite::Transaction<'_>,
-    info_pulled: &(u16, u8, u32, String, u16),
-    vote: &RollCallVoteSenate
-) -> SenateInfo
-{
-    let (congress_number, session_number, rollcall_number, chamber, year) = info_pulled.clone();
-
-    let vote_hash = generate_hash(&serde_json::to_string(vote).unwrap()).await;
-
-    let vote_date = match vote.vote_date.clone() {
-        Response::String(date) => date,
-        _ => {
-            "null".to_string()
-        }
-    };
-
-    let vote_modify_date = match vote.modify_date.clone() {
-        Some(s) => s,
-        _ => {
-            "null".to_string()
-        }
-    };
-
-    let vote_question = match vote.question.clone() {
-        Response::String(question) => question,
-        _ => {
-            "null".to_string()
-        }
-    };
-
-    let vote_question_text = match vote.vote_question_text.clone() {
-        Response::String(question_text) => question_text,
-        _ => {
-            "null".to_string()
-        }
-    };
-
-    let vote_document_text = match vote.vote_document_text.clone() {
-        Response::String(document_text) => document_text,
-        _ => {
-            "null".to_string()
-        }
-    };
-
-    let vote_result = match vote.vote_result.clone() {
-        Some(s) => s,
-        _ => {
-            "null".to_string()
-        }
-    };
-
-    let vote_title = match vote.vote_title.clone() {
-        Some(s) => s,
-        _ => {
-            "null".to_string()
-        }
-    };
-
-    let majority_requirement = match vote.majority_requirement.clone() {
-        Some(s) => s,
-        _ => {
-            "null".to_string()
-        }
-    };
-
-    let documents = if let Some(doc) = &vote.document {
-
-        let mut docs = vec![];
-
-        match doc {
-            ResponseSpecific::DocumentSenate(doc) => {
-                docs.push(*doc.clone());
-            }
-            ResponseSpecific::DSMap(doc_vector) => {
-                for doc in doc_vector {
-                    docs.push(doc.clone());
-                }
-            }
-            _ => {
-                docs.push(DocumentSenate::default());
-            }
-        }
-
-        docs
-    } else {
-        vec![DocumentSenate::default()]
-    };
-
-    let amendments = if let Some(amend) = &vote.amendment {
+/// Upserts every senator on the roll call into `members` and their ballot into `member_votes`.
+pub fn insert_vote_members_senate(tx: &rusqlite::Transaction<'_>, info: &SenateInfo) -> Result<()> {
+    insert_ballots(tx, &info.vote_hash, &info.rollcall, &info.member_ids)
+}
 
-        let mut amends = vec![];
+/// Reads a Senate file into what the insert functions take, from its typed
+/// `RollCall` and the parts of the file the model leaves out.
+pub fn get_senate_vote(id: &RollCallId, file: &SenateFile) -> Result<SenateInfo> {
+    let endpoint = id.endpoint();
+    let vote = &file.roll_call_vote;
 
-        match amend {
-            ResponseSpecific::AmendmentSenate(amend) => {
-                amends.push(*amend.clone());
-            }
-            ResponseSpecific::ASMap(amend_vector) => {
-                for amend in amend_vector {
-                    amends.push(amend.clone());
-                }
-            }
-            _ => {
-                amends.push(AmendmentSenate::default());
-            }
-        }
+    let vote_hash = generate_json_hash(vote).map_err(|e| e.with_roll_call(endpoint))?;
+    let rollcall = RollCall::try_from(file).map_err(|e| e.with_roll_call(endpoint))?;
 
-        amends
-    } else {
-        vec![AmendmentSenate::default()]
-    };
+    let vote_date = text(vote.vote_date.as_text().as_deref());
+    let vote_modify_date = text(vote.modify_date.as_deref());
+    let times = VoteTimes::senate(vote_date.as_deref(), vote_modify_date.as_deref(), Some(id.year as i32));
 
     let (yay, nay, present, absent) = if let Some(count) = &vote.count {
-        let yay = match count.yeas.clone() {
-            Some(s) => s.parse::<u32>().unwrap(),
-            _ => {
-            0
-            }
-        };
-
-        let nay = match count.nays.clone() {
-            Some(s) => s.parse::<u32>().unwrap(),
-            _ => {
-            0
-            }
-        };
-
-        let present = if let Some(s) = count.present.clone() {
-            match s {
-                Response::String(s) => {
-                    s.parse::<u32>().unwrap()
-                }
-                Response::U32(n) => {
-                    n
-                }
-                _ => {
-                    0
-                }
-            }
-        } else {
-            0
-        };
-
-        let absent = if let Some(s) = count.absent.clone() {
-            match s {
-                Response::String(s) => {
-                    s.parse::<u32>().unwrap()
-                }
-                Response::U32(n) => {
-                    n
-                }
-                _ => {
-                    0
-                }
-            }
-        } else {
-            0
-        };
-
-        (yay, nay, present, absent)
+        let parsed = (|| {
+            Ok::<_, Error>((
+                parse_count("count.yeas", count.yeas.clone().map(Response::String).as_ref())?,
+                parse_count("count.nays", count.nays.clone().map(Response::String).as_ref())?,
+                parse_count("count.present", count.present.as_ref())?,
+                parse_count("count.absent", count.absent.as_ref())?,
+            ))
+        })();
+
+        parsed.map_err(|e| e.with_roll_call(endpoint))?
     } else {
         (0, 0, 0, 0)
     };
 
-    let tie_breaker = if let Some(tie) = &vote.tie_breaker {
-        let by_whom = match tie.by_whom.clone() {
-            Response::String(s) => s,
-            _ => {
-                "null".to_string()
-            }
-        };
-        let tie_breaker_vote = match tie.tie_breaker_vote.clone() {
-            Response::String(s) => s,
-            _ => {
-                "null".to_string()
-            }
-        };
-        
-        (by_whom, tie_breaker_vote)
-    } else {
-        ("null".to_string(), "null".to_string())
-    };
-
-    let members = if let Some(mem) = &vote.members {
-        // struct MemberSenate for senate specific filing
-        let mut senate_mems = vec![];
-        // struct CongressionalMember for general filing
-        let mut mems = vec![];
-
-        if let Some(mem_vector) = mem.member.clone() {
-            for member in mem_vector {
-                let full = match member.member_full.clone() {
-                    Some(s) => s,
-                    _ => {
-                        "null".to_string()
-                    }
-                };
-
-                let last_name = match member.last_name.clone() {
-                    Some(s) => s,
-                    _ => {
-                        "null".to_string()
-                    }
-                };
-
-                let first_name = match member.first_name.clone() {
-                    Some(s) => s,
-                    _ => {
-                        "null".to_string()
-                    }
-                };
-
-                let party = match member.party.clone() {
-                    Some(s) => s,
-                    _ => {
-                        "null".to_string()
-                    }
-                };
-
-                let state = match member.state.clone() {
-                    Some(s) => s,
-                    _ => {
-                        "null".to_string()
-                    }
-                };
-
-                let vote_cast = if let Some(vote) = member.vote_cast.clone() {
-                    match vote {
-                        ResponseSpecific::VoteCastMap(name_and_paired) => {
-                            let name = match name_and_paired.name.clone() {
-                                Some(s) => s,
-                                _ => {
-                                    "null".to_string()
-                                }
-                            };
-
-                            let paired = match name_and_paired.paired_with.clone() {
-                                Some(s) => s,
-                                _ => {
-                                    "null".to_string()
-                                }
-                            };
-
-                            VoteCastMap {
-                                name: Some(name),
-                                paired_with: Some(paired)
-                            }
-                        }
-                        ResponseSpecific::String(s) => {
-                            let name = Some(s.clone());
-                            let paired_with = Some("null".to_string());
-
-                            VoteCastMap {
-                                name,
-                                paired_with
-                            }
-                        }
-                        _ => {
-                            VoteCastMap {
-                                name: Some("null".to_string()),
-                                paired_with: Some("null".to_string())
-                            }
-                        }
-                    }
-                } else {
-                    VoteCastMap {
-                        name: Some("null".to_string()),
-                        paired_with: Some("null".to_string())
-                    }
-                };
-
-                let lis_member_id = match member.lis_member_id.clone() {
-                    Some(s) => s,
-                    _ => {
-                        "null".to_string()
-                    }
-                };
-
-                let hash_input = (last_name.trim().to_lowercase(), party.clone(), state.clone());
-
-                let member_hash = generate_hash(&serde_json::to_string(&hash_input).unwrap()).await;
-
-                let m = MemberSenate {
-                    member_full: Some(full),
-                    last_name: Some(last_name.clone()),
-                    first_name: Some(first_name),
-                    party: Some(party.clone()),
-                    state: Some(state.clone()),
-                    vote_cast: Some(ResponseSpecific::VoteCastMap(vote_cast)),
-                    lis_member_id: Some(lis_member_id),
-                    extra: Some(std::collections::HashMap::from_iter(vec![
-                        ("generated_id".to_string(), serde_json::to_value(member_hash.clone()).unwrap())
-                    ]))
-                };
-
-                senate_mems.push(m);
-
-                let m = CongressionalMember {
-                    last_name,
-                    party,
-                    state,
-                    generated_id: member_hash,
-                };
-
-                mems.push(m);
-            }
-        } else {
-            println!("❌ No members found in vote: {}", vote_hash);
-            mems.push(CongressionalMember {
-                last_name: "null".to_string(),
-                party: "null".to_string(),
-                state: "null".to_string(),
-                generated_id: "null".to_string(),
-            });
-        }
-
-        (senate_mems, mems)
-    } else {
-        (vec![], vec![])
+    let (tie_breaker_name, tie_breaker_paired) = match &vote.tie_breaker {
+        Some(tie) => (text(tie.by_whom.as_text().as_deref()), text(tie.tie_breaker_vote.as_text().as_deref())),
+        None => (None, None),
     };
 
-    let senate_info: SenateInfo = SenateInfo {
-        congress_number,
-        session_number,
-        rollcall_number,
-        chamber: chamber.clone(),
-        year,
+    // Senators are keyed on their LIS id, which survives party switches and
+    // tells apart same-named senators; the hash only covers ballots without one
+    let member_ids = rollcall
+        .ballots
+        .iter()
+        .map(|ballot| member_id(&ballot.member))
+        .collect::<Result<Vec<_>>>()
+        .map_err(|e| e.with_roll_call(endpoint))?;
+
+    Ok(SenateInfo {
+        id: *id,
         // hash generated from the vote object
-        vote_hash: vote_hash.clone(),
+        vote_hash,
         vote_date,
         vote_modify_date,
-        vote_question,
-        vote_question_text,
-        vote_document_text,
-        vote_result,
-        vote_title,
-        majority_requirement,
-        // vec of possible documents as json strings
-        documents,
-        // vec of possible amendments as json strings
-        amendments,
+        times,
+        vote_question_text: text(vote.vote_question_text.as_text().as_deref()),
+        vote_document_text: text(vote.vote_document_text.as_text().as_deref()),
+        // documents and amendments as published, in file order
+        documents: vote.document.clone(),
+        amendments: vote.amendment.clone(),
         yay,
         nay,
         present,
         absent,
-        // name
-        tie_breaker_name: tie_breaker.0,
-        // paired_with
-        tie_breaker_paired: tie_breaker.1,
-        // vec of members as MemberSenate objects
-        members_as_is: members.0,
-        // vec of members as CongressionalMember objects
-        members_to_gen: members.1
-    };
-
-    senate_info
+        tie_breaker_name,
+        tie_breaker_paired,
+        member_ids,
+        rollcall,
+    })
 }
@@ -1,324 +1,333 @@
 // sqllite.rs
 
-use rusqlite::{Connection, Error, Result};
-use std::fs::File;
-use walkdir::WalkDir;
+use rusqlite::Connection;
 use indicatif::{ProgressBar, ProgressStyle};
 use sha2::{Sha256, Digest}; 
 
-use crate::responses::*;
+use crate::pipeline::{collect_vote_files, parse_in_order, PathMismatch, VoteFileEntry};
+use crate::workspace::Workspace;
+use crate::migrations;
+use crate::model::{Member, RollCall, RollCallId};
+use crate::responses::{HouseInfo, SenateInfo, VoteFile};
+use crate::xml_reader::{load_vote_file, parse_vote_document, VoteFormat};
+use crate::{Error, Result};
+
+pub use crate::migrations::SCHEMA_VERSION;
+
+/// The full schema at `SCHEMA_VERSION`, kept in `full_data/schema.sql` so it can be read without the crate.
+pub const SCHEMA_SQL: &str = include_str!("../full_data/schema.sql");
+
+/// Function to initialize the database and create tables with appropriate constraints,
+/// applying the migrations it is missing (see `migrations`)
+pub fn initialize_database(conn: &Connection) -> Result<()> {
+    let version = migrations::current_version(conn)?;
+    let applied = migrations::migrate(conn)?;
+
+    if version == 0 {
+        println!("🗄️ Database schema initialized at version {}", SCHEMA_VERSION);
+    } else if !applied.is_empty() {
+        println!("🗄️ Database schema upgraded from version {} to {}", version, SCHEMA_VERSION);
+    }
 
-/// Function to initialize the database and create tables with appropriate constraints
-async fn initialize_database(conn: &Connection) -> Result<()> {
-    
     Ok(())
 }
 
-pub async fn generate_hash(data: &str) -> String {
+/// Opens the workspace's database, creating it (and its directory) if needed, at the current schema version.
+pub fn open_database(workspace: &Workspace) -> Result<Connection> {
+    let db_path = workspace.database_path();
+    Workspace::create_parent(&db_path)?;
+
+    let conn = Connection::open(&db_path).map_err(|e| Error::from(e).with_path(&db_path))?;
+    initialize_database(&conn)?;
+    Ok(conn)
+}
+
+pub fn generate_hash(data: &str) -> String {
     let mut hasher = Sha256::new();
     hasher.update(data);
     let result = hasher.finalize();
     format!("{:x}", result)
 }
 
+/// Hashes the JSON serialization of `value`, as used for `vote_hash` and generated member ids.
+pub fn generate_json_hash<T: serde::Serialize>(value: &T) -> Result<String> {
+    let data = serde_json::to_string(value)
+        .map_err(|e| Error::schema(format!("Failed to serialize value for hashing: {}", e)))?;
+    Ok(generate_hash(&data))
+}
+
+/// `members.member_id` of a ballot: the bioguide or LIS id, or without either a
+/// hash of last name, party and state.
+pub fn member_id(member: &Member) -> Result<String> {
+    match member.id() {
+        Some(id) => Ok(id.to_string()),
+        None => {
+            let last_name = member.last_name.as_deref().unwrap_or_default().to_lowercase();
+            generate_json_hash(&(last_name, &member.party, &member.state))
+        }
+    }
+}
+
+/// Removes an earlier version of the roll call `id` (the same chamber, congress,
+/// session and number under a hash other than `vote_hash`) with its dependent rows,
+/// so a corrected document replaces the one it corrects.
+pub fn replace_previous_version(tx: &rusqlite::Transaction<'_>, id: &RollCallId, vote_hash: &str) -> Result<()> {
+    tx.execute(
+        "DELETE FROM votes
+         WHERE chamber = ?1 AND congress = ?2 AND session = ?3 AND rollcall = ?4 AND vote_hash <> ?5",
+        rusqlite::params![id.chamber.to_string(), id.congress, id.session, id.number, vote_hash],
+    )?;
+    Ok(())
+}
+
+/// Upserts every member on the roll call into `members` and their ballot into
+/// `member_votes`, under `member_ids` (one per ballot).
+pub fn insert_ballots(tx: &rusqlite::Transaction<'_>, vote_hash: &str, rollcall: &RollCall, member_ids: &[String]) -> Result<()> {
+    for (ballot, member_id) in rollcall.ballots.iter().zip(member_ids) {
+        let member = &ballot.member;
+        let party = member.party.as_ref().map(|p| p.code());
+        let state = member.state.map(|s| s.code());
+
+        tx.execute(
+            "INSERT INTO members (
+                member_id, chamber, bioguide_id, lis_member_id, full_name, first_name, last_name, party, state
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+            ON CONFLICT (member_id) DO UPDATE SET
+                bioguide_id = excluded.bioguide_id,
+                lis_member_id = excluded.lis_member_id,
+                full_name = excluded.full_name,
+                first_name = excluded.first_name,
+                last_name = excluded.last_name,
+                party = excluded.party,
+                state = excluded.state",
+            rusqlite::params![
+                member_id,
+                rollcall.chamber.to_string(),
+                member.bioguide_id,
+                member.lis_member_id,
+                member.full_name,
+                member.first_name,
+                member.last_name,
+                party,
+                state,
+            ],
+        )?;
+
+        tx.execute(
+            "INSERT INTO member_votes (
+                vote_hash, member_id, vote_cast, paired_with, party, state
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            ON CONFLICT (vote_hash, member_id) DO UPDATE SET
+                vote_cast = excluded.vote_cast,
+                paired_with = excluded.paired_with,
+                party = excluded.party,
+                state = excluded.state",
+            rusqlite::params![
+                vote_hash,
+                member_id,
+                ballot.cast.as_ref().map(|c| c.to_string()),
+                ballot.paired_with,
+                party,
+                state,
+            ],
+        )?;
+    }
+
+    Ok(())
+}
+
+/// A vote file parsed into what the insert functions take.
+pub enum ParsedVote {
+    House(HouseInfo),
+    Senate(SenateInfo),
+}
+
+/// Loads and parses one vote file. Runs on the parse workers.
+/// The roll call is identified by the document; a path that disagrees with it is returned alongside.
+pub fn parse_vote_file(entry: &VoteFileEntry) -> Result<(ParsedVote, Option<PathMismatch>)> {
+    let file = load_vote_file(&entry.path)?;
+    let (id, mismatch) = entry.identify(&file)?;
+    let parsed = parse_vote(&file, &id).map_err(|e| e.with_path(&entry.path))?;
+    Ok((parsed, mismatch))
+}
+
+/// Turns a parsed document into what the insert functions take.
+pub fn parse_vote(file: &VoteFile, id: &RollCallId) -> Result<ParsedVote> {
+    match file {
+        VoteFile::HouseFile(house) => crate::sql_house::get_house_vote(id, house).map(ParsedVote::House),
+        VoteFile::SenateFile(senate) => crate::sql_senate::get_senate_vote(id, senate).map(ParsedVote::Senate),
+    }
+}
+
+/// Parses a document of either format and chamber (e.g. read from stdin) and
+/// writes it in its own transaction. Returns the roll call it recorded.
+pub fn insert_vote_document(conn: &mut Connection, data: &str) -> Result<RollCallId> {
+    let file = parse_vote_document(data, VoteFormat::detect(data))?;
+    let id = RollCallId::try_from(&file)?;
+    let parsed = parse_vote(&file, &id)?;
+
+    let tx = conn.transaction()?;
+    insert_parsed_vote(&tx, &parsed)?;
+    tx.commit()?;
+
+    Ok(id)
+}
+
+/// Writes one parsed vote and its search index row. Runs on the database writer.
+pub fn insert_parsed_vote(tx: &rusqlite::Transaction<'_>, parsed: &ParsedVote) -> Result<()> {
+    let vote_hash = match parsed {
+        ParsedVote::House(vote_info) => {
+            // Insert the vote metadata and chamber totals
+            crate::sql_house::insert_vote_metadata_house(tx, vote_info)?;
+            // Insert the party and candidate totals
+            crate::sql_house::insert_vote_totals_house(tx, vote_info)?;
+            // Insert the member data
+            crate::sql_house::insert_vote_members_house(tx, vote_info)?;
+            &vote_info.vote_hash
+        }
+        ParsedVote::Senate(vote_info) => {
+            // Insert the vote metadata, documents and amendments
+            crate::sql_senate::insert_vote_metadata_senate(tx, vote_info)?;
+            // Insert the vote count data
+            crate::sql_senate::insert_vote_count_senate(tx, vote_info)?;
+            // Insert the member data
+            crate::sql_senate::insert_vote_members_senate(tx, vote_info)?;
+            &vote_info.vote_hash
+        }
+    };
+
+    crate::search::index_vote(tx, vote_hash)
+}
+
+/// What `insert_vote_files` wrote.
+#[derive(Debug, Default)]
+pub struct InsertReport {
+    pub written: usize,
+    /// Files written as the roll call their document records, not the one their path names.
+    pub mismatched: Vec<PathMismatch>,
+}
+
+/// Parses `vote_files` on `workers` threads and writes them from the calling
+/// thread, in the order given, in a single transaction. Files that fail to
+/// parse are reported and skipped; a database error aborts the transaction.
+pub fn insert_vote_files(conn: &mut Connection, vote_files: &[VoteFileEntry], workers: usize, pb: &ProgressBar) -> Result<InsertReport> {
+    let tx = conn.transaction()?;
+    let mut report = InsertReport::default();
+
+    parse_in_order(vote_files, workers, parse_vote_file, |entry, parsed| {
+        match parsed {
+            Ok((parsed, mismatch)) => {
+                insert_parsed_vote(&tx, &parsed).map_err(|e| e.with_path(&entry.path))?;
+                report.written += 1;
+                if let Some(mismatch) = mismatch {
+                    pb.println(format!("⚠️ {}", mismatch));
+                    report.mismatched.push(mismatch);
+                }
+            }
+            Err(e) => pb.println(format!("❌ {}", e)),
+        }
+
+        pb.inc(1);
+        Ok(())
+    })?;
+
+    // Commit the transaction
+    tx.commit()?;
+    Ok(report)
+}
 
 /// Asynchronous function to process vote files and insert into SQLite
-/// If `single_file` is provided, only that file will be processed.
-/// Otherwise, all JSON files in the `data/json` directory will be processed.
-pub async fn process_vote_files_sql(single_file: Option<&str>) -> Result<()> {
-    let base_path = "data/json";
+/// If `single_file` is provided, only that file will be processed, in whichever format its extension names.
+/// It may be anywhere on disk, and `-` reads one document from stdin.
+/// Otherwise, all files of `format` in the workspace's data directory will be processed.
+/// Files are parsed on `workers` threads (0 for one per core) and written by one database thread
+/// to the workspace's database.
+pub async fn process_vote_files_sql(workspace: &Workspace, single_file: Option<&str>, format: VoteFormat, workers: usize) -> Result<()> {
+    if single_file == Some("-") {
+        return process_stdin_sql(workspace).await;
+    }
 
-    // Determine the list of JSON files to process
-    let json_files: Vec<std::path::PathBuf> = match single_file {
+    // Determine the list of vote files to process
+    let vote_files: Vec<VoteFileEntry> = match single_file {
         Some(file_path) => {
             let path = std::path::PathBuf::from(file_path);
-            if path.is_file()
-                && path
-                    .extension()
-                    .and_then(|s| s.to_str())
-                    .map(|ext| ext.eq_ignore_ascii_case("json"))
-                    .unwrap_or(false)
-            {
-                vec![path]
+            if path.is_file() && VoteFormat::from_path(&path).is_some() {
+                // Outside the layout, the document says where it belongs
+                match workspace.parse_vote_path(&path).or_else(|_| VoteFileEntry::from_document(&path)) {
+                    Ok(entry) => vec![entry],
+                    Err(e) => {
+                        println!("📁 {}", e);
+                        vec![]
+                    }
+                }
             } else {
-                println!("⚠️ Provided file is not a valid JSON file: {}", file_path);
+                println!("⚠️ Provided file is not a valid JSON or XML file: {}", file_path);
                 vec![]
             }
         }
-        None => {
-            // Collect all JSON file paths
-            WalkDir::new(base_path)
-                .into_iter()
-                .filter_map(|e| e.ok())
-                .filter(|e| {
-                    e.file_type().is_file()
-                        && e.path().extension().and_then(|s| s.to_str()) == Some("json")
-                })
-                .map(|e| e.into_path())
-                .collect()
-        }
+        None => collect_vote_files(workspace, format),
     };
 
-    let total_files = json_files.len();
+    insert_vote_entries_sql(workspace, vote_files, workers).await
+}
+
+/// Adds the given vote files to the workspace's database in one transaction,
+/// parsed on `workers` threads (0 for one per core).
+pub async fn insert_vote_entries_sql(workspace: &Workspace, vote_files: Vec<VoteFileEntry>, workers: usize) -> Result<()> {
+    let total_files = vote_files.len();
 
     if total_files == 0 {
-        println!("📭 No JSON files to process.");
+        println!("📭 No vote files to process.");
         return Ok(());
     }
 
-    println!("📂 Total JSON files to process: {}", total_files);
+    println!("📂 Total vote files to process: {}", total_files);
 
     // Initialize the progress bar
     let pb = ProgressBar::new(total_files as u64);
-    let pb_style = match ProgressStyle::default_bar()
-        .template("[{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} ({percent}%) {msg}")
-    {
-        Ok(style) => style,
-        Err(_) => {
-            println!("❌ Failed to create progress bar style");
-            return Err(Error::InvalidParameterName(
-                "Failed to create progress bar style".to_string(),
-            ));
-        }
-    };
+    let pb_style = ProgressStyle::default_bar()
+        .template("[{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} ({percent}%) {msg}")?;
     pb.set_style(pb_style.progress_chars("=> "));
     pb.set_message("Processing files");
 
     let pb_up = pb.clone();
+    let workspace = workspace.clone();
 
-    // Clone the list for moving into the blocking task
-    let json_files_clone = json_files.clone();
-
-    // Spawn a blocking task to handle database operations
-    let handle = async move {
+    // rusqlite blocks, so the writer gets its own thread rather than a runtime worker
+    let handle = tokio::task::spawn_blocking(move || {
         // Initialize the database connection
-        let mut conn = Connection::open("full_data/votes.db")?;
-        initialize_database(&conn).await?;
-
-        // Begin a transaction for batch insertion
-        let tx = conn.transaction()?;
-
-        for path in json_files_clone {
-            let path_str = path.to_str().unwrap_or("Invalid path");
-
-            // Extract parts of the path
-            // data/json/{congress_number}/{chamber}/{session_number}/{file}.json
-            let parts: Vec<&str> = if cfg!(windows) {
-                path_str.split('\\').collect()
-            } else {
-                path_str.split('/').collect()
-            };
-
-            if parts.len() != 6 {
-                println!("📁 Unexpected path format: {}", path_str);
-                pb_up.inc(1);
-                continue;
-            }
+        let mut conn = open_database(&workspace)?;
 
-            let congress_number: u16 = match parts[2].parse() {
-                Ok(num) => num,
-                Err(_) => {
-                    println!("❌ Invalid congress number in path: {}", path_str);
-                    pb_up.inc(1);
-                    continue;
-                }
-            };
-
-            let chamber = parts[3].to_lowercase();
-            let session_number: u8 = match parts[4].parse() {
-                Ok(num) => num,
-                Err(_) => {
-                    println!("❌ Invalid session number in path: {}", path_str);
-                    pb_up.inc(1);
-                    continue;
-                }
-            };
-
-            let file_name = parts[5];
-            // Extract the rollcall number and the year from the file name
-            // Format: {year}_{rollcall_number}.json
-            
-            let (year, rollcall_number): (u16, u32) = match file_name
-                .trim_end_matches(".json")
-                .split('_')
-                .collect::<Vec<&str>>()
-                .as_slice()
-            {
-                [year, rollcall] => match (year.parse(), rollcall.parse()) {
-                    (Ok(y), Ok(r)) => (y, r),
-                    _ => {
-                        println!("❌ Invalid year or rollcall number in file name: {}", file_name);
-                        pb_up.inc(1);
-                        continue;
-                    }
-                },
-                _ => {
-                    println!("❌ Invalid file name format: {}", file_name);
-                    pb_up.inc(1);
-                    continue;
-                }
-            };
-            
-            let info_pulled: (u16, u8, u32, String, u16) = (congress_number, session_number, rollcall_number, chamber.to_string(), year);
-
-            // Open and parse the JSON file
-            let file = match File::open(&path) {
-                Ok(f) => f,
-                Err(e) => {
-                    println!("❌ Failed to open file {}: {}", path_str, e);
-                    pb_up.inc(1);
-                    continue;
-                }
-            };
-            println!("🔍 Processing file: {}", path_str);
-
-            match chamber.as_str() {
-                "house" => {
-                    let vote_parent: HouseFile = match serde_json::from_reader(file) {
-                        Ok(v) => v,
-                        Err(e) => {
-                            println!("❌ Failed to parse JSON file {}: {}", path_str, e);
-                            pb_up.inc(1);
-                            continue;
-                        }
-                    };
+        let report = insert_vote_files(&mut conn, &vote_files, workers, &pb_up)?;
+        println!("📦 Database transaction committed successfully ({} files).", report.written);
+        if !report.mismatched.is_empty() {
+            println!("⚠️ {} files are filed under a different roll call than they record.", report.mismatched.len());
+        }
+        Ok::<(), Error>(())
+    });
 
-                    let vote = vote_parent.rollcall_vote;
+    // Await the database task
+    handle.await.map_err(|e| Error::task("Database", e))??;
 
-                    crate::sql_house::get_house_vote(&tx, &info_pulled, &vote).await?;
+    pb.finish_with_message("✅ Processing complete");
 
-                }
-                "senate" => {
-                    let vote_parent: SenateFile = match serde_json::from_reader(file) {
-                        Ok(v) => v,
-                        Err(e) => {
-                            println!("❌ Failed to parse JSON file {}: {}", path_str, e);
-                            pb_up.inc(1);
-                            continue;
-                        }
-                    };
-
-                    let vote = vote_parent.roll_call_vote;
-
-                    let vote_info = crate::sql_senate::get_senate_vote(&tx, &info_pulled, &vote).await;
-
-                    let vote_metadata = (
-                        congress_number,
-                        session_number,
-                        rollcall_number,
-                        chamber.clone(),
-                        year,
-                        vote_info.vote_hash.clone(),
-                        vote_info.vote_date.clone(),
-                        vote_info.vote_modify_date.clone(),
-                        vote_info.vote_question.clone(),
-                        vote_info.vote_question_text.clone(),
-                        vote_info.vote_document_text.clone(),
-                        vote_info.vote_result.clone(),
-                        vote_info.vote_title.clone(),
-                        vote_info.documents.clone(),
-                        vote_info.amendments.clone(),
-                        vote_info.majority_requirement.clone(),
-                        (   vote_info.yay,
-                            vote_info.nay,
-                            vote_info.present,
-                            vote_info.absent,
-                            vote_info.tie_breaker_name.clone(),
-                            vote_info.tie_breaker_paired.clone()
-                        )
-                    );
-
-                    let count_data = (
-                        vote_info.vote_hash.clone(),
-                        congress_number,
-                        session_number,
-                        rollcall_number,
-                        chamber.clone(),
-                        year,
-                        vote_info.yay,
-                        vote_info.nay,
-                        vote_info.present,
-                        vote_info.absent,
-                        vote_info.tie_breaker_name.clone(),
-                        vote_info.tie_breaker_paired.clone()
-                    );
-
-                    let member_data = (
-                        vote_info.vote_hash.clone(),
-                        congress_number,
-                        session_number,
-                        rollcall_number,
-                        chamber.clone(),
-                        year,
-                        vote_info.members_as_is.clone(),
-                        vote_info.members_to_gen.clone()
-                    );
-
-                    println!("{}", congress_number);
-                    println!("{}", session_number);
-                    println!("{}", rollcall_number);
-                    println!("{}", chamber);
-                    println!("{}", year);
-                    println!("{}", vote_info.vote_hash);
-                    println!("{}", vote_info.vote_date);
-                    println!("{}", vote_info.vote_modify_date);
-                    println!("{}", vote_info.vote_question);
-                    println!("{}", vote_info.vote_question_text);
-                    println!("{}", vote_info.vote_document_text);
-                    println!("{}", vote_info.vote_result);
-                    println!("{}", vote_info.vote_title);
-                    println!("{}", vote_info.majority_requirement);
-                    for doc in vote_info.documents.clone() {
-                        println!("{}", serde_json::to_string_pretty(&doc).unwrap());
-                    }
-                    for amend in vote_info.amendments.clone() {
-                        println!("{}", serde_json::to_string_pretty(&amend).unwrap());
-                    }
-                    println!("{}", vote_info.yay);
-                    println!("{}", vote_info.nay);
-                    println!("{}", vote_info.present);
-                    println!("{}", vote_info.absent);
-                    println!("{}", vote_info.tie_breaker_name);
-                    println!("{}", vote_info.tie_breaker_paired);
-                    println!("{:#?}", vote_info.members_as_is);
-                    println!("{:#?}", vote_info.members_to_gen);
-
-                    println!("{:#?}", vote_info);
-
-                    // Insert the vote metadata
-                    crate::sql_senate::insert_vote_metadata_senate(&tx, vote_metadata).await;
-                    // Insert the vote count data
-                    crate::sql_senate::insert_vote_count_senate(&tx, count_data).await;
-                    // Insert the member data
-                    crate::sql_senate::insert_vote_members_senate(&tx, member_data).await;
-
-                    
-                }
-                _ => {
-                    println!("❌ Invalid chamber in path: {}", path_str);
-                    pb_up.inc(1);
-                    continue;
-                }
-            }
+    Ok(())
+}
 
-            pb_up.inc(1);
-        }
+/// Reads one JSON or XML document from stdin and adds it to the workspace's database.
+async fn process_stdin_sql(workspace: &Workspace) -> Result<()> {
+    let workspace = workspace.clone();
 
-        // Commit the transaction
-        tx.commit()?;
-        Ok::<(), Error>(println!("📦 Database transaction committed successfully."))
-    };
+    let handle = tokio::task::spawn_blocking(move || {
+        let mut data = String::new();
+        std::io::Read::read_to_string(&mut std::io::stdin(), &mut data).map_err(|e| Error::io(std::path::Path::new("-"), e))?;
 
-    // Await the blocking task
-    match handle.await {
-        Ok(result) => result,
-        Err(e) => {
-            println!("❌ Task panicked: {}", e);
-            return Err(Error::InvalidQuery);
-        }
-    }
+        let mut conn = open_database(&workspace)?;
 
-    pb.finish_with_message("✅ Processing complete");
+        let id = insert_vote_document(&mut conn, &data)?;
+        println!("📦 Added {} from stdin.", id);
+        Ok::<(), Error>(())
+    });
 
-    Ok(())
+    handle.await.map_err(|e| Error::task("Database", e))?
 }
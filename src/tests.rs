@@ -1,24 +1,27 @@
-use crate::{endpoints::CongressEndpoint as Congress, CURRENT_CONGRESS, OLDEST_CONGRESS};
+use crate::{endpoints::CongressEndpoint as Congress, OLDEST_CONGRESS};
 
 #[test]
 fn test_congress() {
     let congress = Congress::new("house", 116, 2, 1);
-    assert_eq!(congress.to_year(), 2020);
+    assert_eq!(congress.to_year().unwrap(), 2020);
     assert_eq!(congress.to_string(), "https://clerk.house.gov/evs/2020/roll001.xml");
 
     let congress = Congress::new("senate", 116, 2, 1);
-    assert_eq!(congress.to_year(), 2020);
+    assert_eq!(congress.to_year().unwrap(), 2020);
     assert_eq!(congress.to_string(), "https://www.senate.gov/legislative/LIS/roll_call_votes/vote1162/vote_116_2_00001.xml");
 }
 
 #[test]
 fn test_congress_current() {
-    let congress = Congress::current("house");
-    assert_eq!(congress.to_year(), 2024);
+    use chrono::NaiveDate;
+
+    let date = NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+    let congress = Congress::for_date("house", date, 255);
+    assert_eq!(congress.to_year().unwrap(), 2024);
     assert_eq!(congress.to_string(), "https://clerk.house.gov/evs/2024/roll255.xml");
 
-    let congress = Congress::current("senate");
-    assert_eq!(congress.to_year(), 2024);
+    let congress = Congress::for_date("senate", date, 255);
+    assert_eq!(congress.to_year().unwrap(), 2024);
     assert_eq!(congress.to_string(), "https://www.senate.gov/legislative/LIS/roll_call_votes/vote1182/vote_118_2_00255.xml");
 }
 
@@ -33,7 +36,7 @@ fn test_congress_display() {
 
 #[test]
 fn test_all_congresses() {
-    let max_congress = CURRENT_CONGRESS;
+    let max_congress = crate::endpoints::latest_congress();
     let min_congress = OLDEST_CONGRESS;
     let current_roll_senate = 255;
     let current_roll_house = 455;
@@ -62,14 +65,1629 @@ fn test_all_congresses() {
 }
 
 
+#[test]
+fn test_congress_calendar() {
+    use crate::endpoints::congress_for_date;
+    use chrono::NaiveDate;
+
+    let year = |congress, session| Congress::new("house", congress, session, 1).to_year().ok();
+    assert_eq!(year(1, 1), Some(1789));
+    assert_eq!(year(1, 3), Some(1790));
+    assert_eq!(year(60, 2), Some(1908));
+    assert_eq!(year(67, 4), Some(1922));
+    assert_eq!(year(75, 2), Some(1937));
+    assert_eq!(year(118, 2), Some(2024));
+    assert_eq!(year(130, 1), Some(2047));
+    assert_eq!(year(118, 3), None);
+    assert_eq!(year(0, 1), None);
+
+    let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+    assert_eq!(congress_for_date(date(1789, 3, 4)), (1, 1));
+    assert_eq!(congress_for_date(date(1791, 2, 1)), (1, 3));
+    assert_eq!(congress_for_date(date(1908, 5, 1)), (60, 1));
+    assert_eq!(congress_for_date(date(1933, 3, 3)), (72, 2));
+    assert_eq!(congress_for_date(date(1933, 3, 5)), (73, 1));
+    assert_eq!(congress_for_date(date(1937, 12, 1)), (75, 2));
+    assert_eq!(congress_for_date(date(2023, 1, 2)), (117, 2));
+    assert_eq!(congress_for_date(date(2024, 1, 17)), (118, 2));
+
+    let mut endpoint = Congress::new("senate", 118, 2, 1);
+    assert!(endpoint.number(1).is_ok());
+    assert!(endpoint.session(3).is_ok());
+    assert!(endpoint.session(4).is_err());
+}
+
 use crate::client::RollCallClient as Client;
 
 #[tokio::test]
 async fn test_client() {
-    let client = Client::new(false);
-    let data = client.fetch_data(&Congress::current("house").to_string()).await.unwrap();
+    let client = Client::new(None).unwrap();
+    let latest = client.discover("house").await.unwrap();
+    let data = client.fetch_data(&latest.to_string()).await.unwrap();
     println!("{}", data);
-    assert_eq!(data.contains("rollcall-vote"), true);
+    assert!(data.contains("rollcall-vote"));
+}
+
+
+
+use crate::sqllite::{initialize_database, SCHEMA_VERSION};
+
+/// The House and Senate XML fixtures.
+const XML_FIXTURES: [&str; 2] = ["tests/fixtures/data/xml/118/house/2/2024_10.xml", "tests/fixtures/data/xml/118/senate/2/2024_1.xml"];
+
+/// Entries for vote files in the fixture layout.
+fn fixture_entries(paths: &[&str]) -> Vec<crate::pipeline::VoteFileEntry> {
+    paths.iter().map(|p| crate::pipeline::VoteFileEntry::from_path(std::path::Path::new(p)).unwrap()).collect()
+}
+
+/// A new in-memory database at the current schema holding the vote files in `paths`.
+fn fixture_db(paths: &[&str]) -> rusqlite::Connection {
+    let mut conn = rusqlite::Connection::open_in_memory().unwrap();
+    initialize_database(&conn).unwrap();
+    crate::sqllite::insert_vote_files(&mut conn, &fixture_entries(paths), 1, &indicatif::ProgressBar::hidden()).unwrap();
+    conn
+}
+
+/// A path under the system temp directory for one test, with anything a previous run left there removed.
+fn temp_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("congress_rolls_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+#[tokio::test]
+async fn test_initialize_database() {
+    let conn = rusqlite::Connection::open_in_memory().unwrap();
+    initialize_database(&conn).unwrap();
+    // applying the schema twice must be a no-op
+    initialize_database(&conn).unwrap();
+
+    let version: i32 = conn.query_row("PRAGMA user_version", [], |row| row.get(0)).unwrap();
+    assert_eq!(version, SCHEMA_VERSION);
+
+    for table in ["votes", "vote_counts", "party_totals", "documents", "amendments", "members", "member_votes", "candidate_totals"] {
+        let count: u32 = conn
+            .query_row("SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ?1", [table], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 1, "missing table {}", table);
+    }
+
+    let foreign_keys: bool = conn.query_row("PRAGMA foreign_keys", [], |row| row.get(0)).unwrap();
+    assert!(foreign_keys);
+}
+
+use crate::responses::SenateFile;
+
+#[tokio::test]
+async fn test_senate_insert_is_idempotent() {
+    let file: SenateFile = serde_json::from_str(include_str!("../tests/fixtures/data/json/118/senate/2/2024_1.json")).unwrap();
+    let id = crate::model::RollCallId::try_from(&file).unwrap();
+    let info = crate::sql_senate::get_senate_vote(&id, &file).unwrap();
+
+    let mut conn = fixture_db(&[]);
+
+    for _ in 0..2 {
+        let tx = conn.transaction().unwrap();
+        crate::sql_senate::insert_vote_metadata_senate(&tx, &info).unwrap();
+        crate::sql_senate::insert_vote_count_senate(&tx, &info).unwrap();
+        crate::sql_senate::insert_vote_members_senate(&tx, &info).unwrap();
+        tx.commit().unwrap();
+    }
+
+    let count = |sql: &str| -> u32 { conn.query_row(sql, [], |row| row.get(0)).unwrap() };
+    assert_eq!(count("SELECT COUNT(*) FROM votes"), 1);
+    assert_eq!(count("SELECT COUNT(*) FROM documents"), 1);
+    assert_eq!(count("SELECT COUNT(*) FROM amendments"), 0);
+    assert_eq!(count("SELECT COUNT(*) FROM members"), 4);
+    assert_eq!(count("SELECT COUNT(*) FROM member_votes"), 4);
+    assert_eq!(count("SELECT yea FROM vote_counts"), 2);
+    assert_eq!(count("SELECT not_voting FROM vote_counts"), 1);
+
+    let (question, tie_breaker): (String, Option<String>) = conn
+        .query_row("SELECT v.question, c.tie_breaker_by FROM votes v JOIN vote_counts c USING (vote_hash)", [], |row| Ok((row.get(0)?, row.get(1)?)))
+        .unwrap();
+    assert_eq!(question, "On the Cloture Motion");
+    assert_eq!(tie_breaker, None);
+}
+
+use crate::responses::HouseFile;
+
+#[tokio::test]
+async fn test_house_insert() {
+    // Text that reads "null" is kept; a missing field is NULL
+    let json = include_str!("../tests/fixtures/data/json/118/house/2/2024_10.json")
+        .replace("\"Agent Raul Gonzalez Officer Safety Act\"", "\"null\"")
+        .replace("\"majority\": \"R\",", "");
+    let file: HouseFile = serde_json::from_str(&json).unwrap();
+    let id = crate::model::RollCallId::try_from(&file).unwrap();
+    let info = crate::sql_house::get_house_vote(&id, &file).unwrap();
+
+    let mut conn = fixture_db(&[]);
+
+    for _ in 0..2 {
+        let tx = conn.transaction().unwrap();
+        crate::sql_house::insert_vote_metadata_house(&tx, &info).unwrap();
+        crate::sql_house::insert_vote_totals_house(&tx, &info).unwrap();
+        crate::sql_house::insert_vote_members_house(&tx, &info).unwrap();
+        tx.commit().unwrap();
+    }
+
+    let count = |sql: &str| -> u32 { conn.query_row(sql, [], |row| row.get(0)).unwrap() };
+    assert_eq!(count("SELECT COUNT(*) FROM votes WHERE chamber = 'house'"), 1);
+    assert_eq!(count("SELECT COUNT(*) FROM party_totals"), 3);
+    assert_eq!(count("SELECT nay FROM party_totals WHERE party = 'Democratic'"), 1);
+    assert_eq!(count("SELECT yea FROM vote_counts"), 3);
+    assert_eq!(count("SELECT COUNT(*) FROM members WHERE bioguide_id IS NOT NULL"), 5);
+    assert_eq!(count("SELECT COUNT(*) FROM member_votes WHERE vote_cast = 'Yea'"), 3);
+
+    let (legis_num, time_etz): (String, String) = conn
+        .query_row("SELECT legis_num, action_time_etz FROM votes", [], |row| Ok((row.get(0)?, row.get(1)?)))
+        .unwrap();
+    assert_eq!(legis_num, "H R 788");
+    assert_eq!(time_etz, "14:36");
+
+    let (vote_desc, majority): (Option<String>, Option<String>) = conn
+        .query_row("SELECT vote_desc, majority FROM votes", [], |row| Ok((row.get(0)?, row.get(1)?)))
+        .unwrap();
+    assert_eq!((vote_desc.as_deref(), majority), (Some("null"), None));
+}
+
+use crate::xml_reader::{parse_house_xml, parse_senate_xml, parse_vote_file_xml};
+use crate::responses::VoteFile;
+
+#[test]
+fn test_xml_matches_yq_json() {
+    let house_xml = parse_house_xml(include_str!("../tests/fixtures/data/xml/118/house/2/2024_10.xml")).unwrap();
+    let house_json: HouseFile = serde_json::from_str(include_str!("../tests/fixtures/data/json/118/house/2/2024_10.json")).unwrap();
+    assert_eq!(
+        serde_json::to_value(&house_xml.rollcall_vote).unwrap(),
+        serde_json::to_value(&house_json.rollcall_vote).unwrap()
+    );
+
+    let senate_xml = parse_senate_xml(include_str!("../tests/fixtures/data/xml/118/senate/2/2024_1.xml")).unwrap();
+    let senate_json: SenateFile = serde_json::from_str(include_str!("../tests/fixtures/data/json/118/senate/2/2024_1.json")).unwrap();
+    assert_eq!(
+        serde_json::to_value(&senate_xml.roll_call_vote).unwrap(),
+        serde_json::to_value(&senate_json.roll_call_vote).unwrap()
+    );
+}
+
+#[test]
+fn test_xml_single_member_stays_a_list() {
+    let xml = "<roll_call_vote><congress>101</congress><session>1</session>\
+        <vote_date/><vote_question_text/><vote_document_text/><vote_result_text/><question/>\
+        <members><member><last_name>Dole</last_name></member></members></roll_call_vote>";
+    match parse_vote_file_xml(xml).unwrap() {
+        VoteFile::SenateFile(file) => {
+            let members = file.roll_call_vote.members.unwrap().member.unwrap();
+            assert_eq!(members.len(), 1);
+            assert_eq!(members[0].last_name.as_deref(), Some("Dole"));
+        }
+        VoteFile::HouseFile(_) => panic!("parsed a Senate vote as House"),
+    }
+
+    // Documents and amendments are lists whether there are one, several or none
+    let xml = "<roll_call_vote><congress>101</congress><session>1</session>\
+        <vote_date/><vote_question_text/><vote_document_text/><vote_result_text/><question/>\
+        <document><document_congress>101</document_congress><document_type/><document_number/><document_name>S. 1</document_name><document_title/></document>\
+        <document><document_congress>101</document_congress><document_type/><document_number/><document_name>S. 2</document_name><document_title/></document>\
+        <amendment/></roll_call_vote>";
+    match parse_vote_file_xml(xml).unwrap() {
+        VoteFile::SenateFile(file) => {
+            let names: Vec<_> = file.roll_call_vote.document.iter().map(|d| d.document_name.to_string()).collect();
+            assert_eq!(names, vec!["S. 1", "S. 2"]);
+            assert!(file.roll_call_vote.amendment.is_empty());
+        }
+        VoteFile::HouseFile(_) => panic!("parsed a Senate vote as House"),
+    }
+    let json: SenateFile = serde_json::from_str(include_str!("../tests/fixtures/data/json/118/senate/2/2024_1.json")).unwrap();
+    assert_eq!(json.roll_call_vote.document.len(), 1);
+    assert_eq!(json.roll_call_vote.amendment.len(), 1);
+
+    assert!(parse_vote_file_xml("<html></html>").is_err());
+}
+
+use crate::model::{Party, RollCall, State, VoteCast};
+
+#[test]
+fn test_model_from_house_and_senate() {
+    let house: HouseFile = serde_json::from_str(include_str!("../tests/fixtures/data/json/118/house/2/2024_10.json")).unwrap();
+    let roll = RollCall::try_from(&house).unwrap();
+    assert_eq!((roll.congress, roll.session, roll.number), (118, 2, 10));
+    assert_eq!(roll.date, chrono::NaiveDate::from_ymd_opt(2024, 1, 17));
+    assert_eq!(roll.time, chrono::NaiveTime::from_hms_opt(14, 36, 0));
+    assert_eq!(roll.bill.as_deref(), Some("H R 788"));
+    assert_eq!(roll.ballots.len(), 5);
+    assert_eq!(roll.ballots[0].member.id(), Some("A000370"));
+    assert_eq!(roll.ballots[0].member.party, Some(Party::Democrat));
+    assert_eq!(roll.ballots[0].member.state, Some(State::NorthCarolina));
+    assert_eq!(roll.ballots[4].cast, Some(VoteCast::NotVoting));
+
+    let senate: SenateFile = serde_json::from_str(include_str!("../tests/fixtures/data/json/118/senate/2/2024_1.json")).unwrap();
+    let roll = RollCall::try_from(&senate).unwrap();
+    assert_eq!((roll.congress, roll.session, roll.number), (118, 2, 1));
+    assert_eq!(roll.modified, chrono::NaiveDate::from_ymd_opt(2024, 1, 9).and_then(|d| d.and_hms_opt(18, 2, 0)));
+    assert_eq!(roll.bill.as_deref(), Some("PN1042"));
+    assert_eq!(roll.amendment, None);
+    assert_eq!(roll.ballots[1].member.id(), Some("S317"));
+    assert_eq!(roll.ballots[1].cast, Some(VoteCast::Nay));
+}
+
+#[test]
+fn test_vote_cast_vocabulary() {
+    let cast = |s: &str| s.parse::<VoteCast>().ok();
+    assert_eq!(cast("Aye"), Some(VoteCast::Yea));
+    assert_eq!(cast("No"), Some(VoteCast::Nay));
+    assert_eq!(cast("Present, Giving Live Pair"), Some(VoteCast::Present));
+    assert_eq!(cast("Not Guilty"), Some(VoteCast::NotGuilty));
+    assert_eq!(cast("Jeffries"), Some(VoteCast::Candidate("Jeffries".to_string())));
+    // A blank ballot records no vote, which is not the same as "Not Voting"
+    assert_eq!(cast(" "), None);
+    assert_eq!(serde_json::to_string(&VoteCast::NotVoting).unwrap(), "\"Not Voting\"");
+    assert!(serde_json::from_str::<VoteCast>("\"\"").is_err());
+    assert_eq!(Party::from("Republican"), Party::from("R"));
+    assert!("ZZ".parse::<State>().is_err());
+}
+
+#[tokio::test]
+async fn test_malformed_count_is_an_error() {
+    let json = include_str!("../tests/fixtures/data/json/118/senate/2/2024_1.json").replace("\"yeas\": \"2\"", "\"yeas\": \"two\"");
+    let file: SenateFile = serde_json::from_str(&json).unwrap();
+    let id = crate::model::RollCallId::try_from(&file).unwrap();
+
+    let err = crate::sql_senate::get_senate_vote(&id, &file).unwrap_err();
+    match &err {
+        crate::Error::Parse { field, value, roll_call, .. } => {
+            assert_eq!(field, "count.yeas");
+            assert_eq!(value, "two");
+            assert_eq!(roll_call.map(|rc| rc.roll_call), Some(1));
+        }
+        other => panic!("expected a parse error, got {:?}", other),
+    }
+    assert_eq!(err.to_string(), "Invalid count.yeas \"two\" (senate 118-2 #1)");
+
+    let json = include_str!("../tests/fixtures/data/json/118/house/2/2024_10.json").replace("\"yea-total\": \"3\"", "\"yea-total\": \"3x\"");
+    let file: HouseFile = serde_json::from_str(&json).unwrap();
+    let id = crate::model::RollCallId::try_from(&file).unwrap();
+
+    let err = crate::sql_house::get_house_vote(&id, &file).unwrap_err();
+    assert_eq!(err.to_string(), "Invalid totals-by-vote.yea-total \"3x\" (house 118-2 #10)");
+
+    assert!(matches!(Congress::new("house", 99, 3, 1).to_year(), Err(crate::Error::Congress { congress: 99, session: 3 })));
+    assert!(crate::xml_reader::load_house_file(std::path::Path::new("missing/2024_1.xml")).is_err());
+}
+
+#[test]
+fn test_discovery_parsers() {
+    use crate::client::{parse_house_index, parse_house_roll_page, parse_senate_menu};
+
+    let index = include_str!("../tests/fixtures/discovery/house_index_2024.html");
+    assert_eq!(parse_house_index(index).as_deref(), Some("ROLL_400.asp"));
+    assert_eq!(parse_house_roll_page(include_str!("../tests/fixtures/discovery/house_roll_400_2024.html")), Some(455));
+    assert_eq!(parse_house_roll_page("<HTML></HTML>"), None);
+
+    assert_eq!(parse_senate_menu(include_str!("../tests/fixtures/discovery/vote_menu_118_2.xml")).unwrap(), Some(255));
+    assert_eq!(parse_senate_menu(include_str!("../tests/fixtures/discovery/vote_menu_119_1_single.xml")).unwrap(), Some(1));
+    assert_eq!(parse_senate_menu("<vote_summary><votes/></vote_summary>").unwrap(), None);
+
+    assert_eq!(Congress::new("house", 118, 2, 0).index_url().unwrap(), "https://clerk.house.gov/evs/2024/index.asp");
+    assert_eq!(
+        Congress::new("senate", 118, 2, 0).index_url().unwrap(),
+        "https://www.senate.gov/legislative/LIS/roll_call_lists/vote_menu_118_2.xml"
+    );
+}
+
+/// Serves roll calls on 127.0.0.1 for the downloader tests and counts requests per path.
+/// `/vote/N` answers with a roll call, `/flaky` fails with 503 once, `/blocked` answers
+/// with an HTML page, `/etag` honours `If-None-Match`, `/revised` changes on every
+/// request and anything else is a 404.
+async fn mock_server() -> (String, std::sync::Arc<std::sync::Mutex<std::collections::HashMap<String, usize>>>) {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    let hits = std::sync::Arc::new(std::sync::Mutex::new(std::collections::HashMap::new()));
+
+    let counter = hits.clone();
+    tokio::spawn(async move {
+        loop {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let counter = counter.clone();
+            tokio::spawn(async move {
+                let mut buf = vec![0u8; 4096];
+                let n = socket.read(&mut buf).await.unwrap();
+                let request = String::from_utf8_lossy(&buf[..n]).to_string();
+                let path = request.split_whitespace().nth(1).unwrap_or("/").to_string();
+
+                let seen = {
+                    let mut hits = counter.lock().unwrap();
+                    let seen = hits.entry(path.clone()).or_insert(0);
+                    *seen += 1;
+                    *seen
+                };
+
+                let mut headers = String::new();
+                let (status, body) = match path.as_str() {
+                    p if p.starts_with("/vote/") => ("200 OK", format!("<roll_call_vote><vote_number>{}</vote_number></roll_call_vote>", &p[6..])),
+                    "/flaky" if seen == 1 => ("503 Service Unavailable", String::new()),
+                    "/flaky" => ("200 OK", "<rollcall-vote></rollcall-vote>".to_string()),
+                    "/blocked" => ("200 OK", "<html>Please contact the Webmaster</html>".to_string()),
+                    "/etag" if request.to_lowercase().contains("if-none-match: \"v1\"") => ("304 Not Modified", String::new()),
+                    "/etag" => {
+                        headers.push_str("ETag: \"v1\"\r\n");
+                        ("200 OK", "<roll_call_vote></roll_call_vote>".to_string())
+                    }
+                    "/revised" => ("200 OK", format!("<roll_call_vote><modify_date>{}</modify_date></roll_call_vote>", seen)),
+                    _ => ("404 Not Found", String::new()),
+                };
+
+                let response = format!("HTTP/1.1 {}\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n{}", status, headers, body.len(), body);
+                socket.write_all(response.as_bytes()).await.unwrap();
+            });
+        }
+    });
+
+    (base, hits)
+}
+
+#[tokio::test]
+async fn test_downloader() {
+    use crate::client::{DownloadJob, Downloader};
+    use std::time::Duration;
+
+    let (base, hits) = mock_server().await;
+    let dir = temp_dir("download");
+
+    let job = |url: &str, file: &str| DownloadJob { url: format!("{}{}", base, url), path: dir.join(file) };
+    let jobs = vec![
+        job("/vote/1", "2024_1.xml"),
+        job("/vote/2", "2024_2.xml"),
+        job("/vote/3", "2024_3.xml"),
+        job("/flaky", "2024_4.xml"),
+        job("/missing", "2024_5.xml"),
+        job("/blocked", "2024_6.xml"),
+    ];
+
+    // An earlier, interrupted run already fetched roll call 3
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("2024_3.xml"), "<roll_call_vote/>").unwrap();
+
+    let started = std::time::Instant::now();
+    let report = Downloader::new(Client::new(None).unwrap())
+        .concurrency(2)
+        .rate_limit(Duration::from_millis(40))
+        .retries(2, Duration::from_millis(10))
+        .progress(false)
+        .run(jobs)
+        .await
+        .unwrap();
+
+    assert_eq!(report.downloaded, 3);
+    assert_eq!(report.skipped, 1);
+    assert_eq!(report.failed.len(), 2);
+    assert!(report.failed.iter().any(|(_, e)| matches!(e, crate::Error::Network { status: Some(404), .. })));
+    assert!(report.failed.iter().any(|(_, e)| matches!(e, crate::Error::Schema { .. })));
+
+    // 6 requests to one host, at least 40ms apart
+    assert!(started.elapsed() >= Duration::from_millis(200));
+
+    let hits = hits.lock().unwrap();
+    assert_eq!(hits.get("/flaky"), Some(&2));
+    assert_eq!(hits.get("/missing"), Some(&1));
+    assert_eq!(hits.get("/vote/3"), None);
+
+    assert!(std::fs::read_to_string(dir.join("2024_2.xml")).unwrap().contains("<vote_number>2</vote_number>"));
+    assert!(!dir.join("2024_5.xml").exists());
+    assert!(!dir.join("2024_4.xml.part").exists());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_downloader_refresh() {
+    use crate::client::{DownloadJob, Downloader, Manifest};
+    use std::time::Duration;
+
+    let (base, hits) = mock_server().await;
+    let dir = temp_dir("refresh");
+
+    let job = |url: &str, file: &str| DownloadJob { url: format!("{}{}", base, url), path: dir.join(file) };
+    let jobs = vec![job("/etag", "2024_1.xml"), job("/revised", "2024_2.xml"), job("/vote/3", "2024_3.xml")];
+
+    let downloader = |refresh| {
+        Downloader::new(Client::new(None).unwrap())
+            .rate_limit(Duration::ZERO)
+            .progress(false)
+            .refresh(refresh)
+            .manifest(dir.join("manifest.json"))
+            .changelog(dir.join("changelog.log"))
+    };
+
+    let report = downloader(false).run(jobs.clone()).await.unwrap();
+    assert_eq!(report.downloaded, 3);
+
+    let manifest = Manifest::load(&dir.join("manifest.json")).unwrap();
+    assert!(!dir.join("manifest.json.tmp").exists());
+    let etag = &manifest.files[&dir.join("2024_1.xml").display().to_string()];
+    assert_eq!(etag.validators.etag.as_deref(), Some("\"v1\""));
+
+    // Without refresh nothing is requested again
+    let report = downloader(false).run(jobs.clone()).await.unwrap();
+    assert_eq!(report.skipped, 3);
+
+    let report = downloader(true).run(jobs).await.unwrap();
+    assert_eq!(report.downloaded, 0);
+    assert_eq!(report.unchanged, 2);
+    assert_eq!(report.changed.len(), 1);
+    assert_eq!(report.changed[0].path, dir.join("2024_2.xml"));
+    assert_ne!(report.changed[0].old_hash, report.changed[0].new_hash);
+
+    assert_eq!(hits.lock().unwrap().get("/etag"), Some(&2));
+    assert!(std::fs::read_to_string(dir.join("2024_2.xml")).unwrap().contains("<modify_date>2</modify_date>"));
+
+    let changelog = std::fs::read_to_string(dir.join("changelog.log")).unwrap();
+    assert_eq!(changelog.lines().count(), 1);
+    assert!(changelog.contains("2024_2.xml"));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_json_stream_writer() {
+    use crate::json_master::JsonStreamWriter;
+    use crate::pipeline::VoteFileEntry;
+    use crate::responses::RollCallData;
+
+    let rollcall = |n| RollCallData { rollcall_number: n, vote_question: Some("On Passage".to_string()), ..Default::default() };
+
+    let mut writer = JsonStreamWriter::new(Vec::new()).unwrap();
+    writer.write_rollcall("house", 118, 1, &rollcall(1)).unwrap();
+    writer.write_rollcall("house", 118, 2, &rollcall(1)).unwrap();
+    writer.write_rollcall("house", 118, 2, &rollcall(2)).unwrap();
+    writer.write_rollcall("house", 119, 1, &rollcall(1)).unwrap();
+    writer.write_rollcall("senate", 118, 2, &rollcall(7)).unwrap();
+    assert!(writer.write_rollcall("house", 118, 1, &rollcall(3)).is_err());
+    let json = writer.finish().unwrap();
+
+    let data: serde_json::Value = serde_json::from_slice(&json).unwrap();
+    let house = &data["chambers"]["house"]["congresses"];
+    assert_eq!(house["118"]["sessions"]["2"]["rollcalls"].as_array().unwrap().len(), 2);
+    assert_eq!(house["119"]["sessions"]["1"]["rollcalls"][0]["rollcall_number"], 1);
+    assert_eq!(data["chambers"]["senate"]["congresses"]["118"]["sessions"]["2"]["rollcalls"][0]["rollcall_number"], 7);
+
+    let empty: serde_json::Value = serde_json::from_slice(&JsonStreamWriter::new(Vec::new()).unwrap().finish().unwrap()).unwrap();
+    assert_eq!(empty, serde_json::json!({ "chambers": {} }));
+
+    // Roll call 10 sorts after roll call 9, and the house before the senate
+    let entry = |p: &str| VoteFileEntry::from_path(std::path::Path::new(p)).unwrap();
+    let mut entries = [
+        entry("data/xml/118/senate/1/2023_1.xml"),
+        entry("data/xml/118/house/2/2024_10.xml"),
+        entry("data/xml/118/house/2/2024_9.xml"),
+    ];
+    entries.sort();
+    let order: Vec<_> = entries.iter().map(|e| (e.chamber.as_str(), e.rollcall_number)).collect();
+    assert_eq!(order, vec![("house", 9), ("house", 10), ("senate", 1)]);
+    assert!(VoteFileEntry::from_path(std::path::Path::new("data/xml/house/2/2024_9.xml")).is_err());
+}
+
+#[test]
+fn test_combined_data_is_compact() {
+    use crate::json_master::JsonStreamWriter;
+    use crate::responses::{CombinedData, UnifiedVote};
+
+    let ballot = |roll: u32, id: &str, cast: &str| UnifiedVote {
+        congress_number: 118,
+        chamber: "House".to_string(),
+        session_number: 2,
+        rollcall_number: roll,
+        vote_date: Some(format!("{}-Jan-2024", roll)),
+        vote_day: Some(format!("2024-01-{:02}", roll)),
+        vote_timestamp: None,
+        vote_question: Some("On Passage".to_string()),
+        vote_result: Some("Passed".to_string()),
+        legislator_id: Some(id.to_string()),
+        legislator_name: Some(id.to_lowercase()),
+        party: Some("D".to_string()),
+        state: None,
+        vote_cast: Some(cast.to_string()),
+    };
+
+    let mut data = CombinedData::default();
+    for roll in [10, 9] {
+        data.insert(&ballot(roll, "A000370", "Yea"));
+        data.insert(&ballot(roll, "B001234", "Nay"));
+    }
+
+    assert_eq!(data.members.len(), 2);
+    assert_eq!(data.ballot_count(), 4);
+    // "On Passage", "Passed", "D", "Yea" and "Nay" are stored once, and the missing state not at all
+    assert_eq!(data.strings.len(), 13);
+
+    let rolls: Vec<u32> = data.rollcalls().map(|(_, _, _, roll, _)| roll).collect();
+    assert_eq!(rolls, vec![9, 10]);
+
+    let (chamber, congress, session, roll, rollcall) = data.rollcalls().next().unwrap();
+    let expanded = data.expand(chamber, congress, session, roll, rollcall);
+    assert_eq!(expanded.vote_date.as_deref(), Some("9-Jan-2024"));
+    assert_eq!(expanded.vote_day.as_deref(), Some("2024-01-09"));
+    assert_eq!(expanded.vote_casts[0].vote_timestamp, None);
+    assert_eq!(expanded.vote_casts[1].legislator_name.as_deref(), Some("b001234"));
+    assert_eq!(expanded.vote_casts[1].state, None);
+    assert_eq!(expanded.vote_casts[1].chamber, "house");
+
+    // Serializing the struct gives the same document as streaming it
+    let mut writer = JsonStreamWriter::new(Vec::new()).unwrap();
+    for (chamber, congress, session, roll, rollcall) in data.rollcalls() {
+        writer.write_rollcall(chamber, congress, session, &data.expand(chamber, congress, session, roll, rollcall)).unwrap();
+    }
+    let streamed: serde_json::Value = serde_json::from_slice(&writer.finish().unwrap()).unwrap();
+    assert_eq!(serde_json::to_value(&data).unwrap(), streamed);
+
+    // And reads back into the same compact data
+    let json = serde_json::to_string(&data).unwrap();
+    let read: CombinedData = serde_json::from_str(&json).unwrap();
+    assert_eq!((read.members.len(), read.ballot_count(), read.strings.len()), (2, 4, 13));
+    assert_eq!(serde_json::to_string(&read).unwrap(), json);
+
+    let chamber: crate::responses::ChamberJson = serde_json::from_value(streamed["chambers"]["house"].clone()).unwrap();
+    assert_eq!(chamber.congresses[&118].sessions[&2].rollcalls.len(), 2);
+}
+
+#[test]
+fn test_parse_in_order() {
+    use crate::pipeline::parse_in_order;
+
+    // Later items finish first; they are still consumed in input order
+    let items: Vec<u64> = (0..16).collect();
+    let mut seen = vec![];
+    parse_in_order(&items, 4, |n| {
+        std::thread::sleep(std::time::Duration::from_millis(16 - n));
+        n * 2
+    }, |item, doubled| {
+        assert_eq!(*item * 2, doubled);
+        seen.push(*item);
+        Ok(())
+    })
+    .unwrap();
+    assert_eq!(seen, items);
+
+    // An error from the consumer stops the pipeline
+    let mut consumed = 0;
+    let result = parse_in_order(&items, 4, |n| *n, |_, n| {
+        consumed += 1;
+        if n == 3 { Err(crate::Error::schema("stop")) } else { Ok(()) }
+    });
+    assert!(result.is_err());
+    assert_eq!(consumed, 4);
+
+    // A slow first item holds the workers within 4 per worker of the consumer
+    let items: Vec<u64> = (0..64).collect();
+    let done = std::sync::atomic::AtomicUsize::new(0);
+    let ahead = std::sync::atomic::AtomicUsize::new(0);
+    parse_in_order(&items, 2, |n| {
+        if *n == 0 {
+            std::thread::sleep(std::time::Duration::from_millis(50));
+        }
+        let lead = *n as usize - done.load(std::sync::atomic::Ordering::SeqCst);
+        ahead.fetch_max(lead, std::sync::atomic::Ordering::SeqCst);
+    }, |_, _| {
+        done.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        Ok(())
+    })
+    .unwrap();
+    assert_eq!(done.into_inner(), 64);
+    assert!(ahead.into_inner() < 8);
+}
+
+#[test]
+fn test_parallel_sql_matches_serial() {
+    use crate::sqllite::insert_vote_files;
+
+    let files = fixture_entries(&[
+        "tests/fixtures/data/json/118/house/2/2024_10.json",
+        "tests/fixtures/data/json/118/senate/2/2024_1.json",
+        XML_FIXTURES[0],
+        XML_FIXTURES[1],
+    ]);
+
+    let dump = |workers| {
+        let mut conn = fixture_db(&[]);
+        let report = insert_vote_files(&mut conn, &files, workers, &indicatif::ProgressBar::hidden()).unwrap();
+        assert_eq!((report.written, report.mismatched.len()), (4, 0));
+
+        // Both formats of a roll call hash the same, so each is stored once
+        let votes: u32 = conn.query_row("SELECT COUNT(*) FROM votes", [], |row| row.get(0)).unwrap();
+        assert_eq!(votes, 2);
+
+        let mut rows = vec![];
+        for sql in [
+            "SELECT vote_hash, chamber, rollcall, question FROM votes ORDER BY vote_hash",
+            "SELECT vote_hash, member_id, vote_cast FROM member_votes ORDER BY vote_hash, member_id",
+            "SELECT member_id, full_name, party FROM members ORDER BY member_id",
+        ] {
+            let mut stmt = conn.prepare(sql).unwrap();
+            let columns = stmt.column_count();
+            let mut query = stmt.query([]).unwrap();
+            while let Some(row) = query.next().unwrap() {
+                let values: Vec<String> = (0..columns)
+                    .map(|i| format!("{:?}", row.get_ref(i).unwrap()))
+                    .collect();
+                rows.push(values.join("|"));
+            }
+        }
+        rows
+    };
+
+    assert_eq!(dump(1), dump(4));
+}
+
+#[test]
+fn test_workspace() {
+    use crate::endpoints::CongressEndpoint;
+    use crate::pipeline::collect_vote_files;
+    use crate::workspace::Workspace;
+    use crate::xml_reader::VoteFormat;
+    use std::path::Path;
+
+    let out = temp_dir("workspace");
+
+    // Data is read under the root, outputs can live anywhere
+    let workspace = Workspace::new("tests/fixtures").master_json(out.join("nested/votes.json"));
+    assert_eq!(workspace.data_dir(VoteFormat::Xml), Path::new("tests/fixtures/data/xml"));
+    assert_eq!(workspace.database_path(), Path::new("tests/fixtures/full_data/votes.db"));
+    assert_eq!(workspace.manifest_path(), Path::new("tests/fixtures/data/xml/manifest.json"));
+
+    let files = collect_vote_files(&workspace, VoteFormat::Xml);
+    let rolls: Vec<_> = files.iter().map(|e| (e.chamber.as_str(), e.year, e.rollcall_number)).collect();
+    assert_eq!(rolls, vec![("house", 2024, 10), ("senate", 2024, 1)]);
+
+    crate::json_master::write_vote_files_json(&workspace, VoteFormat::Xml, 2).unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&std::fs::read(workspace.master_json_path()).unwrap()).unwrap();
+    assert_eq!(json["chambers"]["senate"]["congresses"]["118"]["sessions"]["2"]["rollcalls"][0]["rollcall_number"], 1);
+
+    // Paths render and parse back through the layout
+    let endpoint = CongressEndpoint::new("senate", 118, 2, 7);
+    let path = workspace.vote_file_path(VoteFormat::Json, &endpoint).unwrap();
+    assert_eq!(path, Path::new("tests/fixtures/data/json/118/senate/2/2024_7.json"));
+    let entry = workspace.parse_vote_path(&path).unwrap();
+    assert_eq!((entry.chamber.as_str(), entry.congress_number, entry.session_number, entry.year, entry.rollcall_number), ("senate", 118, 2, 2024, 7));
+
+    // Layouts without a year take the year the session convened
+    let flat = Workspace::new(&out).layout("{chamber}-{congress}-{session}/roll{roll}.{ext}").unwrap();
+    let path = flat.vote_file_path(VoteFormat::Xml, &CongressEndpoint::new("house", 101, 1, 12)).unwrap();
+    assert_eq!(path, out.join("data/xml/house-101-1/roll12.xml"));
+    let entry = flat.parse_vote_path(&path).unwrap();
+    assert_eq!((entry.chamber.as_str(), entry.congress_number, entry.session_number, entry.year, entry.rollcall_number), ("house", 101, 1, 1989, 12));
+    assert!(flat.parse_vote_path(Path::new("data/xml/house-101-1/12.xml")).is_err());
+    assert!(Workspace::default().layout("{congress}/{year}_{roll}.{ext}").is_err());
+
+    std::fs::remove_dir_all(&out).unwrap();
+}
+
+#[test]
+fn test_identity_from_document() {
+    use crate::endpoints::CongressChamber;
+    use crate::model::RollCallId;
+    use crate::pipeline::VoteFileEntry;
+    use crate::sqllite::{insert_vote_document, parse_vote_file, ParsedVote};
+    use crate::xml_reader::{load_vote_file, VoteFormat};
+
+    let dir = temp_dir("identity");
+
+    let house = RollCallId { chamber: CongressChamber::House, congress: 118, session: 2, number: 10, year: 2024 };
+    let senate = RollCallId { chamber: CongressChamber::Senate, congress: 118, session: 2, number: 1, year: 2024 };
+    for (path, id) in [
+        ("tests/fixtures/data/xml/118/house/2/2024_10.xml", house),
+        ("tests/fixtures/data/json/118/house/2/2024_10.json", house),
+        ("tests/fixtures/data/xml/118/senate/2/2024_1.xml", senate),
+        ("tests/fixtures/data/json/118/senate/2/2024_1.json", senate),
+    ] {
+        let file = load_vote_file(std::path::Path::new(path)).unwrap();
+        assert_eq!(RollCallId::try_from(&file).unwrap(), id);
+    }
+
+    // A file filed under the wrong roll call is stored under the one it records
+    let misplaced = dir.join("118/house/2/2024_11.xml");
+    std::fs::create_dir_all(misplaced.parent().unwrap()).unwrap();
+    std::fs::copy("tests/fixtures/data/xml/118/house/2/2024_10.xml", &misplaced).unwrap();
+    let entry = VoteFileEntry::from_path(&misplaced).unwrap();
+    assert_eq!(entry.rollcall_number, 11);
+    let (parsed, mismatch) = parse_vote_file(&entry).unwrap();
+    match parsed {
+        ParsedVote::House(info) => assert_eq!(info.id.number, 10),
+        ParsedVote::Senate(_) => panic!("expected a House roll call"),
+    }
+    let mismatch = mismatch.expect("the path names roll call 11");
+    assert_eq!((mismatch.entry.rollcall_number, mismatch.document), (11, house));
+    let err = crate::Error::from(mismatch);
+    assert!(matches!(&err, crate::Error::Schema { path: Some(p), .. } if *p == misplaced), "{:?}", err);
+
+    // The master JSON file places it by its document too
+    let (id, votes, mismatch) = crate::json_master::read_unified_votes(&entry).unwrap();
+    assert_eq!(id, house);
+    assert!(mismatch.is_some());
+    assert!(votes.iter().all(|v| v.rollcall_number == 10));
+    let workspace = crate::workspace::Workspace::new(&dir).data_dir_for(VoteFormat::Xml, &dir);
+    crate::json_master::write_vote_files_json(&workspace, VoteFormat::Xml, 1).unwrap();
+    let master: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(workspace.master_json_path()).unwrap()).unwrap();
+    assert_eq!(master["chambers"]["house"]["congresses"]["118"]["sessions"]["2"]["rollcalls"][0]["rollcall_number"], 10);
+
+    // Files outside the layout, or without a file name, are placed by their content
+    let loose = dir.join("download.xml");
+    std::fs::copy("tests/fixtures/data/xml/118/senate/2/2024_1.xml", &loose).unwrap();
+    assert!(VoteFileEntry::from_path(&loose).is_err());
+    assert_eq!(VoteFileEntry::from_document(&loose).unwrap(), VoteFileEntry::from_id(&senate, &loose));
+
+    let xml = std::fs::read_to_string("tests/fixtures/data/xml/118/house/2/2024_10.xml").unwrap();
+    let json = std::fs::read_to_string("tests/fixtures/data/json/118/senate/2/2024_1.json").unwrap();
+    assert_eq!(VoteFormat::detect(&xml), VoteFormat::Xml);
+    assert_eq!(VoteFormat::detect(&json), VoteFormat::Json);
+
+    let mut conn = fixture_db(&[]);
+    assert_eq!(insert_vote_document(&mut conn, &xml).unwrap(), house);
+    assert_eq!(insert_vote_document(&mut conn, &json).unwrap(), senate);
+    let rolls: Vec<(String, u32)> = conn
+        .prepare("SELECT chamber, rollcall FROM votes ORDER BY chamber")
+        .unwrap()
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+        .unwrap()
+        .collect::<rusqlite::Result<_>>()
+        .unwrap();
+    assert_eq!(rolls, vec![("house".to_string(), 10), ("senate".to_string(), 1)]);
+    assert!(insert_vote_document(&mut conn, "<html></html>").is_err());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_misfiled_master_json() {
+    use crate::xml_reader::VoteFormat;
+
+    let dir = temp_dir("misfiled");
+
+    let house = include_str!("../tests/fixtures/data/json/118/house/2/2024_10.json");
+    let senate = include_str!("../tests/fixtures/data/json/118/senate/2/2024_1.json");
+    let files = [
+        // A second copy of house 118-2 #10, filed under session 1
+        ("118/house/1/2023_10.json", house.to_string()),
+        // House 118-1 #3, filed under session 2: held back until session 2 is reached
+        ("118/house/2/2024_3.json", house.replace("\"2nd\"", "\"1st\"").replace("\"rollcall-num\": \"10\"", "\"rollcall-num\": \"3\"")),
+        ("118/house/2/2024_10.json", house.to_string()),
+        ("118/senate/2/2024_1.json", senate.to_string()),
+        // Senate 118-1 #1, filed after senate session 2 was written
+        ("118/senate/2/2024_2.json", senate.replace("\"session\": \"2\"", "\"session\": \"1\"")),
+    ];
+    for (path, json) in &files {
+        let path = dir.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, json).unwrap();
+    }
+
+    let workspace = crate::workspace::Workspace::new(&dir).data_dir_for(VoteFormat::Json, &dir);
+    crate::json_master::write_vote_files_json(&workspace, VoteFormat::Json, 2).unwrap();
+
+    let master: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(workspace.master_json_path()).unwrap()).unwrap();
+    let rolls = |chamber: &str, session: &str| -> Vec<u64> {
+        master["chambers"][chamber]["congresses"]["118"]["sessions"][session]["rollcalls"]
+            .as_array()
+            .map(|r| r.iter().map(|rc| rc["rollcall_number"].as_u64().unwrap()).collect())
+            .unwrap_or_default()
+    };
+    assert_eq!(rolls("house", "1"), vec![3]);
+    assert_eq!(rolls("house", "2"), vec![10]);
+    assert_eq!(rolls("senate", "2"), vec![1]);
+    assert_eq!(rolls("senate", "1"), Vec::<u64>::new());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_legislator_registry() {
+    use crate::legislators::{insert_legislators, Registry};
+    use crate::model::RollCall;
+    use crate::xml_reader::load_vote_file;
+
+    let registry = Registry::load(std::path::Path::new("tests/fixtures/legislators.yaml")).unwrap();
+    assert_eq!(registry.len(), 3);
+    assert_eq!(registry.by_lis("S354").unwrap().id.bioguide, "B001230");
+    assert!(registry.by_lis("S317").is_none());
+
+    // A replaced legislator whose LIS id changed is no longer found under the old one
+    let mut renumbered = registry.clone();
+    let mut baldwin = registry.by_lis("S354").unwrap().clone();
+    baldwin.id.lis = Some("S999".to_string());
+    renumbered.extend([baldwin]);
+    assert_eq!(renumbered.len(), 3);
+    assert!(renumbered.by_lis("S354").is_none());
+    assert_eq!(renumbered.by_lis("S999").unwrap().id.bioguide, "B001230");
+
+    // Senate ballots carry LIS ids, House ballots bioguide ids
+    let senate = load_vote_file(std::path::Path::new("tests/fixtures/data/xml/118/senate/2/2024_1.xml")).unwrap();
+    let rollcall = RollCall::try_from(&senate).unwrap();
+    let resolved: Vec<_> = rollcall.ballots.iter().filter_map(|b| registry.resolve(&b.member)).map(|l| l.id.bioguide.as_str()).collect();
+    assert_eq!(resolved, vec!["B001230", "B001243"]);
+
+    let mut conn = fixture_db(&XML_FIXTURES);
+
+    let tx = conn.transaction().unwrap();
+    assert_eq!(insert_legislators(&tx, &registry).unwrap(), 3);
+    // Importing again replaces rather than duplicates
+    insert_legislators(&tx, &registry).unwrap();
+    tx.commit().unwrap();
+
+    let senate_ids: Vec<String> = conn
+        .prepare("SELECT member_id FROM members WHERE chamber = 'senate' ORDER BY member_id")
+        .unwrap()
+        .query_map([], |row| row.get(0))
+        .unwrap()
+        .collect::<rusqlite::Result<_>>()
+        .unwrap();
+    assert_eq!(senate_ids, vec!["S317", "S330", "S354", "S396"]);
+
+    let ballots: Vec<(String, String, String)> = conn
+        .prepare("SELECT bioguide_id, chamber, member_id FROM legislator_votes ORDER BY bioguide_id, chamber")
+        .unwrap()
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+        .unwrap()
+        .collect::<rusqlite::Result<_>>()
+        .unwrap();
+    assert_eq!(
+        ballots,
+        vec![
+            ("A000370".to_string(), "house".to_string(), "A000370".to_string()),
+            ("B001230".to_string(), "senate".to_string(), "S354".to_string()),
+            ("B001243".to_string(), "senate".to_string(), "S396".to_string()),
+        ]
+    );
+
+    let terms: u32 = conn.query_row("SELECT COUNT(*) FROM legislator_terms", [], |row| row.get(0)).unwrap();
+    assert_eq!(terms, 5);
+
+    // Version 2 databases keyed senators on a hash; upgrading moves them to their LIS id
+    conn.execute_batch(
+        "DELETE FROM member_votes WHERE member_id = 'S354';
+         INSERT INTO members (member_id, chamber, lis_member_id, last_name) VALUES ('0a1b2c', 'senate', 'S354', 'Baldwin');
+         INSERT INTO member_votes (vote_hash, member_id, vote_cast) SELECT vote_hash, '0a1b2c', 'Yea' FROM votes WHERE chamber = 'senate';
+         DELETE FROM members WHERE member_id = 'S354';
+         DELETE FROM schema_version WHERE version > 2;",
+    )
+    .unwrap();
+    initialize_database(&conn).unwrap();
+
+    let moved: (u32, u32) = conn
+        .query_row(
+            "SELECT (SELECT COUNT(*) FROM member_votes WHERE member_id = 'S354'), (SELECT COUNT(*) FROM members WHERE member_id = '0a1b2c')",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .unwrap();
+    assert_eq!(moved, (1, 0));
+}
+
+/// A roll call with the given `(member id, party, vote)` ballots and nothing else.
+fn synthetic_rollcall(chamber: &str, congress: u16, session: u8, number: u32, ballots: &[(&str, &str, &str)]) -> crate::model::RollCall {
+    use crate::model::{Ballot, Member, Party, RollCall};
+
+    RollCall {
+        chamber: chamber.into(),
+        congress,
+        session,
+        number,
+        date: None,
+        time: None,
+        modified: None,
+        question: None,
+        description: None,
+        result: None,
+        vote_type: None,
+        majority_requirement: None,
+        bill: None,
+        amendment: None,
+        ballots: ballots
+            .iter()
+            .map(|(id, party, cast)| Ballot {
+                member: Member {
+                    bioguide_id: Some(id.to_string()),
+                    full_name: Some(id.to_lowercase()),
+                    party: Some(Party::from(*party)),
+                    ..Default::default()
+                },
+                cast: cast.parse().ok(),
+                paired_with: None,
+            })
+            .collect(),
+    }
+}
+
+#[test]
+fn test_party_unity() {
+    use crate::analytics::{insert_party_unity, party_unity, unity_vote, write_party_unity_csv};
+    use crate::endpoints::CongressChamber::House;
+
+    let rollcalls = [
+        // Most Democrats for, most Republicans against: C and Z defect
+        synthetic_rollcall("house", 118, 1, 1, &[("A", "D", "Yea"), ("B", "D", "Yea"), ("C", "D", "Nay"), ("X", "R", "Nay"), ("Y", "R", "No"), ("Z", "R", "Aye")]),
+        // Bipartisan
+        synthetic_rollcall("house", 118, 1, 2, &[("A", "D", "Yea"), ("B", "D", "Yea"), ("C", "D", "Yea"), ("X", "R", "Yea"), ("Y", "R", "Yea"), ("Z", "R", "Yea")]),
+        // Independents and members not voting are not scored
+        synthetic_rollcall("house", 118, 2, 3, &[("A", "D", "Nay"), ("B", "D", "Nay"), ("C", "D", "Nay"), ("X", "R", "Yea"), ("Y", "R", "Yea"), ("Z", "R", "Not Voting"), ("Q", "I", "Nay")]),
+    ];
+
+    assert!(unity_vote(&rollcalls[0]).is_some());
+    assert!(unity_vote(&rollcalls[1]).is_none());
+
+    let mut conn = fixture_db(&[]);
+    for rollcall in &rollcalls {
+        let hash = format!("{}-{}", rollcall.session, rollcall.number);
+        conn.execute(
+            "INSERT INTO votes (vote_hash, chamber, congress, session, rollcall, year) VALUES (?1, 'house', 118, ?2, ?3, 2023)",
+            rusqlite::params![hash, rollcall.session, rollcall.number],
+        )
+        .unwrap();
+        for ballot in &rollcall.ballots {
+            let id = ballot.member.id().unwrap();
+            conn.execute(
+                "INSERT OR IGNORE INTO members (member_id, chamber, full_name) VALUES (?1, 'house', ?2)",
+                rusqlite::params![id, ballot.member.full_name],
+            )
+            .unwrap();
+            conn.execute(
+                "INSERT INTO member_votes (vote_hash, member_id, vote_cast, party) VALUES (?1, ?2, ?3, ?4)",
+                rusqlite::params![hash, id, ballot.cast.as_ref().map(|c| c.to_string()), ballot.member.party.as_ref().map(|p| p.code())],
+            )
+            .unwrap();
+        }
+    }
+    // A roll call without ballots counts towards the share
+    conn.execute("INSERT INTO votes (vote_hash, chamber, congress, session, rollcall, year) VALUES ('empty', 'house', 118, 2, 4, 2024)", []).unwrap();
+
+    let unity = party_unity(&conn).unwrap();
+
+    assert_eq!(unity.votes.len(), 2);
+    let defectors: Vec<_> = unity.defections.iter().map(|d| (d.member_id.as_str(), d.vote_cast.as_str())).collect();
+    assert_eq!(defectors, vec![("C", "Nay"), ("Z", "Yea")]);
+
+    let score = |session, id| unity.score(House, 118, session, id).map(|s| (s.with_party, s.against_party, s.score));
+    assert_eq!(score(Some(1), "C"), Some((0, 1, 0.0)));
+    assert_eq!(score(None, "C"), Some((1, 1, 0.5)));
+    assert_eq!(score(None, "A"), Some((2, 0, 1.0)));
+    assert_eq!(score(None, "Z"), Some((0, 1, 0.0)));
+    assert_eq!(score(None, "Q"), None);
+    assert_eq!(unity.unity_share()[&(House, 118)], 2.0 / 4.0);
+
+    // Sessions come before the congress total
+    let sessions: Vec<_> = unity.scores().iter().filter(|s| s.member_id == "A").map(|s| s.session).collect();
+    assert_eq!(sessions, vec![Some(1), Some(2), None]);
+
+    for _ in 0..2 {
+        let tx = conn.transaction().unwrap();
+        insert_party_unity(&tx, &unity).unwrap();
+        tx.commit().unwrap();
+    }
+    let counts: (u32, u32, u32) = conn
+        .query_row(
+            "SELECT (SELECT COUNT(*) FROM party_unity_votes), (SELECT COUNT(*) FROM party_defections),
+                    (SELECT COUNT(*) FROM party_unity_scores WHERE session = 0)",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .unwrap();
+    assert_eq!(counts, (2, 2, 6));
+
+    let dir = temp_dir("unity");
+    write_party_unity_csv(&dir, &unity).unwrap();
+    let csv = std::fs::read_to_string(dir.join("party_defections.csv")).unwrap();
+    let lines: Vec<_> = csv.lines().collect();
+    assert_eq!(lines[0], "chamber,congress,session,rollcall,member_id,name,party,state,vote_cast,party_position");
+    assert_eq!(lines[1], "house,118,1,1,C,c,D,,Nay,1");
+    assert_eq!(lines.len(), 3);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_attendance() {
+    use crate::attendance::{attendance, is_missed, AttendanceFilter};
+    use crate::endpoints::CongressChamber::{House, Senate};
+
+    let conn = fixture_db(&[]);
+
+    let rollcall = |chamber: &str, session: u8, roll: u32, ballots: &[(&str, Option<&str>)]| {
+        let hash = format!("{}-{}-{}", chamber, session, roll);
+        conn.execute(
+            "INSERT INTO votes (vote_hash, chamber, congress, session, rollcall, year, vote_date) VALUES (?1, ?2, 118, ?3, ?4, 2023, ?5)",
+            rusqlite::params![hash, chamber, session, roll, format!("{}-Jan-2023", roll)],
+        )
+        .unwrap();
+        for (id, cast) in ballots {
+            conn.execute("INSERT OR IGNORE INTO members (member_id, chamber, full_name) VALUES (?1, ?2, ?1)", rusqlite::params![id, chamber]).unwrap();
+            conn.execute("INSERT INTO member_votes (vote_hash, member_id, vote_cast) VALUES (?1, ?2, ?3)", rusqlite::params![hash, id, cast]).unwrap();
+        }
+    };
+
+    rollcall("house", 1, 1, &[("A", Some("Yea")), ("B", Some("Nay"))]);
+    rollcall("house", 1, 2, &[("A", Some("Present")), ("B", Some("Not Voting"))]);
+    rollcall("house", 1, 3, &[("A", Some("Yea")), ("B", None)]);
+    rollcall("house", 1, 4, &[("A", Some("Nay")), ("B", Some("Yea"))]);
+    rollcall("house", 2, 1, &[("A", Some("Yea")), ("B", Some("Not Voting")), ("C", Some("Not Voting"))]);
+    rollcall("senate", 1, 1, &[("S1", Some("Not Voting")), ("S2", Some("Yea"))]);
+
+    assert!(is_missed(Some("not voting")) && is_missed(Some("Absent")) && is_missed(None));
+    assert!(!is_missed(Some("Present")));
+
+    let report = attendance(&conn, &AttendanceFilter::default()).unwrap();
+
+    let member = |session, id: &str| {
+        report.members.iter().find(|m| m.chamber == House && m.session == session && m.member_id == id).map(|m| (m.rollcalls, m.missed, m.rate))
+    };
+    assert_eq!(member(Some(1), "B"), Some((4, 2, 0.5)));
+    assert_eq!(member(None, "B"), Some((5, 3, 0.4)));
+    assert_eq!(member(None, "A"), Some((5, 0, 1.0)));
+    assert_eq!(member(Some(1), "C"), None);
+
+    // B's two misses in a row outrank the single misses
+    let streaks: Vec<_> = report.streaks.iter().map(|s| (s.member_id.as_str(), s.length, s.first.rollcall, s.last.rollcall)).collect();
+    assert_eq!(streaks, vec![("B", 2, 2, 3), ("C", 1, 1, 1), ("S1", 1, 1, 1)]);
+    assert_eq!(report.streaks[0].first_date.as_deref(), Some("2-Jan-2023"));
+
+    let chambers: Vec<_> = report
+        .chambers
+        .iter()
+        .map(|c| (c.chamber, c.session, c.rollcalls, c.members, c.missed, c.rate, c.median_member_rate))
+        .collect();
+    assert_eq!(
+        chambers,
+        vec![
+            (House, Some(1), 4, 2, 2, 0.75, 0.75),
+            (House, Some(2), 1, 3, 2, 1.0 - 2.0 / 3.0, 0.0),
+            (House, None, 5, 3, 4, 1.0 - 4.0 / 11.0, 0.4),
+            (Senate, Some(1), 1, 2, 1, 0.5, 0.5),
+            (Senate, None, 1, 2, 1, 0.5, 0.5),
+        ]
+    );
+
+    let senate = attendance(&conn, &AttendanceFilter { chamber: Some(Senate), congress: Some(118) }).unwrap();
+    assert_eq!(senate.members.len(), 4);
+    assert!(attendance(&conn, &AttendanceFilter { chamber: None, congress: Some(117) }).unwrap().members.is_empty());
+}
+
+#[test]
+fn test_vote_matrix() {
+    use crate::endpoints::CongressChamber::House;
+    use crate::matrix::{rollcall_id, MatrixFilter, VoteMatrix};
+
+    let conn = fixture_db(&XML_FIXTURES);
+
+    // A later roll call only Adams was on
+    conn.execute_batch(
+        "INSERT INTO votes (vote_hash, chamber, congress, session, rollcall, year) VALUES ('h11', 'house', 118, 2, 11, 2024);
+         INSERT INTO member_votes (vote_hash, member_id, vote_cast) VALUES ('h11', 'A000370', 'Nay');",
+    )
+    .unwrap();
+
+    let all = VoteMatrix::build(&conn, &MatrixFilter::default()).unwrap();
+    assert_eq!((all.rows(), all.columns()), (9, 3));
+
+    let matrix = VoteMatrix::build(&conn, &MatrixFilter { chamber: Some(House), congress: Some(118), session: Some(2) }).unwrap();
+    assert_eq!((matrix.rows(), matrix.columns()), (5, 2));
+    assert_eq!(matrix.rollcalls[1].rollcall_id, rollcall_id(House, 118, 2, 11));
+
+    let cell = |member: &str, rollcall: &str| matrix.get(matrix.row_of(member).unwrap(), matrix.column_of(rollcall).unwrap());
+    assert_eq!(cell("A000370", "house-118-2-10"), Some(1));
+    assert_eq!(cell("A000371", "house-118-2-10"), Some(-1));
+    assert_eq!(cell("A000148", "house-118-2-10"), Some(0));
+    assert_eq!(cell("A000370", "house-118-2-11"), Some(-1));
+    assert_eq!(cell("A000055", "house-118-2-11"), None);
+    assert_eq!(matrix.triplets().count(), 6);
+
+    let dir = temp_dir("matrix");
+    matrix.export(&dir).unwrap();
+
+    let dense = std::fs::read_to_string(dir.join("matrix.csv")).unwrap();
+    let lines: Vec<_> = dense.lines().collect();
+    assert_eq!(lines[0], "member_id,house-118-2-10,house-118-2-11");
+    assert_eq!(lines[1], "A000055,1,");
+    assert_eq!(lines.len(), 6);
+
+    let triplets = std::fs::read_to_string(dir.join("matrix_triplets.csv")).unwrap();
+    assert_eq!(triplets.lines().next(), Some("row,column,value"));
+    assert_eq!(triplets.lines().count(), 7);
+
+    let labels = std::fs::read_to_string(dir.join("matrix_rollcalls.csv")).unwrap();
+    assert!(labels.starts_with("column,rollcall_id,chamber,congress,session,rollcall,vote_date,question\n0,house-118-2-10,house,118,2,10,"));
+
+    // .npy: magic, 1.0, a header ending on a 64 byte boundary, then little-endian f32 cells
+    let npy = std::fs::read(dir.join("matrix.npy")).unwrap();
+    assert_eq!(&npy[..8], b"\x93NUMPY\x01\x00");
+    let header_len = u16::from_le_bytes([npy[8], npy[9]]) as usize;
+    assert_eq!((10 + header_len) % 64, 0);
+    let header = std::str::from_utf8(&npy[10..10 + header_len]).unwrap();
+    assert!(header.contains("'shape': (5, 2)") && header.ends_with('\n'));
+    let cells: Vec<f32> = npy[10 + header_len..].chunks(4).map(|b| f32::from_le_bytes(b.try_into().unwrap())).collect();
+    assert_eq!(cells.len(), 10);
+    assert_eq!(cells[0], 1.0);
+    assert!(cells[1].is_nan());
+
+    std::fs::remove_dir_all(&dir).unwrap();
 }
 
+#[test]
+fn test_ideal_points() {
+    use crate::endpoints::CongressChamber::House;
+    use crate::ideal_points::{estimate, insert_ideal_points, IdealPointOptions};
+    use crate::matrix::{MatrixFilter, VoteMatrix};
+
+    let mut conn = fixture_db(&[]);
+
+    // 30 members on a line, Republicans to the right; each roll call splits them at a
+    // cutpoint, with about one vote in twenty flipped and a few missed
+    let positions: Vec<f64> = (0..30).map(|i| -1.45 + 0.1 * i as f64).collect();
+    let mut seed: u64 = 42;
+    let mut random = move || {
+        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (seed >> 33) as f64 / (1u64 << 31) as f64
+    };
+
+    for (i, &x) in positions.iter().enumerate() {
+        let party = if x > 0.0 { "R" } else { "D" };
+        conn.execute(
+            "INSERT INTO members (member_id, chamber, full_name, party) VALUES (?1, 'house', ?1, ?2)",
+            rusqlite::params![format!("M{:03}", i), party],
+        )
+        .unwrap();
+    }
+    for j in 0..120 {
+        let hash = format!("h{}", j);
+        conn.execute(
+            "INSERT INTO votes (vote_hash, chamber, congress, session, rollcall, year) VALUES (?1, 'house', 118, 1, ?2, 2023)",
+            rusqlite::params![hash, j + 1],
+        )
+        .unwrap();
+
+        let cutpoint = random() * 2.6 - 1.3;
+        let direction = if j % 2 == 0 { 1.0 } else { -1.0 };
+        for (i, &x) in positions.iter().enumerate() {
+            let draw = random();
+            let yea = ((x - cutpoint) * direction > 0.0) != (draw < 0.05);
+            let cast = match draw {
+                d if d > 0.97 => "Not Voting",
+                _ if yea => "Yea",
+                _ => "Nay",
+            };
+            conn.execute(
+                "INSERT INTO member_votes (vote_hash, member_id, vote_cast, party) VALUES (?1, ?2, ?3, ?4)",
+                rusqlite::params![hash, format!("M{:03}", i), cast, if x > 0.0 { "R" } else { "D" }],
+            )
+            .unwrap();
+        }
+    }
 
+    let matrix = VoteMatrix::build(&conn, &MatrixFilter { chamber: Some(House), congress: Some(118), session: None }).unwrap();
+
+    let correlation = |estimated: &[f64]| {
+        let n = estimated.len() as f64;
+        let (mx, my) = (positions.iter().sum::<f64>() / n, estimated.iter().sum::<f64>() / n);
+        let cov: f64 = positions.iter().zip(estimated).map(|(x, y)| (x - mx) * (y - my)).sum();
+        let sx: f64 = positions.iter().map(|x| (x - mx).powi(2)).sum::<f64>().sqrt();
+        let sy: f64 = estimated.iter().map(|y| (y - my).powi(2)).sum::<f64>().sqrt();
+        cov / (sx * sy)
+    };
+
+    let fit = estimate(&matrix, &IdealPointOptions::default()).unwrap();
+    assert_eq!(fit.points.len(), 30);
+    assert!(fit.converged);
+    assert!(fit.classified > 0.9, "classified {}", fit.classified);
+
+    // Recovered up to scale, with Republicans positive
+    let first: Vec<f64> = fit.points.iter().map(|p| p.coordinates[0]).collect();
+    assert!(correlation(&first) > 0.95, "correlation {}", correlation(&first));
+    assert!(fit.point("M000").unwrap().coordinates[0] < 0.0);
+    assert!(fit.point("M029").unwrap().coordinates[0] > 0.0);
+    assert!(fit.points.iter().all(|p| p.standard_errors[0] > 0.0 && p.standard_errors[0] < 1.0));
+
+    // Members at the extremes are pinned down less well than those in the middle
+    let se = |id: &str| fit.point(id).unwrap().standard_errors[0];
+    assert!(se("M000") > se("M015"));
+
+    let two = estimate(&matrix, &IdealPointOptions { dimensions: 2, ..Default::default() }).unwrap();
+    assert!(two.points.iter().all(|p| p.coordinates.len() == 2 && p.standard_errors.len() == 2));
+    let first: Vec<f64> = two.points.iter().map(|p| p.coordinates[0]).collect();
+    assert!(correlation(&first) > 0.9, "correlation {}", correlation(&first));
+    assert!(two.classified >= fit.classified - 0.01);
+
+    assert!(matches!(estimate(&matrix, &IdealPointOptions { dimensions: 3, ..Default::default() }), Err(crate::Error::Config { .. })));
+    assert!(matches!(estimate(&matrix, &IdealPointOptions { min_votes: 1000, ..Default::default() }), Err(crate::Error::Estimation { .. })));
+
+    let tx = conn.transaction().unwrap();
+    insert_ideal_points(&tx, House, 118, &fit).unwrap();
+    insert_ideal_points(&tx, House, 118, &two).unwrap();
+    insert_ideal_points(&tx, House, 118, &fit).unwrap();
+    tx.commit().unwrap();
+
+    let count = |sql: &str| conn.query_row(sql, [], |row| row.get::<_, i64>(0)).unwrap();
+    assert_eq!(count("SELECT COUNT(*) FROM member_ideal_points WHERE dimensions = 1 AND coord2 IS NULL"), 30);
+    assert_eq!(count("SELECT COUNT(*) FROM member_ideal_points WHERE dimensions = 2 AND se2 > 0"), 30);
+}
+
+#[test]
+fn test_vote_times() {
+    use crate::dates::{parse_date, parse_datetime, parse_time, to_eastern, VoteTimes};
+    use crate::pipeline::VoteFileEntry;
+    use chrono::{NaiveDate, NaiveTime};
+
+    let day = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+    let hm = |h, m| NaiveTime::from_hms_opt(h, m, 0).unwrap();
+
+    // Format drift across the years
+    for value in ["24-Jul-2008", "24-JUL-2008", " 24-jul-2008 ", "2008-07-24", "July 24, 2008", "Thursday, July 24, 2008", "7/24/2008", "24-Jul-08"] {
+        assert_eq!(parse_date(value, None), Some(day(2008, 7, 24)), "{}", value);
+    }
+    assert_eq!(parse_date("Sept. 9, 1991", None), Some(day(1991, 9, 9)));
+    assert_eq!(parse_date("09-Jan", None), None);
+    assert_eq!(parse_date("09-Jan", Some(2024)), Some(day(2024, 1, 9)));
+
+    for value in ["14:36", "2:36 PM", "02:36 pm", "2:36 p.m.", "2:36PM", "14:36:00"] {
+        assert_eq!(parse_time(value), Some(hm(14, 36)), "{}", value);
+    }
+    assert_eq!(parse_time("12:05 AM"), Some(hm(0, 5)));
+
+    assert_eq!(parse_datetime("January 9, 2024, 05:31 PM", None), Some((day(2024, 1, 9), Some(hm(17, 31)))));
+    assert_eq!(parse_datetime("January  9, 2024,  05:31 PM ET", None), Some((day(2024, 1, 9), Some(hm(17, 31)))));
+    assert_eq!(parse_datetime("January 9, 2024 at 5:31 p.m.", None), Some((day(2024, 1, 9), Some(hm(17, 31)))));
+    assert_eq!(parse_datetime("2024-01-09T17:31:00", None), Some((day(2024, 1, 9), Some(hm(17, 31)))));
+    assert_eq!(parse_datetime("January 9, 2024", None), Some((day(2024, 1, 9), None)));
+    assert_eq!(parse_datetime("not a date", None), None);
+
+    // Standard and daylight time; the repeated hour is the first one, the skipped hour moves forward
+    assert_eq!(to_eastern(day(2024, 1, 9), hm(17, 31)).to_rfc3339(), "2024-01-09T17:31:00-05:00");
+    assert_eq!(to_eastern(day(2008, 7, 24), hm(14, 36)).to_rfc3339(), "2008-07-24T14:36:00-04:00");
+    assert_eq!(to_eastern(day(2023, 11, 5), hm(1, 30)).to_rfc3339(), "2023-11-05T01:30:00-04:00");
+    assert_eq!(to_eastern(day(2023, 3, 12), hm(2, 30)).to_rfc3339(), "2023-03-12T03:30:00-04:00");
+
+    let house = VoteTimes::house(Some("17-Jan-2024"), None, Some("2:36 PM"), None);
+    assert_eq!(house.at_string().as_deref(), Some("2024-01-17T14:36:00-05:00"));
+    assert_eq!(house.epoch(), Some(1705520160));
+    let undated = VoteTimes::house(Some("17-Jan-2024"), None, None, None);
+    assert_eq!((undated.day_string().as_deref(), undated.at), (Some("2024-01-17"), None));
+
+    // Both chambers' fixtures, in SQLite
+    let conn = fixture_db(&XML_FIXTURES);
+
+    let times = |conn: &rusqlite::Connection| {
+        conn.prepare("SELECT chamber, vote_day, vote_timestamp, modified_timestamp FROM votes ORDER BY vote_epoch")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)))
+            .unwrap()
+            .collect::<rusqlite::Result<Vec<(String, String, String, Option<String>)>>>()
+            .unwrap()
+    };
+    let expected = vec![
+        ("senate".to_string(), "2024-01-09".to_string(), "2024-01-09T17:31:00-05:00".to_string(), Some("2024-01-09T18:02:00-05:00".to_string())),
+        ("house".to_string(), "2024-01-17".to_string(), "2024-01-17T14:36:00-05:00".to_string(), None),
+    ];
+    assert_eq!(times(&conn), expected);
+
+    // Databases from before version 6 get the columns filled in from the raw values
+    conn.execute_batch("UPDATE votes SET vote_day = NULL, vote_timestamp = NULL, vote_epoch = NULL, modified_timestamp = NULL; DELETE FROM schema_version WHERE version > 5;")
+        .unwrap();
+    initialize_database(&conn).unwrap();
+    assert_eq!(times(&conn), expected);
+
+    // And the master JSON carries them too
+    let entry = VoteFileEntry::from_path(std::path::Path::new("tests/fixtures/data/xml/118/senate/2/2024_1.xml")).unwrap();
+    let (_, votes, _) = crate::json_master::read_unified_votes(&entry).unwrap();
+    assert_eq!(votes[0].vote_day.as_deref(), Some("2024-01-09"));
+    assert_eq!(votes[0].vote_timestamp.as_deref(), Some("2024-01-09T17:31:00-05:00"));
+}
+
+#[test]
+fn test_search() {
+    use crate::endpoints::CongressChamber::{House, Senate};
+    use crate::search::{quote_terms, search, SearchFilter};
+    use crate::sqllite::insert_vote_files;
+
+    let mut conn = fixture_db(&XML_FIXTURES);
+
+    let all = SearchFilter::default();
+    let rollcalls = |conn: &rusqlite::Connection, query: &str, filter: &SearchFilter| {
+        search(conn, query, filter, 10).unwrap().iter().map(|h| (h.chamber, h.rollcall)).collect::<Vec<_>>()
+    };
+
+    // Senate title, question and document text; porter stemming matches "judges"
+    let hits = search(&conn, "cloture judges", &all, 10).unwrap();
+    assert_eq!(hits.len(), 1);
+    assert_eq!((hits[0].chamber, hits[0].congress, hits[0].session, hits[0].rollcall), (Senate, 118, 2, 1));
+    assert_eq!(hits[0].vote_date.as_deref(), Some("2024-01-09"));
+    assert!(hits[0].snippet.contains("[Cloture]") || hits[0].snippet.contains("[Judge]"), "{}", hits[0].snippet);
+
+    // House description, FTS5 prefixes and boolean operators
+    assert_eq!(rollcalls(&conn, "gonz*", &all), vec![(House, 10)]);
+    assert_eq!(rollcalls(&conn, "officer OR illinois", &all).len(), 2);
+    assert_eq!(rollcalls(&conn, "illinois NOT cloture", &all), vec![]);
+
+    // Bill numbers aren't FTS5 syntax, so they are searched word by word
+    assert_eq!(quote_terms("H.R. 788"), "\"H\" \"R\" \"788\"");
+    assert_eq!(rollcalls(&conn, "H.R. 788", &all), vec![(House, 10)]);
+    assert_eq!(rollcalls(&conn, "\"unterminated", &all), vec![]);
+    assert_eq!(rollcalls(&conn, "  ", &all), vec![]);
+    assert_eq!(rollcalls(&conn, "title: gonz*", &all), vec![(House, 10)]);
+
+    assert_eq!(rollcalls(&conn, "illinois", &SearchFilter { chamber: Some(House), congress: None }), vec![]);
+    assert_eq!(rollcalls(&conn, "illinois", &SearchFilter { chamber: None, congress: Some(117) }), vec![]);
+
+    // Failures other than the query's syntax are errors, not retried word by word
+    let broken = rusqlite::Connection::open_in_memory().unwrap();
+    assert!(search(&broken, "H.R. 788", &all, 10).is_err());
+
+    // Re-ingesting keeps one row per vote; deleting a vote drops its row
+    insert_vote_files(&mut conn, &fixture_entries(&XML_FIXTURES), 1, &indicatif::ProgressBar::hidden()).unwrap();
+    let count = |conn: &rusqlite::Connection| conn.query_row("SELECT COUNT(*) FROM vote_search", [], |row| row.get::<_, i64>(0)).unwrap();
+    assert_eq!(count(&conn), 2);
+
+    conn.execute("DELETE FROM votes WHERE chamber = 'senate'", []).unwrap();
+    assert_eq!(count(&conn), 1);
+    assert_eq!(rollcalls(&conn, "cloture", &all), vec![]);
+
+    // Databases from before version 7 are indexed when opened
+    conn.execute_batch("DELETE FROM vote_search; DELETE FROM schema_version WHERE version > 6;").unwrap();
+    initialize_database(&conn).unwrap();
+    assert_eq!(rollcalls(&conn, "gonzalez", &all), vec![(House, 10)]);
+}
+
+#[tokio::test]
+async fn test_server() {
+    use crate::endpoints::CongressChamber::{House, Senate};
+    use crate::queries::{self, normalize_bill, Page, VoteQuery};
+    use crate::server::{bind, handle};
+    use hyper::StatusCode;
+
+    let dir = temp_dir("server");
+    std::fs::create_dir_all(&dir).unwrap();
+    let db_path = dir.join("votes.db");
+
+    let conn = fixture_db(&XML_FIXTURES);
+    conn.execute("VACUUM INTO ?1", [db_path.to_str().unwrap()]).unwrap();
+
+    // The query layer
+    let all = VoteQuery::new().rollcall_page(&conn).unwrap();
+    assert_eq!(all.total, 2);
+    assert_eq!(all.items.iter().map(|r| (r.chamber, r.rollcall)).collect::<Vec<_>>(), vec![(House, 10), (Senate, 1)]);
+    assert_eq!(all.items[0].bill.as_deref(), Some("H R 788"));
+    assert_eq!(all.items[1].bill.as_deref(), Some("PN1042"));
+    assert_eq!(Page::new(0, 5000).limit, crate::queries::MAX_PAGE);
+    assert_eq!(normalize_bill("H.R. 788"), "HR788");
+    // Punctuation is dropped the same way in Rust and in SQL
+    assert_eq!(normalize_bill("h.r.-7_88"), "HR788");
+    assert_eq!(VoteQuery::new().bill("h.r.-7_88").rollcall_page(&conn).unwrap().total, 1);
+    assert_eq!(VoteQuery::new().bill("p.n. 1042").rollcall_page(&conn).unwrap().total, 1);
+    assert_eq!(VoteQuery::new().bill("H.R. 788(a)").rollcall_page(&conn).unwrap().total, 0);
+
+    let detail = queries::rollcall(&conn, House, 118, 2, 10).unwrap().unwrap();
+    assert_eq!((detail.summary.yea, detail.summary.nay), (3, 1));
+    assert_eq!(detail.ballots.len(), 5);
+    assert!(queries::rollcall(&conn, House, 118, 2, 11).unwrap().is_none());
+    drop(conn);
+
+    // Routes, answered straight from a read-only connection
+    let conn = rusqlite::Connection::open_with_flags(&db_path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY).unwrap();
+    let get = |path: &str, query: Option<&str>| handle(&conn, path, query);
+
+    let (status, body) = get("/rollcalls", Some("chamber=senate"));
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["total"], 1);
+    assert_eq!(body["items"][0]["vote_date"], "2024-01-09");
+    assert_eq!(body["items"][0]["vote_timestamp"], "2024-01-09T17:31:00-05:00");
+
+    assert_eq!(get("/rollcalls", Some("from=2024-01-10")).1["total"], 1);
+    assert_eq!(get("/rollcalls", Some("to=2024-01-09&congress=118&session=2")).1["total"], 1);
+    assert_eq!(get("/rollcalls", Some("congress=117")).1["total"], 0);
+
+    let (_, body) = get("/rollcalls", Some("limit=1&offset=1"));
+    assert_eq!((body["total"].as_u64(), body["limit"].as_u64(), body["offset"].as_u64()), (Some(2), Some(1), Some(1)));
+    assert_eq!(body["items"].as_array().unwrap().len(), 1);
+    assert_eq!(body["items"][0]["chamber"], "senate");
+
+    let (status, body) = get("/rollcalls/house/118/2/10", None);
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["result"], "Passed");
+    assert_eq!(body["ballots"][0]["member_id"], "A000055");
+
+    let (_, body) = get("/members/A000370", None);
+    assert_eq!((body["chamber"].as_str(), body["party"].as_str(), body["state"].as_str()), (Some("house"), Some("D"), Some("NC")));
+
+    let (_, body) = get("/members/S354/votes", None);
+    assert_eq!(body["total"], 1);
+    assert_eq!(body["items"][0]["rollcall"], 1);
+    assert!(body["items"][0]["vote_cast"].is_string());
+    assert_eq!(get("/members/S354/votes", Some("chamber=house")).1["total"], 0);
+    assert_eq!(get("/members/S354/votes", Some("cast=nay")).1["total"], 0);
+    assert_eq!(get("/rollcalls", Some("party=R&cast=nay&state=WY")).1["items"][0]["chamber"], "senate");
+
+    // Bill numbers however they are written
+    for bill in ["/bills/H.R.%20788/votes", "/bills/hr788/votes", "/bills/PN1042/votes"] {
+        assert_eq!(get(bill, None).1["total"], 1, "{}", bill);
+    }
+    assert_eq!(get("/bills/HR789/votes", None).1["total"], 0);
+
+    let (status, body) = get("/search", Some("q=cloture&chamber=senate"));
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["items"][0]["rollcall"], 1);
+    assert_eq!(get("/search", Some("q=cloture&offset=1")).1["items"].as_array().unwrap().len(), 0);
+    let huge = format!("q=cloture&offset={}", usize::MAX);
+    assert_eq!(get("/search", Some(&huge)).0, StatusCode::OK);
+    let (status, body) = get("/rollcalls", Some(&format!("offset={}", usize::MAX)));
+    assert_eq!((status, body["offset"].as_u64(), body["items"].as_array().map(|a| a.len())), (StatusCode::OK, Some(i32::MAX as u64), Some(0)));
+
+    // Errors
+    let status = |path: &str, query: Option<&str>| get(path, query).0;
+    assert_eq!(status("/rollcalls/house/118/2/11", None), StatusCode::NOT_FOUND);
+    assert_eq!(status("/members/Z999999", None), StatusCode::NOT_FOUND);
+    assert_eq!(status("/members/Z999999/votes", None), StatusCode::NOT_FOUND);
+    assert_eq!(status("/nowhere", None), StatusCode::NOT_FOUND);
+    assert_eq!(status("/rollcalls/parliament/118/2/10", None), StatusCode::BAD_REQUEST);
+    assert_eq!(status("/rollcalls", Some("congress=last")), StatusCode::BAD_REQUEST);
+    assert_eq!(status("/rollcalls", Some("from=01/09/2024")), StatusCode::BAD_REQUEST);
+    assert_eq!(status("/rollcalls", Some("state=XX")), StatusCode::BAD_REQUEST);
+    assert_eq!(status("/search", None), StatusCode::BAD_REQUEST);
+    assert!(get("/nowhere", None).1["error"].is_string());
+
+    // One round trip over HTTP
+    let (addr, server) = bind(&db_path, "127.0.0.1:0".parse().unwrap()).unwrap();
+    let server = tokio::spawn(server);
+    let client = reqwest::Client::builder().no_proxy().build().unwrap();
+
+    let response = client.get(format!("http://{}/rollcalls/senate/118/2/1", addr)).send().await.unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(response.headers()["content-type"], "application/json");
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["question"].as_str().map(|q| q.contains("Cloture")), Some(true));
+
+    let response = client.post(format!("http://{}/rollcalls", addr)).send().await.unwrap();
+    assert_eq!(response.status().as_u16(), 405);
+
+    server.abort();
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_vote_query() {
+    use crate::endpoints::CongressChamber::{House, Senate};
+    use crate::model::{Party, State, VoteCast};
+    use crate::queries::{Page, VoteQuery};
+    use chrono::NaiveDate;
+
+    let conn = fixture_db(&XML_FIXTURES);
+
+    let rollcalls = |query: VoteQuery| query.rollcalls(&conn).unwrap().collect_all().unwrap().iter().map(|r| (r.chamber, r.rollcall)).collect::<Vec<_>>();
+    let members = |query: VoteQuery| query.ballots(&conn).unwrap().collect_all().unwrap().into_iter().map(|b| b.member_id).collect::<Vec<_>>();
+
+    // Roll call filters
+    assert_eq!(rollcalls(VoteQuery::new()), vec![(House, 10), (Senate, 1)]);
+    assert_eq!(rollcalls(VoteQuery::new().chamber(House).congress(118).session(2)), vec![(House, 10)]);
+    assert_eq!(rollcalls(VoteQuery::new().congresses(..=118)).len(), 2);
+    assert_eq!(rollcalls(VoteQuery::new().congresses(117..118)), vec![]);
+    assert_eq!(rollcalls(VoteQuery::new().congresses(119..)), vec![]);
+    assert_eq!(rollcalls(VoteQuery::new().from(NaiveDate::from_ymd_opt(2024, 1, 10).unwrap())), vec![(House, 10)]);
+    assert_eq!(rollcalls(VoteQuery::new().to(NaiveDate::from_ymd_opt(2024, 1, 9).unwrap())), vec![(Senate, 1)]);
+    assert_eq!(rollcalls(VoteQuery::new().question("CLOTURE")), vec![(Senate, 1)]);
+    assert_eq!(rollcalls(VoteQuery::new().question("passage")), vec![(House, 10)]);
+    assert_eq!(rollcalls(VoteQuery::new().result("passed")), vec![(House, 10)]);
+    assert_eq!(rollcalls(VoteQuery::new().result("agreed to")), vec![(Senate, 1)]);
+    assert_eq!(rollcalls(VoteQuery::new().bill("h.r. 788")), vec![(House, 10)]);
+    assert_eq!(rollcalls(VoteQuery::new().bill("PN 1042")), vec![(Senate, 1)]);
+    assert_eq!(rollcalls(VoteQuery::new().bill("PN1042").chamber(House)), vec![]);
+
+    // Ballot filters; roll calls with at least one matching ballot
+    assert_eq!(members(VoteQuery::new()).len(), 9);
+    assert_eq!(members(VoteQuery::new().party(Party::Republican)), vec!["A000055", "A000379", "S317", "S396"]);
+    assert_eq!(members(VoteQuery::new().party("republican")).len(), 4);
+    assert_eq!(members(VoteQuery::new().vote_cast("aye".parse::<VoteCast>().unwrap())).len(), 5);
+    assert_eq!(members(VoteQuery::new().vote_cast(VoteCast::NotVoting)), vec!["A000148", "S396"]);
+    assert_eq!(members(VoteQuery::new().party("R").vote_cast(VoteCast::Nay)), vec!["S317"]);
+    assert_eq!(members(VoteQuery::new().state(State::Wyoming)), vec!["S317"]);
+    assert_eq!(rollcalls(VoteQuery::new().party("R").vote_cast(VoteCast::Nay)), vec![(Senate, 1)]);
+    assert_eq!(rollcalls(VoteQuery::new().member("A000371")), vec![(House, 10)]);
+
+    let mut ballots = VoteQuery::new().member("S354").ballots(&conn).unwrap();
+    let ballot = ballots.iter().unwrap().next().unwrap().unwrap();
+    assert_eq!((ballot.rollcall.chamber, ballot.rollcall.rollcall), (Senate, 1));
+    assert_eq!((ballot.party, ballot.state, ballot.vote_cast), (Some(Party::Democrat), Some(State::Wisconsin), Some(VoteCast::Yea)));
+    assert_eq!(ballot.rollcall.vote_date.as_deref(), Some("2024-01-09"));
+    assert_eq!(ballots.iter().unwrap().count(), 1, "each iteration reads from the start");
+
+    // Pages and counts
+    let query = VoteQuery::new().chamber(House).page(Page::new(2, 2));
+    assert_eq!(members(query.clone()), vec!["A000370", "A000371"]);
+    let page = query.ballot_page(&conn).unwrap();
+    assert_eq!((page.total, page.offset, page.limit, page.items.len()), (5, 2, 2, 2));
+    assert_eq!(query.count_ballots(&conn).unwrap(), 5);
+    assert_eq!(VoteQuery::new().vote_cast(VoteCast::Yea).count_rollcalls(&conn).unwrap(), 2);
+
+    // Without a parsed day, dates fall back to the date as published
+    conn.execute("UPDATE votes SET vote_day = NULL, vote_date = '2024-01-09' WHERE chamber = 'senate'", []).unwrap();
+    assert_eq!(rollcalls(VoteQuery::new().to(NaiveDate::from_ymd_opt(2024, 1, 9).unwrap())), vec![(Senate, 1)]);
+}
+
+#[test]
+fn test_migrations() {
+    use crate::migrations::{self, MIGRATIONS};
+    use crate::search::{search, SearchFilter};
+    use crate::sqllite::SCHEMA_SQL;
+    use rusqlite::Connection;
+
+    // Every table, index, view and trigger, with each table's columns
+    let schema = |conn: &Connection| {
+        let mut stmt = conn
+            .prepare(
+                "SELECT m.type, m.name, group_concat(c.name || ' ' || c.type || ' ' || c.\"notnull\" || ' ' || c.pk, ', ')
+                 FROM sqlite_master m LEFT JOIN pragma_table_info(m.name) c ON m.type = 'table'
+                 WHERE m.name NOT LIKE 'sqlite_%' AND m.name NOT LIKE 'vote_search_%'
+                 GROUP BY m.type, m.name ORDER BY m.type, m.name",
+            )
+            .unwrap();
+        stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, Option<String>>(2)?)))
+            .unwrap()
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .unwrap()
+    };
+    let recorded = |conn: &Connection| {
+        let mut stmt = conn.prepare("SELECT version, applied_at IS NOT NULL FROM schema_version ORDER BY version").unwrap();
+        stmt.query_map([], |row| Ok((row.get::<_, i32>(0)?, row.get::<_, bool>(1)?))).unwrap().collect::<rusqlite::Result<Vec<_>>>().unwrap()
+    };
+    let one = |conn: &Connection, sql: &str| conn.query_row(sql, [], |row| row.get::<_, Option<String>>(0)).unwrap();
+
+    // The migrations build a new database up to schema.sql
+    let expected = Connection::open_in_memory().unwrap();
+    expected.execute_batch(SCHEMA_SQL).unwrap();
+    let tables = schema(&expected);
+    assert!(tables.iter().any(|(kind, name, columns)| kind == "table" && name == "votes" && columns.as_deref().unwrap().contains("vote_epoch INTEGER")));
+    assert!(tables.iter().any(|(kind, name, _)| kind == "trigger" && name == "votes_search_delete"));
+
+    let conn = Connection::open_in_memory().unwrap();
+    assert_eq!(migrations::current_version(&conn).unwrap(), 0);
+    assert_eq!(migrations::migrate(&conn).unwrap(), (1..=SCHEMA_VERSION).collect::<Vec<_>>());
+    assert_eq!(schema(&conn), schema(&expected));
+    assert_eq!(recorded(&conn), (1..=SCHEMA_VERSION).map(|v| (v, true)).collect::<Vec<_>>());
+    assert_eq!(MIGRATIONS.iter().map(|m| m.version).collect::<Vec<_>>(), (1..=SCHEMA_VERSION).collect::<Vec<_>>());
+    assert!(migrations::migrate(&conn).unwrap().is_empty());
+
+    // Databases released at each version, which kept it in user_version, upgrade in place
+    for version in 1..=SCHEMA_VERSION {
+        let path = format!("tests/fixtures/schema/v{}.sql", version);
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        conn.pragma_update(None, "user_version", version).unwrap();
+        conn.execute_batch(&std::fs::read_to_string("tests/fixtures/schema/data.sql").unwrap()).unwrap();
+
+        let status = migrations::status(&conn).unwrap();
+        assert_eq!(status.iter().filter(|m| m.applied).count() as i32, version, "{}", path);
+        assert!(status.iter().all(|m| m.applied_at.is_none()));
+        assert_eq!(migrations::current_version(&conn).unwrap(), version);
+
+        initialize_database(&conn).unwrap();
+
+        assert_eq!(schema(&conn), schema(&expected), "{}", path);
+        assert_eq!(recorded(&conn), (1..=SCHEMA_VERSION).map(|v| (v, v > version)).collect::<Vec<_>>(), "{}", path);
+        let user_version: i32 = conn.query_row("PRAGMA user_version", [], |row| row.get(0)).unwrap();
+        assert_eq!(user_version, SCHEMA_VERSION);
+
+        // Rows survive, and each migration the database missed updates them
+        assert_eq!(one(&conn, "SELECT COUNT(*) || ' ' || SUM(vote_cast = 'Yea') FROM member_votes").as_deref(), Some("2 2"), "{}", path);
+        let senator = one(&conn, "SELECT member_id FROM member_votes WHERE vote_hash = 's1'");
+        assert_eq!(senator.as_deref(), Some(if version < 3 { "S354" } else { "0a1b2c" }), "{}", path);
+        let day = one(&conn, "SELECT vote_day FROM votes WHERE vote_hash = 's1'");
+        assert_eq!(day.as_deref(), if version < 6 { Some("2024-01-09") } else { None }, "{}", path);
+        let hits = search(&conn, "cloture", &SearchFilter::default(), 10).unwrap();
+        assert_eq!(hits.len(), if version < 7 { 1 } else { 0 }, "{}", path);
+
+        // Opening it again changes nothing
+        initialize_database(&conn).unwrap();
+        assert_eq!(recorded(&conn).len() as i32, SCHEMA_VERSION);
+    }
+
+    // A database from a newer build is refused
+    conn.execute("INSERT INTO schema_version (version, name) VALUES (?1, 'from the future')", [SCHEMA_VERSION + 1]).unwrap();
+    assert!(migrations::migrate(&conn).is_err());
+    assert!(initialize_database(&conn).is_err());
+    assert!(!migrations::status(&conn).unwrap().iter().any(|m| !m.applied));
+}
@@ -0,0 +1,282 @@
+// workspace.rs
+//
+// Where a dataset lives: the root directory, the data directories for each
+// format, the layout of vote files inside them, and the database and master
+// JSON outputs. Every path the tools read or write comes from here.
+
+use std::path::{Component, Path, PathBuf};
+
+use crate::endpoints::CongressEndpoint;
+use crate::pipeline::VoteFileEntry;
+use crate::xml_reader::VoteFormat;
+use crate::{Error, Result};
+
+/// Layout of vote files below a data directory.
+/// Placeholders are `{congress}`, `{chamber}`, `{session}`, `{year}`, `{roll}` and `{ext}`.
+pub const DEFAULT_LAYOUT: &str = "{congress}/{chamber}/{session}/{year}_{roll}.{ext}";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Workspace {
+    root: PathBuf,
+    json_dir: PathBuf,
+    xml_dir: PathBuf,
+    database: PathBuf,
+    master_json: PathBuf,
+    analytics: PathBuf,
+    layout: String,
+}
+
+impl Default for Workspace {
+    /// The current directory, laid out as in the repository.
+    fn default() -> Self {
+        Workspace::new(".")
+    }
+}
+
+impl Workspace {
+    /// Environment variable holding the workspace root.
+    pub const ROOT_ENV: &'static str = "CONGRESS_ROLLS_ROOT";
+
+    /// A workspace at `root` with the default layout: `data/json`, `data/xml`,
+    /// `full_data/votes.db`, `full_data/votes.json` and `full_data/analytics`.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Workspace {
+            root: root.into(),
+            json_dir: PathBuf::from(VoteFormat::Json.base_path()),
+            xml_dir: PathBuf::from(VoteFormat::Xml.base_path()),
+            database: PathBuf::from("full_data/votes.db"),
+            master_json: PathBuf::from("full_data/votes.json"),
+            analytics: PathBuf::from("full_data/analytics"),
+            layout: DEFAULT_LAYOUT.to_string(),
+        }
+    }
+
+    /// A workspace at `$CONGRESS_ROLLS_ROOT`, or the current directory if it isn't set.
+    pub fn from_env() -> Self {
+        match std::env::var_os(Self::ROOT_ENV) {
+            Some(root) if !root.is_empty() => Workspace::new(root),
+            _ => Workspace::default(),
+        }
+    }
+
+    /// Sets the directory files of `format` are read from and downloaded to.
+    /// Relative paths are resolved against the root.
+    pub fn data_dir_for(mut self, format: VoteFormat, dir: impl Into<PathBuf>) -> Self {
+        match format {
+            VoteFormat::Json => self.json_dir = dir.into(),
+            VoteFormat::Xml => self.xml_dir = dir.into(),
+        }
+        self
+    }
+
+    /// Sets the SQLite database. Relative paths are resolved against the root.
+    pub fn database(mut self, path: impl Into<PathBuf>) -> Self {
+        self.database = path.into();
+        self
+    }
+
+    /// Sets the master JSON file. Relative paths are resolved against the root.
+    pub fn master_json(mut self, path: impl Into<PathBuf>) -> Self {
+        self.master_json = path.into();
+        self
+    }
+
+    /// Sets the directory analytics CSV files are written to. Relative paths are resolved against the root.
+    pub fn analytics(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.analytics = dir.into();
+        self
+    }
+
+    /// Sets the layout of vote files, see `DEFAULT_LAYOUT`. `{chamber}`, `{congress}`,
+    /// `{session}` and `{roll}` must all appear.
+    pub fn layout(mut self, layout: &str) -> Result<Self> {
+        for field in ["{chamber}", "{congress}", "{session}", "{roll}"] {
+            if !layout.contains(field) {
+                return Err(Error::Config {
+                    message: format!("Layout {:?} is missing {}", layout, field),
+                });
+            }
+        }
+
+        self.layout = layout.to_string();
+        Ok(self)
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn data_dir(&self, format: VoteFormat) -> PathBuf {
+        match format {
+            VoteFormat::Json => self.root.join(&self.json_dir),
+            VoteFormat::Xml => self.root.join(&self.xml_dir),
+        }
+    }
+
+    pub fn database_path(&self) -> PathBuf {
+        self.root.join(&self.database)
+    }
+
+    pub fn master_json_path(&self) -> PathBuf {
+        self.root.join(&self.master_json)
+    }
+
+    /// Directory the analytics CSV files are written to.
+    pub fn analytics_dir(&self) -> PathBuf {
+        self.root.join(&self.analytics)
+    }
+
+    /// Download manifest, next to the XML it describes.
+    pub fn manifest_path(&self) -> PathBuf {
+        self.data_dir(VoteFormat::Xml).join("manifest.json")
+    }
+
+    /// Log of roll calls changed by a refresh.
+    pub fn changelog_path(&self) -> PathBuf {
+        self.data_dir(VoteFormat::Xml).join("changelog.log")
+    }
+
+    /// Creates the directory `path` goes in, so outputs can be written to a fresh workspace.
+    pub fn create_parent(path: &Path) -> Result<()> {
+        match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => {
+                std::fs::create_dir_all(parent).map_err(|e| Error::io(parent, e))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Where the roll call at `endpoint` is stored in `format`.
+    pub fn vote_file_path(&self, format: VoteFormat, endpoint: &CongressEndpoint) -> Result<PathBuf> {
+        let relative = self
+            .layout
+            .replace("{congress}", &endpoint.number.to_string())
+            .replace("{chamber}", &endpoint.chamber.to_string())
+            .replace("{session}", &endpoint.session.to_string())
+            .replace("{year}", &endpoint.to_year()?.to_string())
+            .replace("{roll}", &endpoint.roll_call.to_string())
+            .replace("{ext}", format.extension());
+
+        Ok(self.data_dir(format).join(relative))
+    }
+
+    /// Reads chamber, congress, session, year and roll call from a vote file path.
+    ///
+    /// Paths inside a data directory are matched from there; any other path is
+    /// matched on its last components, as many as the layout has.
+    pub fn parse_vote_path(&self, path: &Path) -> Result<VoteFileEntry> {
+        let path_err = |message: &str| Error::Path { path: path.to_path_buf(), message: message.to_string() };
+
+        let relative = [VoteFormat::Json, VoteFormat::Xml]
+            .iter()
+            .find_map(|format| path.strip_prefix(self.data_dir(*format)).ok())
+            .unwrap_or(path);
+
+        let template: Vec<&str> = self.layout.split('/').collect();
+        let components: Vec<&str> = relative
+            .components()
+            .filter_map(|c| match c {
+                Component::Normal(part) => part.to_str(),
+                _ => None,
+            })
+            .collect();
+
+        if components.len() < template.len() {
+            return Err(path_err("Unexpected path format"));
+        }
+
+        let mut fields = Fields::default();
+        for (pattern, text) in template.iter().zip(&components[components.len() - template.len()..]) {
+            if !match_component(pattern, text, &mut fields) {
+                return Err(path_err("Path does not match the workspace layout"));
+            }
+        }
+
+        let number = |value: Option<&str>, message: &str| -> Result<u32> {
+            value.and_then(|v| v.parse().ok()).ok_or_else(|| path_err(message))
+        };
+
+        let chamber = fields.chamber.ok_or_else(|| path_err("Missing chamber in path"))?.to_lowercase();
+        let congress_number = number(fields.congress, "Invalid congress number in path")?;
+        let session_number = number(fields.session, "Invalid session number in path")?;
+        let rollcall_number = number(fields.roll, "Invalid rollcall number in file name")?;
+
+        // Layouts without a year fall back to the year the session convened
+        let year = match fields.year {
+            Some(_) => number(fields.year, "Invalid year in file name")?,
+            None => CongressEndpoint::new(&chamber, congress_number, session_number, rollcall_number)
+                .to_year()
+                .map_err(|_| path_err("Invalid congress and session in path"))?,
+        };
+
+        Ok(VoteFileEntry {
+            chamber,
+            congress_number: u16::try_from(congress_number).map_err(|_| path_err("Invalid congress number in path"))?,
+            session_number: u8::try_from(session_number).map_err(|_| path_err("Invalid session number in path"))?,
+            rollcall_number,
+            year: u16::try_from(year).map_err(|_| path_err("Invalid year in file name"))?,
+            path: path.to_path_buf(),
+        })
+    }
+}
+
+#[derive(Default)]
+struct Fields<'a> {
+    chamber: Option<&'a str>,
+    congress: Option<&'a str>,
+    session: Option<&'a str>,
+    year: Option<&'a str>,
+    roll: Option<&'a str>,
+}
+
+/// Matches one path component against one layout component, e.g. `2024_10.xml`
+/// against `{year}_{roll}.{ext}`. A placeholder takes everything up to the next
+/// literal text of the pattern.
+fn match_component<'a>(pattern: &str, mut text: &'a str, fields: &mut Fields<'a>) -> bool {
+    let mut pattern = pattern;
+
+    while !pattern.is_empty() {
+        if let Some(rest) = pattern.strip_prefix('{') {
+            let Some(end) = rest.find('}') else { return false };
+            let name = &rest[..end];
+            pattern = &rest[end + 1..];
+
+            let literal_end = pattern.find('{').unwrap_or(pattern.len());
+            let literal = &pattern[..literal_end];
+
+            let value_end = if literal.is_empty() {
+                text.len()
+            } else {
+                match text.find(literal) {
+                    Some(i) => i,
+                    None => return false,
+                }
+            };
+
+            let value = &text[..value_end];
+            if value.is_empty() {
+                return false;
+            }
+            text = &text[value_end..];
+
+            match name {
+                "chamber" => fields.chamber = Some(value),
+                "congress" => fields.congress = Some(value),
+                "session" => fields.session = Some(value),
+                "year" => fields.year = Some(value),
+                "roll" => fields.roll = Some(value),
+                _ => {}
+            }
+        } else {
+            let literal_end = pattern.find('{').unwrap_or(pattern.len());
+            let literal = &pattern[..literal_end];
+            match text.strip_prefix(literal) {
+                Some(rest) => text = rest,
+                None => return false,
+            }
+            pattern = &pattern[literal_end..];
+        }
+    }
+
+    text.is_empty()
+}
@@ -0,0 +1,256 @@
+// xml_reader.rs
+//
+// Reads clerk.house.gov (`rollcall-vote`) and senate.gov (`roll_call_vote`) XML
+// straight into `HouseFile`/`SenateFile`, without going through yq.
+//
+// The XML is first turned into the same JSON shape `scripts/xml_to_json.sh`
+// produces (attributes as plain keys, element text next to attributes as
+// `content`, empty elements as null, repeated elements as lists) so the
+// `responses` structs deserialize either source unchanged.
+
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use serde_json::{Map, Value};
+use std::path::Path;
+
+use crate::responses::*;
+use crate::{Error, Result};
+
+/// Elements that are lists in the clerk and LIS schemas. They are always emitted
+/// as lists, even when a file holds a single entry.
+const LIST_ELEMENTS: &[&str] = &["recorded-vote", "totals-by-party", "totals-by-candidate", "member", "document", "amendment"];
+
+/// Format of a roll call file on disk, decided by its extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VoteFormat {
+    Json,
+    Xml,
+}
+
+impl VoteFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension().and_then(|s| s.to_str()).map(|s| s.to_lowercase()) {
+            Some(ext) if ext == "json" => Some(VoteFormat::Json),
+            Some(ext) if ext == "xml" => Some(VoteFormat::Xml),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            VoteFormat::Json => "json",
+            VoteFormat::Xml => "xml",
+        }
+    }
+
+    /// Guesses the format of a document from its first character, for input without a file name.
+    pub fn detect(data: &str) -> Self {
+        match data.trim_start_matches('\u{feff}').trim_start().starts_with('<') {
+            true => VoteFormat::Xml,
+            false => VoteFormat::Json,
+        }
+    }
+
+    /// Default directory the format is stored in, relative to the project root.
+    pub fn base_path(&self) -> &'static str {
+        match self {
+            VoteFormat::Json => "data/json",
+            VoteFormat::Xml => "data/xml",
+        }
+    }
+}
+
+/// One element being built while walking the document.
+struct Frame {
+    name: String,
+    map: Map<String, Value>,
+    text: String,
+}
+
+impl Frame {
+    fn new(start: &BytesStart) -> Result<Self> {
+        let name = String::from_utf8_lossy(start.name().as_ref()).to_string();
+        let mut map = Map::new();
+
+        for attr in start.attributes() {
+            let attr = attr.map_err(|e| Error::schema(format!("Invalid attribute on <{}>: {}", name, e)))?;
+            let key = String::from_utf8_lossy(attr.key.as_ref()).to_string();
+            let value = match attr.unescape_value() {
+                Ok(v) => v.to_string(),
+                Err(_) => String::from_utf8_lossy(&attr.value).to_string(),
+            };
+            map.insert(key, Value::String(value));
+        }
+
+        Ok(Frame { name, map, text: String::new() })
+    }
+
+    fn into_value(self) -> (String, Value) {
+        let text = self.text.trim().to_string();
+
+        let value = if self.map.is_empty() {
+            if text.is_empty() {
+                Value::Null
+            } else {
+                Value::String(text)
+            }
+        } else {
+            let mut map = self.map;
+            if !text.is_empty() {
+                map.insert("content".to_string(), Value::String(text));
+            }
+            Value::Object(map)
+        };
+
+        (self.name, value)
+    }
+}
+
+/// Adds a finished child element to its parent, turning repeated elements into lists.
+fn attach(parent: &mut Map<String, Value>, name: String, value: Value) {
+    if LIST_ELEMENTS.contains(&name.as_str()) {
+        match parent.entry(name).or_insert_with(|| Value::Array(vec![])) {
+            Value::Array(list) => list.push(value),
+            other => *other = Value::Array(vec![other.take(), value]),
+        }
+        return;
+    }
+
+    match parent.get_mut(&name) {
+        Some(Value::Array(list)) => list.push(value),
+        Some(existing) => *existing = Value::Array(vec![existing.take(), value]),
+        None => {
+            parent.insert(name, value);
+        }
+    }
+}
+
+/// Converts an XML document into the JSON value yq would produce for it.
+pub fn xml_to_value(xml: &str) -> Result<Value> {
+    let mut reader = Reader::from_str(xml);
+
+    let mut stack: Vec<Frame> = vec![];
+    let mut root = Map::new();
+
+    loop {
+        let event = reader
+            .read_event()
+            .map_err(|e| Error::schema(format!("XML error at position {}: {}", reader.buffer_position(), e)))?;
+
+        match event {
+            Event::Start(start) => stack.push(Frame::new(&start)?),
+            Event::Empty(start) => {
+                let (name, value) = Frame::new(&start)?.into_value();
+                match stack.last_mut() {
+                    Some(parent) => attach(&mut parent.map, name, value),
+                    None => attach(&mut root, name, value),
+                }
+            }
+            Event::End(_) => {
+                let frame = stack.pop().ok_or_else(|| Error::schema("Unbalanced closing tag"))?;
+                let (name, value) = frame.into_value();
+                match stack.last_mut() {
+                    Some(parent) => attach(&mut parent.map, name, value),
+                    None => attach(&mut root, name, value),
+                }
+            }
+            Event::Text(text) => {
+                if let Some(frame) = stack.last_mut() {
+                    match text.unescape() {
+                        Ok(t) => frame.text.push_str(&t),
+                        Err(_) => frame.text.push_str(&String::from_utf8_lossy(&text)),
+                    }
+                }
+            }
+            Event::CData(data) => {
+                if let Some(frame) = stack.last_mut() {
+                    frame.text.push_str(&String::from_utf8_lossy(&data));
+                }
+            }
+            Event::Eof => break,
+            // declarations, doctypes, processing instructions and comments carry no vote data
+            _ => {}
+        }
+    }
+
+    if !stack.is_empty() {
+        return Err(Error::schema(format!("Unclosed element <{}>", stack[stack.len() - 1].name)));
+    }
+
+    Ok(Value::Object(root))
+}
+
+/// Parses a clerk.house.gov `rollcall-vote` document.
+pub fn parse_house_xml(xml: &str) -> Result<HouseFile> {
+    let value = xml_to_value(xml)?;
+    serde_json::from_value(value).map_err(|e| Error::schema(format!("Invalid House roll call: {}", e)))
+}
+
+/// Parses a senate.gov `roll_call_vote` document.
+pub fn parse_senate_xml(xml: &str) -> Result<SenateFile> {
+    let value = xml_to_value(xml)?;
+    serde_json::from_value(value).map_err(|e| Error::schema(format!("Invalid Senate roll call: {}", e)))
+}
+
+/// Parses either chamber's document, deciding on the root element.
+pub fn parse_vote_file_xml(xml: &str) -> Result<VoteFile> {
+    vote_file_from_value(xml_to_value(xml)?)
+}
+
+/// Parses either chamber's document in either format.
+pub fn parse_vote_document(data: &str, format: VoteFormat) -> Result<VoteFile> {
+    match format {
+        VoteFormat::Xml => parse_vote_file_xml(data),
+        VoteFormat::Json => {
+            let value = serde_json::from_str(data).map_err(|e| Error::schema(format!("Invalid roll call JSON: {}", e)))?;
+            vote_file_from_value(value)
+        }
+    }
+}
+
+fn vote_file_from_value(value: Value) -> Result<VoteFile> {
+    if value.get("rollcall-vote").is_some() {
+        serde_json::from_value(value)
+            .map(VoteFile::HouseFile)
+            .map_err(|e| Error::schema(format!("Invalid House roll call: {}", e)))
+    } else if value.get("roll_call_vote").is_some() {
+        serde_json::from_value(value)
+            .map(VoteFile::SenateFile)
+            .map_err(|e| Error::schema(format!("Invalid Senate roll call: {}", e)))
+    } else {
+        Err(Error::schema("Unknown roll call document: expected <rollcall-vote> or <roll_call_vote>"))
+    }
+}
+
+fn read_file(path: &Path) -> Result<String> {
+    let bytes = std::fs::read(path).map_err(|e| Error::io(path, e))?;
+    Ok(String::from_utf8_lossy(&bytes).to_string())
+}
+
+/// Loads a roll call of either chamber, in the format its extension names
+/// (or, without a known extension, the format its content looks like).
+pub fn load_vote_file(path: &Path) -> Result<VoteFile> {
+    let data = read_file(path)?;
+    let format = VoteFormat::from_path(path).unwrap_or_else(|| VoteFormat::detect(&data));
+    parse_vote_document(&data, format).map_err(|e| e.with_path(path))
+}
+
+/// Loads a House roll call from a `.json` (yq output) or `.xml` (clerk) file.
+pub fn load_house_file(path: &Path) -> Result<HouseFile> {
+    let data = read_file(path)?;
+    let parsed = match VoteFormat::from_path(path) {
+        Some(VoteFormat::Xml) => parse_house_xml(&data),
+        _ => serde_json::from_str(&data).map_err(|e| Error::schema(format!("Invalid House roll call: {}", e))),
+    };
+    parsed.map_err(|e| e.with_path(path))
+}
+
+/// Loads a Senate roll call from a `.json` (yq output) or `.xml` (senate.gov) file.
+pub fn load_senate_file(path: &Path) -> Result<SenateFile> {
+    let data = read_file(path)?;
+    let parsed = match VoteFormat::from_path(path) {
+        Some(VoteFormat::Xml) => parse_senate_xml(&data),
+        _ => serde_json::from_str(&data).map_err(|e| Error::schema(format!("Invalid Senate roll call: {}", e))),
+    };
+    parsed.map_err(|e| e.with_path(path))
+}
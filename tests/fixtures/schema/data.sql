@@ -0,0 +1,21 @@
+-- data.sql: rows valid at every schema version, loaded into the legacy
+-- databases before they are upgraded. The senator is keyed on a hash, as
+-- before version 3.
+
+INSERT INTO votes (vote_hash, chamber, congress, session, rollcall, year, vote_date, question, result, legis_num, action_time, action_time_etz, vote_desc)
+VALUES ('h10', 'house', 118, 2, 10, 2024, '17-Jan-2024', 'On Passage', 'Passed', 'H R 788', '2:36 PM', '14:36', 'Agent Raul Gonzalez Officer Safety Act');
+
+INSERT INTO votes (vote_hash, chamber, congress, session, rollcall, year, vote_date, modify_date, question, question_text, result, title)
+VALUES ('s1', 'senate', 118, 2, 1, 2024, 'January 9, 2024, 05:31 PM', 'January 9, 2024, 06:02 PM', 'On the Cloture Motion',
+        'On the Cloture Motion PN1042', 'Cloture Motion Agreed to', 'Motion to Invoke Cloture: Jeffrey Irvine Cummings');
+
+INSERT INTO vote_counts (vote_hash, yea, nay, present, not_voting) VALUES ('h10', 3, 1, 0, 1), ('s1', 2, 1, 0, 1);
+INSERT INTO documents (vote_hash, position, document_name, document_title) VALUES ('s1', 0, 'PN1042', 'Jeffrey Irvine Cummings, of Illinois');
+
+INSERT INTO members (member_id, chamber, bioguide_id, lis_member_id, full_name, last_name, party, state) VALUES
+    ('A000370', 'house', 'A000370', NULL, 'Adams', 'Adams', 'D', 'NC'),
+    ('0a1b2c', 'senate', NULL, 'S354', 'Baldwin (D-WI)', 'Baldwin', 'D', 'WI');
+
+INSERT INTO member_votes (vote_hash, member_id, vote_cast, party, state) VALUES
+    ('h10', 'A000370', 'Yea', 'D', 'NC'),
+    ('s1', '0a1b2c', 'Yea', 'D', 'WI');
@@ -0,0 +1,241 @@
+-- v5.sql: full_data/schema.sql as it was at schema version 5, before the
+-- schema_version table. Builds the legacy databases the migration tests upgrade.
+--
+-- SQLite schema for votes.db, applied by `sqllite::initialize_database`.
+-- Every statement is idempotent so the schema can be re-applied to an
+-- existing database. Rows hang off `votes.vote_hash`, a SHA-256 of the
+-- roll call document, and are removed together with their vote.
+
+PRAGMA foreign_keys = ON;
+
+-- One row per roll call, both chambers.
+-- Senate-only and House-only columns are left NULL for the other chamber.
+CREATE TABLE IF NOT EXISTS votes (
+    vote_hash            TEXT PRIMARY KEY NOT NULL,
+    chamber              TEXT NOT NULL CHECK (chamber IN ('house', 'senate')),
+    congress             INTEGER NOT NULL,
+    session              INTEGER NOT NULL,
+    rollcall             INTEGER NOT NULL,
+    year                 INTEGER NOT NULL,
+    vote_date            TEXT,
+    modify_date          TEXT,
+    question             TEXT,
+    question_text        TEXT,
+    document_text        TEXT,
+    result               TEXT,
+    title                TEXT,
+    majority_requirement TEXT,
+    majority             TEXT,
+    committee            TEXT,
+    legis_num            TEXT,
+    vote_type            TEXT,
+    action_time          TEXT,
+    action_time_etz      TEXT,
+    vote_desc            TEXT,
+    UNIQUE (chamber, congress, session, rollcall)
+);
+
+CREATE INDEX IF NOT EXISTS idx_votes_congress ON votes (chamber, congress, session);
+CREATE INDEX IF NOT EXISTS idx_votes_year ON votes (year);
+
+-- Chamber-wide totals. Senate "absent" is stored as `not_voting`.
+CREATE TABLE IF NOT EXISTS vote_counts (
+    vote_hash          TEXT PRIMARY KEY NOT NULL REFERENCES votes (vote_hash) ON DELETE CASCADE,
+    yea                INTEGER NOT NULL DEFAULT 0,
+    nay                INTEGER NOT NULL DEFAULT 0,
+    present            INTEGER NOT NULL DEFAULT 0,
+    not_voting         INTEGER NOT NULL DEFAULT 0,
+    tie_breaker_by     TEXT,
+    tie_breaker_vote   TEXT
+);
+
+-- Per-party totals (House `totals-by-party`).
+CREATE TABLE IF NOT EXISTS party_totals (
+    vote_hash  TEXT NOT NULL REFERENCES votes (vote_hash) ON DELETE CASCADE,
+    party      TEXT NOT NULL,
+    yea        INTEGER NOT NULL DEFAULT 0,
+    nay        INTEGER NOT NULL DEFAULT 0,
+    present    INTEGER NOT NULL DEFAULT 0,
+    not_voting INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (vote_hash, party)
+);
+
+-- Documents a Senate vote was taken on, in file order.
+CREATE TABLE IF NOT EXISTS documents (
+    vote_hash            TEXT NOT NULL REFERENCES votes (vote_hash) ON DELETE CASCADE,
+    position             INTEGER NOT NULL,
+    document_congress    TEXT,
+    document_type        TEXT,
+    document_number      TEXT,
+    document_name        TEXT,
+    document_title       TEXT,
+    document_short_title TEXT,
+    PRIMARY KEY (vote_hash, position)
+);
+
+CREATE INDEX IF NOT EXISTS idx_documents_name ON documents (document_name);
+
+-- Amendments a Senate vote was taken on, in file order.
+CREATE TABLE IF NOT EXISTS amendments (
+    vote_hash                                   TEXT NOT NULL REFERENCES votes (vote_hash) ON DELETE CASCADE,
+    position                                    INTEGER NOT NULL,
+    amendment_number                            TEXT,
+    amendment_to_amendment_number               TEXT,
+    amendment_to_amendment_to_amendment_number  TEXT,
+    amendment_to_document_number                TEXT,
+    amendment_to_document_short_title           TEXT,
+    amendment_purpose                           TEXT,
+    PRIMARY KEY (vote_hash, position)
+);
+
+CREATE INDEX IF NOT EXISTS idx_amendments_number ON amendments (amendment_number);
+
+-- Legislators seen on any ballot. `member_id` is the House bioguide
+-- `name-id` or the Senate `lis_member_id`. Ballots without either fall
+-- back to a hash of last name, party and state.
+CREATE TABLE IF NOT EXISTS members (
+    member_id     TEXT PRIMARY KEY NOT NULL,
+    chamber       TEXT NOT NULL CHECK (chamber IN ('house', 'senate')),
+    bioguide_id   TEXT,
+    lis_member_id TEXT,
+    full_name     TEXT,
+    first_name    TEXT,
+    last_name     TEXT,
+    party         TEXT,
+    state         TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_members_bioguide ON members (bioguide_id);
+CREATE INDEX IF NOT EXISTS idx_members_lis ON members (lis_member_id);
+
+-- One ballot per member per roll call. Party and state are recorded as
+-- they were on the day of the vote.
+CREATE TABLE IF NOT EXISTS member_votes (
+    vote_hash   TEXT NOT NULL REFERENCES votes (vote_hash) ON DELETE CASCADE,
+    member_id   TEXT NOT NULL REFERENCES members (member_id),
+    vote_cast   TEXT,
+    paired_with TEXT,
+    party       TEXT,
+    state       TEXT,
+    PRIMARY KEY (vote_hash, member_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_member_votes_member ON member_votes (member_id);
+
+-- Votes per candidate (House Speaker elections, `totals-by-candidate`).
+CREATE TABLE IF NOT EXISTS candidate_totals (
+    vote_hash TEXT NOT NULL REFERENCES votes (vote_hash) ON DELETE CASCADE,
+    candidate TEXT NOT NULL,
+    total     INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (vote_hash, candidate)
+);
+
+-- Legislator registry, imported from a congress-legislators crosswalk.
+-- One row per person; `bioguide_id` matches House members and `lis_id`
+-- matches Senate members, so both careers link to the same row.
+CREATE TABLE IF NOT EXISTS legislators (
+    bioguide_id   TEXT PRIMARY KEY NOT NULL,
+    lis_id        TEXT UNIQUE,
+    thomas_id     TEXT,
+    govtrack_id   INTEGER,
+    icpsr_id      INTEGER,
+    first_name    TEXT,
+    last_name     TEXT,
+    official_full TEXT,
+    birthday      TEXT,
+    gender        TEXT
+);
+
+-- Terms in office, in crosswalk order.
+CREATE TABLE IF NOT EXISTS legislator_terms (
+    bioguide_id TEXT NOT NULL REFERENCES legislators (bioguide_id) ON DELETE CASCADE,
+    position    INTEGER NOT NULL,
+    chamber     TEXT NOT NULL CHECK (chamber IN ('house', 'senate')),
+    start_date  TEXT NOT NULL,
+    end_date    TEXT NOT NULL,
+    state       TEXT NOT NULL,
+    district    INTEGER,
+    party       TEXT,
+    PRIMARY KEY (bioguide_id, position)
+);
+
+-- The registry entry of each member, by bioguide id in the House and LIS id in the Senate.
+CREATE VIEW IF NOT EXISTS member_legislators AS
+SELECT m.member_id, m.chamber, l.bioguide_id
+FROM members m
+JOIN legislators l
+  ON (m.chamber = 'house' AND l.bioguide_id = m.bioguide_id)
+  OR (m.chamber = 'senate' AND l.lis_id = m.lis_member_id);
+
+-- Every ballot of a legislator, across both chambers.
+CREATE VIEW IF NOT EXISTS legislator_votes AS
+SELECT ml.bioguide_id, v.chamber, v.congress, v.session, v.rollcall, v.vote_date,
+       mv.vote_hash, mv.member_id, mv.vote_cast, mv.party, mv.state
+FROM member_votes mv
+JOIN member_legislators ml ON ml.member_id = mv.member_id
+JOIN votes v ON v.vote_hash = mv.vote_hash;
+
+-- Party-unity votes: roll calls on which a majority of voting Democrats
+-- opposed a majority of voting Republicans. Positions are +1 yea, -1 nay.
+-- The analytics tables are rebuilt per chamber and congress by `analyze`.
+CREATE TABLE IF NOT EXISTS party_unity_votes (
+    chamber             TEXT NOT NULL CHECK (chamber IN ('house', 'senate')),
+    congress            INTEGER NOT NULL,
+    session             INTEGER NOT NULL,
+    rollcall            INTEGER NOT NULL,
+    democrat_yea        INTEGER NOT NULL,
+    democrat_nay        INTEGER NOT NULL,
+    republican_yea      INTEGER NOT NULL,
+    republican_nay      INTEGER NOT NULL,
+    democrat_position   INTEGER NOT NULL,
+    republican_position INTEGER NOT NULL,
+    PRIMARY KEY (chamber, congress, session, rollcall)
+);
+
+-- Party-unity scores per member and session; session 0 is the whole congress.
+CREATE TABLE IF NOT EXISTS party_unity_scores (
+    chamber       TEXT NOT NULL CHECK (chamber IN ('house', 'senate')),
+    congress      INTEGER NOT NULL,
+    session       INTEGER NOT NULL,
+    member_id     TEXT NOT NULL,
+    party         TEXT NOT NULL,
+    name          TEXT,
+    with_party    INTEGER NOT NULL,
+    against_party INTEGER NOT NULL,
+    score         REAL NOT NULL,
+    PRIMARY KEY (chamber, congress, session, member_id, party)
+);
+
+-- Ballots cast against the member's party majority on a party-unity vote.
+CREATE TABLE IF NOT EXISTS party_defections (
+    chamber        TEXT NOT NULL CHECK (chamber IN ('house', 'senate')),
+    congress       INTEGER NOT NULL,
+    session        INTEGER NOT NULL,
+    rollcall       INTEGER NOT NULL,
+    member_id      TEXT NOT NULL,
+    party          TEXT NOT NULL,
+    vote_cast      TEXT NOT NULL,
+    party_position INTEGER NOT NULL,
+    PRIMARY KEY (chamber, congress, session, rollcall, member_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_party_defections_member ON party_defections (member_id);
+
+-- Ideal points per member and congress, in one or two dimensions. coord2 and se2
+-- are NULL for one-dimensional fits; classified is the share of votes predicted correctly.
+CREATE TABLE IF NOT EXISTS member_ideal_points (
+    chamber    TEXT NOT NULL CHECK (chamber IN ('house', 'senate')),
+    congress   INTEGER NOT NULL,
+    member_id  TEXT NOT NULL,
+    dimensions INTEGER NOT NULL CHECK (dimensions IN (1, 2)),
+    name       TEXT,
+    party      TEXT,
+    state      TEXT,
+    votes      INTEGER NOT NULL,
+    coord1     REAL NOT NULL,
+    coord2     REAL,
+    se1        REAL NOT NULL,
+    se2        REAL,
+    classified REAL NOT NULL,
+    PRIMARY KEY (chamber, congress, member_id, dimensions)
+);